@@ -0,0 +1,61 @@
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    log: PathBuf,
+    allowed: PathBuf,
+    denied: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup files with and without an exception.
+    let allowed = tempdir.join("allowed");
+    let denied = tempdir.join("denied");
+    fs::write(&allowed, "allowed").unwrap();
+    fs::write(&denied, "denied").unwrap();
+
+    let log = tempdir.join("audit.log");
+    let log_file = File::create(&log).unwrap();
+
+    // Setup our test sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Read(allowed.clone())).unwrap();
+    sandbox.set_audit(log_file);
+
+    // Serialize test data.
+    let data = TestData { log, allowed, denied };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Access outside of the exceptions is not blocked.
+    assert_eq!(fs::read_to_string(&data.allowed).unwrap(), "allowed");
+    assert_eq!(fs::read_to_string(&data.denied).unwrap(), "denied");
+    assert_eq!(fs::read_to_string(&data.denied).unwrap(), "denied");
+    fs::write(&data.allowed, "written").unwrap();
+
+    // Denied syscalls are allowed.
+    let result = unsafe { libc::syscall(libc::SYS_vhangup) };
+    let errno = std::io::Error::last_os_error().raw_os_error();
+    assert!(result == 0 || errno != Some(libc::EACCES));
+
+    // Only violations are reported, without duplicates.
+    let log = fs::read_to_string(&data.log).unwrap();
+    let lines: Vec<_> = log.lines().collect();
+    assert!(lines.contains(&format!("read {:?}", data.denied).as_str()));
+    assert!(lines.contains(&format!("write {:?}", data.allowed).as_str()));
+    assert!(!lines.contains(&format!("read {:?}", data.allowed).as_str()));
+    let denied_reads = lines.iter().filter(|line| line.ends_with(&format!("{:?}", data.denied)));
+    assert_eq!(denied_reads.count(), 1);
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox, SandboxBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    public_file: PathBuf,
+    private_file: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let public_file = tempdir.join("public");
+    let private_file = tempdir.join("private");
+    fs::write(&public_file, "public").unwrap();
+    fs::write(&private_file, "private").unwrap();
+
+    // Invalid exceptions are rejected immediately.
+    let result = SandboxBuilder::new().exception(Exception::Read(tempdir.join("missing")));
+    assert!(result.is_err());
+
+    // Setup sandbox from an iterator of exceptions.
+    let paths = [public_file.clone()];
+    let sandbox = Birdcage::builder()
+        .exceptions(paths.into_iter().map(Exception::Read))
+        .unwrap()
+        .exception(Exception::Environment("PATH".into()))
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(sandbox.exceptions().len(), 2);
+
+    // Conversion skips the final validation.
+    let builder = SandboxBuilder::new().exception(Exception::Networking).unwrap();
+    let converted = Birdcage::from(builder);
+    assert_eq!(converted.exceptions().len(), 1);
+
+    // Serialize test data.
+    let data = TestData { public_file, private_file };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Only the allowed file is accessible.
+    assert_eq!(fs::read_to_string(data.public_file).unwrap(), "public");
+    assert!(fs::read_to_string(data.private_file).is_err());
+}
@@ -0,0 +1,36 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, CapabilitySet, Exception, IdMap, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    let euid = unsafe { libc::geteuid() };
+    let egid = unsafe { libc::getegid() };
+
+    // Map the caller to root, which keeps its capabilities after `execve`.
+    let uid_map = vec![IdMap { inside_id: 0, outside_id: euid, count: 1 }];
+    let gid_map = vec![IdMap { inside_id: 0, outside_id: egid, count: 1 }];
+    let mut sandbox = Birdcage::new();
+    sandbox.with_user_namespace(uid_map, gid_map);
+    sandbox.add_exception(Exception::DropCapabilities(CapabilitySet::SYS_TIME)).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Only the dropped capability is missing.
+    let status = fs::read_to_string("/proc/self/status").unwrap();
+    for set in ["CapEff", "CapPrm", "CapBnd"] {
+        let line = status.lines().find(|line| line.starts_with(set)).unwrap();
+        let caps = u64::from_str_radix(line.split_whitespace().nth(1).unwrap(), 16).unwrap();
+        assert_eq!(caps & (1 << 25), 0, "{set} contains CAP_SYS_TIME");
+        assert_ne!(caps & 1, 0, "{set} is missing CAP_CHOWN");
+    }
+
+    // Changing the system time is denied.
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    unsafe { libc::clock_gettime(libc::CLOCK_REALTIME, &mut now) };
+    assert_ne!(unsafe { libc::clock_settime(libc::CLOCK_REALTIME, &now) }, 0);
+}
@@ -1,8 +1,21 @@
-use std::io::Write;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::os::unix::process::ExitStatusExt;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{panic, ptr, slice};
 
+use birdcage::error::Error;
+#[cfg(target_os = "linux")]
+use birdcage::process::Signal;
 use birdcage::process::{Command, Stdio};
-use birdcage::{Birdcage, Exception, Sandbox};
+use birdcage::{Birdcage, Exception, Sandbox, SandboxWarning};
+#[cfg(target_os = "linux")]
+use birdcage::{IdMap, NetworkNamespace, ProcAccess, SandboxMode};
 
 // macOs uses `std::process` and thus does not require explicit testing. This
 // allows running multiple tests in the same process rather than having to add
@@ -13,7 +26,31 @@ fn main() {}
 #[cfg(target_os = "linux")]
 fn main() {
     pipe_stdin_to_stdout();
+    file_stdio();
+    current_dir();
     exit_signal();
+    wait_timeout();
+    command_env();
+    capture_output();
+    temp_dir();
+    cgroup_memory_limit();
+    clone_config();
+    kill_on_drop();
+    deny_subprocesses();
+    drop_privileges();
+    network_namespace();
+    proc_access();
+    audit_channel();
+    signal();
+    fd_mapping();
+    timeout();
+    inherited_fds();
+    pid_namespace();
+    pre_exec();
+    command_ids();
+    sandbox_output();
+    core_dumps();
+    shared_memory();
 }
 
 #[cfg(target_os = "linux")]
@@ -38,6 +75,72 @@ fn pipe_stdin_to_stdout() {
     assert_eq!(&output.stdout, expected);
 }
 
+#[cfg(target_os = "linux")]
+fn file_stdio() {
+    // Setup input and output files.
+    let tempdir = tempfile::tempdir().unwrap();
+    let input_path = tempdir.path().join("input");
+    let output_path = tempdir.path().join("output");
+    fs::write(&input_path, "test\n").unwrap();
+
+    // Setup echo-back command.
+    let mut cmd = Command::new("cat");
+    cmd.stdin(File::open(&input_path).unwrap());
+    cmd.stdout(File::create(&output_path).unwrap());
+
+    // Spawn sandbox child.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let mut child = sandbox.spawn(cmd).unwrap();
+    assert!(child.stdin.is_none());
+    assert!(child.stdout.is_none());
+
+    // Output is written to the file.
+    assert!(child.wait().unwrap().success());
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), "test\n");
+}
+
+#[cfg(target_os = "linux")]
+fn current_dir() {
+    // Setup a file in the working directory.
+    let tempdir = tempfile::tempdir().unwrap();
+    fs::write(tempdir.path().join("file"), "test\n").unwrap();
+
+    // Setup command reading relative to the working directory.
+    let mut cmd = Command::new("cat");
+    cmd.arg("./file");
+    cmd.current_dir(tempdir.path());
+    cmd.stdout(Stdio::piped());
+
+    // Spawn sandbox child.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let child = sandbox.spawn(cmd).unwrap();
+
+    // Read stdout.
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(&output.stdout, b"test\n");
+
+    // Working directory is reported by the sandboxee.
+    let mut cmd = Command::new("pwd");
+    cmd.current_dir(tempdir.path());
+    cmd.stdout(Stdio::piped());
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let output = sandbox.spawn(cmd).unwrap().wait_with_output().unwrap();
+    let expected = format!("{}\n", tempdir.path().canonicalize().unwrap().display());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+    // Inaccessible working directories are rejected before spawning.
+    let mut cmd = Command::new("pwd");
+    cmd.current_dir(tempdir.path());
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/usr".into())).unwrap();
+    let result = sandbox.spawn(cmd);
+    assert!(matches!(result, Err(Error::CurrentDirNotAccessible(_))));
+}
+
 #[cfg(target_os = "linux")]
 fn exit_signal() {
     // Setup echo-back command.
@@ -56,3 +159,640 @@ fn exit_signal() {
     let status = child.wait().unwrap();
     assert_eq!(status.signal(), Some(9));
 }
+
+#[cfg(target_os = "linux")]
+fn wait_timeout() {
+    // Setup long-running command.
+    let mut cmd = Command::new("sleep");
+    cmd.arg("10");
+
+    // Spawn sandbox child.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let mut child = sandbox.spawn(cmd).unwrap();
+
+    // Child is still running after the timeout.
+    let status = child.wait_timeout(Duration::from_millis(100)).unwrap();
+    assert!(status.is_none());
+
+    // Kill the child.
+    child.kill().unwrap();
+
+    // Child exits with the kill signal.
+    let status = child.wait_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(status.and_then(|status| status.signal()), Some(9));
+}
+
+#[cfg(target_os = "linux")]
+fn command_env() {
+    // Setup command printing its environment.
+    let mut cmd = Command::new("/usr/bin/env");
+    cmd.env("FOO", "bar");
+    cmd.env_remove("REMOVED");
+    cmd.stdout(Stdio::piped());
+
+    // Spawn sandbox child with a custom environment.
+    let custom_env = HashMap::from([
+        ("CUSTOM".to_string(), "value".to_string()),
+        ("REMOVED".to_string(), "value".to_string()),
+    ]);
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    sandbox.add_exception(Exception::CustomEnvironment(custom_env)).unwrap();
+    let child = sandbox.spawn(cmd).unwrap();
+
+    // Command variables are layered on top of the custom environment.
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let mut env: Vec<_> = output.stdout.split(|b| *b == b'\n').filter(|l| !l.is_empty()).collect();
+    env.sort_unstable();
+    assert_eq!(env, [&b"CUSTOM=value"[..], b"FOO=bar"]);
+}
+
+#[cfg(target_os = "linux")]
+fn capture_output() {
+    // Setup command writing more than a pipe buffer to stderr.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg("echo hello; head -c 262144 /dev/zero >&2");
+    cmd.capture_output();
+
+    // Spawn sandbox child.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let child = sandbox.spawn(cmd).unwrap();
+
+    // Both streams are collected completely.
+    let output = child.output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"hello\n");
+    assert_eq!(output.stderr.len(), 262144);
+}
+
+#[cfg(target_os = "linux")]
+fn temp_dir() {
+    // Setup sandbox with a temporary directory.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let tempdir = sandbox.add_temp_dir().unwrap();
+
+    // Spawn sandbox child writing to the directory.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg(format!("echo data > {}/file", tempdir.display()));
+    let mut child = sandbox.spawn(cmd).unwrap();
+
+    // Directory is removed after waiting on the child.
+    assert!(tempdir.exists());
+    assert!(child.wait().unwrap().success());
+    assert!(!tempdir.exists());
+
+    // Directory is removed when the child is dropped.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let tempdir = sandbox.add_temp_dir().unwrap();
+    let child = sandbox.spawn(Command::new("/bin/true")).unwrap();
+    assert!(tempdir.exists());
+    drop(child);
+    assert!(!tempdir.exists());
+}
+
+#[cfg(target_os = "linux")]
+fn cgroup_memory_limit() {
+    // Setup sandbox with a 32MiB memory limit.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    sandbox.set_cgroup_memory_limit(32 * 1024 * 1024);
+
+    // Limits are skipped outside the host's root cgroup.
+    let warnings = sandbox.validate().unwrap();
+    if warnings.contains(&SandboxWarning::CgroupUnavailable) {
+        let child = sandbox.spawn(Command::new("/bin/true")).unwrap();
+        assert!(child.wait_with_output().unwrap().status.success());
+        return;
+    }
+
+    // Spawn sandbox child buffering an endless stream of zeroes.
+    let mut cmd = Command::new("tail");
+    cmd.args(["-c", "268435456"]);
+    cmd.stdin(File::open("/dev/zero").unwrap());
+    cmd.stdout(Stdio::null());
+    let mut child = sandbox.spawn(cmd).unwrap();
+
+    // Sandboxee is killed once it exceeds the limit.
+    let status = match child.wait_timeout(Duration::from_secs(30)).unwrap() {
+        Some(status) => status,
+        None => {
+            child.kill().unwrap();
+            panic!("memory limit was not enforced");
+        },
+    };
+    assert_eq!(status.signal(), Some(9));
+}
+
+#[cfg(target_os = "linux")]
+fn clone_config() {
+    // Setup a writable and a read-only directory.
+    let tempdir = tempfile::tempdir().unwrap();
+    let writable = tempdir.path().join("writable");
+    let readonly = tempdir.path().join("readonly");
+    fs::create_dir(&writable).unwrap();
+    fs::create_dir(&readonly).unwrap();
+
+    // Setup sandbox with write access to one directory.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    sandbox.add_exception(Exception::WriteAndRead(writable.clone())).unwrap();
+
+    // Spawn commands writing to each directory.
+    let write_cmd = |dir: &std::path::Path| {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg(format!("echo data > {}/file", dir.display()));
+        cmd.stderr(Stdio::null());
+        cmd
+    };
+    let writable_child = sandbox.clone_config().spawn(write_cmd(&writable)).unwrap();
+    let readonly_child = sandbox.clone_config().spawn(write_cmd(&readonly)).unwrap();
+
+    // Both copies enforce the same policy.
+    assert!(writable_child.wait_with_output().unwrap().status.success());
+    assert!(!readonly_child.wait_with_output().unwrap().status.success());
+    assert!(writable.join("file").exists());
+    assert!(!readonly.join("file").exists());
+
+    // Original sandbox is still usable.
+    fs::remove_file(writable.join("file")).unwrap();
+    let child = sandbox.spawn(write_cmd(&writable)).unwrap();
+    assert!(child.wait_with_output().unwrap().status.success());
+    assert!(writable.join("file").exists());
+}
+
+#[cfg(target_os = "linux")]
+fn kill_on_drop() {
+    // Spawn long-running sandbox child.
+    let mut cmd = Command::new("sleep");
+    cmd.arg("10").kill_on_drop(true);
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let child = sandbox.spawn(cmd).unwrap();
+    assert!(child.kill_on_drop());
+    let pid = child.id() as i32;
+
+    // Drop the child while unwinding.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(move || {
+        let _child = child;
+        panic!("early exit");
+    });
+    panic::set_hook(hook);
+    assert!(result.is_err());
+
+    // Child was killed and reaped.
+    let exists = unsafe { libc::kill(pid, 0) } == 0;
+    assert!(!exists);
+}
+
+#[cfg(target_os = "linux")]
+fn deny_subprocesses() {
+    // Setup sandbox denying subprocesses.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    sandbox.allow_subprocesses(false);
+
+    // Spawn shell script which needs to fork.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg("/bin/true; echo forked");
+    cmd.capture_output();
+    let output = sandbox.spawn(cmd).unwrap().output().unwrap();
+
+    // Script is aborted after failing to fork.
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[cfg(target_os = "linux")]
+fn drop_privileges() {
+    const NOBODY: u32 = 65534;
+
+    // Dropping privileges requires root.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    sandbox.drop_privileges(NOBODY, NOBODY);
+    if unsafe { libc::geteuid() } != 0 {
+        let result = sandbox.spawn(Command::new("/bin/true"));
+        assert!(matches!(result, Err(Error::ActivationFailed(_))));
+        return;
+    }
+
+    // Setup directory writable by the unprivileged user.
+    let tempdir = tempfile::tempdir().unwrap();
+    fs::set_permissions(tempdir.path(), fs::Permissions::from_mode(0o777)).unwrap();
+    sandbox.add_exception(Exception::WriteAndRead(tempdir.path().into())).unwrap();
+
+    // Spawn sandbox child reporting its IDs.
+    let file = tempdir.path().join("file");
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg(format!("id -u; id -G; touch {}", file.display()));
+    cmd.capture_output();
+    let output = sandbox.spawn(cmd).unwrap().output().unwrap();
+    assert!(output.status.success());
+
+    // Sandboxee runs as the unprivileged user without supplementary groups.
+    assert_eq!(String::from_utf8_lossy(&output.stdout), format!("{NOBODY}\n{NOBODY}\n"));
+    let metadata = fs::metadata(&file).unwrap();
+    assert_eq!((metadata.uid(), metadata.gid()), (NOBODY, NOBODY));
+}
+
+#[cfg(target_os = "linux")]
+fn network_namespace() {
+    // Setup sandbox with an isolated network namespace, despite allowing
+    // networking.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    sandbox.add_exception(Exception::Networking).unwrap();
+    sandbox.with_network_namespace(NetworkNamespace::Isolated);
+
+    // List network interfaces visible to the sandboxee.
+    let mut cmd = Command::new("/bin/cat");
+    cmd.arg("/proc/net/dev");
+    cmd.capture_output();
+    let output = sandbox.spawn(cmd).unwrap().output().unwrap();
+    assert!(output.status.success());
+
+    // Only the loopback interface exists.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let interfaces: Vec<_> =
+        stdout.lines().skip(2).filter_map(|line| line.split(':').next()).map(str::trim).collect();
+    assert_eq!(interfaces, ["lo"]);
+}
+
+#[cfg(target_os = "linux")]
+fn proc_access() {
+    // Setup sandbox only exposing the sandboxee's own processes.
+    //
+    // The sandboxee must not be root, otherwise it could see the sandbox's PID 1.
+    let id_map = |outside_id| vec![IdMap { inside_id: 1000, outside_id, count: 1 }];
+    let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+    let mut sandbox = Birdcage::new();
+    sandbox.with_user_namespace(id_map(uid), id_map(gid));
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    sandbox.proc_access(ProcAccess::SelfOnly);
+
+    // Read own process information and system-wide files.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg("cat /proc/self/maps >/dev/null && echo self; cat /proc/cpuinfo; ls /proc/1");
+    cmd.capture_output();
+    let output = sandbox.spawn(cmd).unwrap().output().unwrap();
+
+    // Only the sandboxee's own processes are visible.
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "self\n");
+}
+
+#[cfg(target_os = "linux")]
+fn audit_channel() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let denied = tempdir.path().join("denied");
+    fs::write(&denied, "denied").unwrap();
+
+    // Setup sandbox reporting violations to a channel.
+    let (sender, receiver) = mpsc::channel();
+    let mut sandbox = Birdcage::new();
+    for exception in Exception::platform_libraries() {
+        sandbox.add_exception(exception).unwrap();
+    }
+    sandbox.add_exception(Exception::ExecuteAndRead("/usr/bin/cat".into())).unwrap();
+    sandbox.with_audit_log(sender);
+    assert_eq!(sandbox.mode(), SandboxMode::Audit);
+
+    // Read file without an exception.
+    let mut cmd = Command::new("/usr/bin/cat");
+    cmd.arg(&denied);
+    cmd.capture_output();
+    let output = sandbox.spawn(cmd).unwrap().output().unwrap();
+
+    // Access is allowed, but reported.
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "denied");
+    let denied = denied.canonicalize().unwrap();
+    let violation = receiver.iter().find(|violation| violation.path.as_ref() == Some(&denied));
+    let violation = violation.unwrap();
+    assert_eq!(violation.access, "read");
+    assert!(violation.syscall.starts_with("open"));
+}
+
+#[cfg(target_os = "linux")]
+fn signal() {
+    // Setup long-running command.
+    let mut cmd = Command::new("sleep");
+    cmd.arg("10");
+
+    // Spawn sandbox child.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let mut child = sandbox.spawn(cmd).unwrap();
+
+    // Stopped child does not exit.
+    child.signal(Signal::STOP).unwrap();
+    let status = child.wait_timeout(Duration::from_millis(100)).unwrap();
+    assert!(status.is_none());
+    assert!(child.try_wait().unwrap().is_none());
+
+    // Sandboxee itself is stopped, rather than the sandbox's PID 1.
+    let sandboxee_pid = child.sandboxee_id().unwrap();
+    assert_ne!(sandboxee_pid, child.id());
+    let stat = fs::read_to_string(format!("/proc/{sandboxee_pid}/stat")).unwrap();
+    let state = stat.rsplit_once(") ").unwrap().1;
+    assert!(state.starts_with('T'), "sandboxee is not stopped: {stat}");
+
+    // Resume and terminate the child.
+    child.signal(Signal::CONT).unwrap();
+    child.signal(Signal::TERM).unwrap();
+
+    // Child exits with the termination signal.
+    let status = child.wait_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(status.and_then(|status| status.signal()), Some(libc::SIGTERM));
+
+    // Reaped children cannot be signaled anymore.
+    let result = child.signal(Signal::TERM);
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    child.kill().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+fn fd_mapping() {
+    // Create a file which is not accessible from within the sandbox.
+    let path = std::env::temp_dir().join(format!("birdcage-fd-mapping-{}", std::process::id()));
+    fs::write(&path, "mapped\n").unwrap();
+    let file = File::open(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // Inheritable FD which is not passed to the sandboxee.
+    let leaked_fd = unsafe { libc::dup(file.as_raw_fd()) };
+    assert!(leaked_fd > 2);
+
+    // Spawn sandbox child reading from both FDs.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg(format!("cat <&5; cat <&{leaked_fd} 2> /dev/null && echo leaked"));
+    cmd.fd_mapping(5, file.as_raw_fd());
+    cmd.stdout(Stdio::piped());
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let child = sandbox.spawn(cmd).unwrap();
+
+    // Only the mapped FD is accessible.
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "mapped\n");
+    unsafe { libc::close(leaked_fd) };
+
+    // Mapping missing FDs is rejected.
+    let mut cmd = Command::new("/bin/true");
+    cmd.fd_mapping(3, leaked_fd);
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    assert!(matches!(sandbox.spawn(cmd), Err(Error::Io(_))));
+}
+
+#[cfg(target_os = "linux")]
+fn timeout() {
+    // Setup long-running command with a short deadline.
+    let mut cmd = Command::new("sleep");
+    cmd.arg("60").timeout(Duration::from_millis(200));
+
+    // Spawn sandbox child.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let start = Instant::now();
+    let mut child = sandbox.spawn(cmd).unwrap();
+
+    // Child is killed once the deadline expired.
+    let status = child.wait().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+    assert!(child.timed_out());
+
+    // Children exiting before their deadline are not affected.
+    let mut cmd = Command::new("/bin/true");
+    cmd.timeout(Duration::from_secs(60));
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let mut child = sandbox.spawn(cmd).unwrap();
+    assert!(child.wait().unwrap().success());
+    assert!(!child.timed_out());
+}
+
+#[cfg(target_os = "linux")]
+fn inherited_fds() {
+    // Create an inheritable FD for a file which is not accessible from within the
+    // sandbox.
+    let path = std::env::temp_dir().join(format!("birdcage-inherited-{}", std::process::id()));
+    fs::write(&path, "inherited\n").unwrap();
+    let file = File::open(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let fd = unsafe { libc::dup(file.as_raw_fd()) };
+    assert!(fd > 2);
+
+    let read_fd = |configure: &dyn Fn(&mut Command)| {
+        unsafe { libc::lseek(fd, 0, libc::SEEK_SET) };
+
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg(format!("cat <&{fd} 2> /dev/null"));
+        cmd.stdout(Stdio::piped());
+        configure(&mut cmd);
+        let mut sandbox = Birdcage::new();
+        sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+        let output = sandbox.spawn(cmd).unwrap().wait_with_output().unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // FDs are closed by default.
+    assert_eq!(read_fd(&|_| ()), "");
+
+    // FDs can be passed explicitly.
+    assert_eq!(read_fd(&|cmd| _ = cmd.keep_fd(fd)), "inherited\n");
+
+    // FDs below the threshold are inherited.
+    assert_eq!(read_fd(&|cmd| _ = cmd.close_fds_from(fd + 1)), "inherited\n");
+
+    unsafe { libc::close(fd) };
+}
+
+#[cfg(target_os = "linux")]
+fn pid_namespace() {
+    // Print own PIDs and all processes visible through procfs.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.args(["-c", "echo $PPID $$; ls /proc"]);
+    cmd.stdout(Stdio::piped());
+
+    // Spawn sandbox child.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let child = sandbox.spawn(cmd).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    // Sandboxee is spawned by the sandbox's PID 1.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("1 2"));
+
+    // Only the sandbox's own processes are visible.
+    let mut pids: Vec<u32> = lines.filter_map(|entry| entry.parse().ok()).collect();
+    pids.sort_unstable();
+    assert_eq!(pids, [1, 2, 3]);
+}
+
+#[cfg(target_os = "linux")]
+fn pre_exec() {
+    // Print shell's PID and its process group.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.args(["-c", "echo $$; cut -d' ' -f5 /proc/self/stat"]);
+    cmd.stdout(Stdio::piped());
+
+    // Move the sandboxee into its own process group.
+    unsafe {
+        cmd.pre_exec(|| match libc::setpgid(0, 0) {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        })
+    };
+
+    // Spawn sandbox child.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let child = sandbox.spawn(cmd).unwrap();
+
+    // Sandboxee leads its own process group.
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (pid, pgid) = stdout.trim().split_once('\n').unwrap();
+    assert_eq!(pid, pgid);
+}
+
+#[cfg(target_os = "linux")]
+fn command_ids() {
+    let uid = unsafe { libc::geteuid() };
+    let gid = unsafe { libc::getegid() };
+    let id_map = |outside_id| vec![IdMap { inside_id: 1000, outside_id, count: 1 }];
+
+    // Spawn sandbox child reporting its IDs.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.args(["-c", "id -u; id -g"]).uid(1000).gid(1000);
+    cmd.stdout(Stdio::piped());
+    let mut sandbox = Birdcage::new();
+    sandbox.with_user_namespace(id_map(uid), id_map(gid));
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let output = sandbox.clone_config().spawn(cmd).unwrap().wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1000\n1000\n");
+
+    // IDs outside of the user namespace's mapping are rejected.
+    let mut cmd = Command::new("/bin/true");
+    cmd.uid(1001);
+    let result = sandbox.spawn(cmd);
+    assert!(matches!(result, Err(Error::ActivationFailed(_))));
+}
+
+#[cfg(target_os = "linux")]
+fn sandbox_output() {
+    // Output is captured without configuring stdio.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg("echo out; echo err >&2; exit 3");
+
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    let output = sandbox.output(cmd).unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(output.stdout, b"out\n");
+    assert_eq!(output.stderr, b"err\n");
+}
+
+#[cfg(target_os = "linux")]
+fn core_dumps() {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    // Crash the child in a writable working directory.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg("ulimit -c; ulimit -Hc; kill -SEGV $$");
+    cmd.current_dir(tempdir.path()).suppress_core_dumps().stdout(Stdio::piped());
+
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    sandbox.add_exception(Exception::WriteAndRead(tempdir.path().into())).unwrap();
+    let output = sandbox.spawn(cmd).unwrap().wait_with_output().unwrap();
+
+    // Core dumps are disabled and no core file was written.
+    assert_eq!(output.status.signal(), Some(libc::SIGSEGV));
+    assert_eq!(output.stdout, b"0\n0\n");
+    assert_eq!(fs::read_dir(tempdir.path()).unwrap().count(), 0);
+
+    // Core dumps can be re-enabled up to the hard limit.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg("[ \"$(ulimit -c)\" = \"$(ulimit -Hc)\" ]");
+    cmd.allow_core_dumps(None);
+
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    assert!(sandbox.spawn(cmd).unwrap().wait().unwrap().success());
+}
+
+#[cfg(target_os = "linux")]
+fn shared_memory() {
+    let pid = std::process::id();
+    let existing = format!("/birdcage_shm_{pid}");
+    let created = format!("/birdcage_shm_created_{pid}");
+    let hidden = format!("/birdcage_shm_hidden_{pid}");
+
+    let shm_open = |name: &str, flags| {
+        let name = CString::new(name).unwrap();
+        let fd = unsafe { libc::shm_open(name.as_ptr(), flags, 0o600) };
+        assert!(fd >= 0, "shm_open failed: {}", io::Error::last_os_error());
+        unsafe { OwnedFd::from_raw_fd(fd) }
+    };
+
+    // Create objects outside of the sandbox.
+    shm_open(&existing, libc::O_CREAT | libc::O_RDWR);
+    shm_open(&hidden, libc::O_CREAT | libc::O_RDWR);
+
+    // Write to both allowed objects and list all visible objects.
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c").arg(format!(
+        "printf existing > /dev/shm{existing}; printf created > /dev/shm{created}; ls /dev/shm"
+    ));
+    cmd.stdout(Stdio::piped());
+
+    let mut sandbox = Birdcage::new();
+    for path in ["/bin", "/usr", "/lib", "/lib64"] {
+        sandbox.add_exception_lenient(Exception::ExecuteAndRead(path.into())).unwrap();
+    }
+    sandbox.add_exception(Exception::SharedMemory(existing.clone())).unwrap();
+    sandbox.add_exception(Exception::SharedMemoryCreate(created.clone())).unwrap();
+
+    // Objects are only created once the sandbox is spawned.
+    assert!(fs::metadata(format!("/dev/shm{created}")).is_err());
+    let output = sandbox.spawn(cmd).unwrap().wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    // Other objects are hidden.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut visible: Vec<_> = stdout.lines().collect();
+    visible.sort_unstable();
+    assert_eq!(visible, [&existing[1..], &created[1..]]);
+
+    // Writes are visible through a shared mapping.
+    for (name, content) in [(&existing, &b"existing"[..]), (&created, b"created")] {
+        let fd = shm_open(name, libc::O_RDONLY);
+        let len = content.len();
+        let mapping = unsafe {
+            libc::mmap(ptr::null_mut(), len, libc::PROT_READ, libc::MAP_SHARED, fd.as_raw_fd(), 0)
+        };
+        assert_ne!(mapping, libc::MAP_FAILED);
+        assert_eq!(unsafe { slice::from_raw_parts(mapping as *const u8, len) }, content);
+        unsafe { libc::munmap(mapping, len) };
+    }
+
+    for name in [existing, created, hidden] {
+        let name = CString::new(name).unwrap();
+        unsafe { libc::shm_unlink(name.as_ptr()) };
+    }
+}
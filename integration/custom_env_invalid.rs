@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    let mut sandbox = Birdcage::new();
+
+    // Malformed variables are rejected instead of panicking during setup.
+    for (key, value) in [("A=B", "value"), ("NUL\0KEY", "value"), ("KEY", "nul\0value"), ("", "")]
+    {
+        let env = HashMap::from([(key.to_string(), value.to_string())]);
+        let result = sandbox.add_exception(Exception::CustomEnvironment(env.clone()));
+        assert!(matches!(result, Err(Error::InvalidEnvVar(invalid)) if invalid == key));
+        let result = sandbox.add_exception(Exception::SetEnvironment(env));
+        assert!(matches!(result, Err(Error::InvalidEnvVar(invalid)) if invalid == key));
+    }
+
+    let env = HashMap::from([("VALID".to_string(), "a=b".to_string())]);
+    sandbox.add_exception(Exception::CustomEnvironment(env)).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Only the valid environment is used.
+    let env: Vec<_> = env::vars().collect();
+    assert_eq!(env, [("VALID".to_string(), "a=b".to_string())]);
+}
@@ -0,0 +1,31 @@
+use std::env;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Remove all existing variables.
+    for (key, _) in env::vars_os() {
+        env::remove_var(key);
+    }
+
+    // Setup our environment variables
+    env::set_var("PUBLIC", "GOOD");
+    env::set_var("PUBLIC_TOO", "GOOD");
+    env::set_var("PUBLICX", "BAD");
+
+    // Activate our sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.allow_env_vars(["PUBLIC".into(), "PUBLIC_TOO".into(), "MISSING".into()]).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Only allowed variables remain.
+    let mut env: Vec<_> = env::vars().collect();
+    env.sort_unstable();
+    assert_eq!(env, vec![("PUBLIC".into(), "GOOD".into()), ("PUBLIC_TOO".into(), "GOOD".into())]);
+}
@@ -0,0 +1,33 @@
+use std::env;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Remove all existing variables.
+    for (key, _) in env::vars_os() {
+        env::remove_var(key);
+    }
+
+    // Setup our environment variables
+    env::set_var("PUBLIC", "GOOD");
+    env::set_var("SECRET", "BAD");
+    env::set_var("TOKEN", "BAD");
+
+    // Activate our sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.inherit_env_except(&["SECRET".into(), "TOKEN".into()]).unwrap();
+
+    // Variables set afterwards are not inherited.
+    env::set_var("LATE", "BAD");
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Only variables which are not denied remain.
+    let env: Vec<_> = env::vars().collect();
+    assert_eq!(env, vec![("PUBLIC".into(), "GOOD".into())]);
+}
@@ -0,0 +1,28 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup environment variables with invalid unicode.
+    env::set_var("PUBLIC", OsStr::from_bytes(b"GOOD\xff"));
+    env::set_var(OsStr::from_bytes(b"PRIVATE\xff"), "BAD");
+    env::set_var("PRIVATE", OsStr::from_bytes(b"BAD\xff"));
+
+    // Activate our sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Environment("PUBLIC".into())).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Only the `PUBLIC` environment variable remains.
+    let env: Vec<_> = env::vars_os().collect();
+    let value = OsStr::from_bytes(b"GOOD\xff").to_os_string();
+    assert_eq!(env, vec![(OsString::from("PUBLIC"), value)]);
+}
@@ -0,0 +1,39 @@
+use std::env;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Remove variables set by cargo.
+    for (key, _) in env::vars().filter(|(key, _)| key.starts_with("CARGO")) {
+        env::remove_var(key);
+    }
+
+    // Setup our environment variables
+    env::set_var("MY_APP_FOO", "GOOD");
+    env::set_var("MY_APP_BAR", "GOOD");
+    env::set_var("MY_APPX", "BAD");
+    env::set_var("OTHER_VAR", "BAD");
+    env::set_var("LIB_A_PATH", "GOOD");
+    env::set_var("LIB_AB_PATH", "BAD");
+
+    // Activate our sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::EnvironmentPattern("MY_APP_*".into())).unwrap();
+    sandbox.add_exception(Exception::EnvironmentPattern("LIB_?_PATH".into())).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Only variables matching one of the patterns remain.
+    let mut env: Vec<_> = env::vars().collect();
+    env.sort_unstable();
+    assert_eq!(env, vec![
+        ("LIB_A_PATH".into(), "GOOD".into()),
+        ("MY_APP_BAR".into(), "GOOD".into()),
+        ("MY_APP_FOO".into(), "GOOD".into())
+    ]);
+}
@@ -0,0 +1,35 @@
+use std::env;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Remove variables set by cargo.
+    for (key, _) in env::vars().filter(|(key, _)| key.starts_with("CARGO")) {
+        env::remove_var(key);
+    }
+
+    // Setup our environment variables
+    env::set_var("CARGO_HOME", "GOOD");
+    env::set_var("CARGO_TARGET_DIR", "GOOD");
+    env::set_var("CARGOX", "BAD");
+    env::set_var("RUSTUP_HOME", "BAD");
+
+    // Activate our sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::EnvironmentPrefix("CARGO_".into())).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Only variables starting with `CARGO_` remain.
+    let mut env: Vec<_> = env::vars().collect();
+    env.sort_unstable();
+    assert_eq!(env, vec![
+        ("CARGO_HOME".into(), "GOOD".into()),
+        ("CARGO_TARGET_DIR".into(), "GOOD".into())
+    ]);
+}
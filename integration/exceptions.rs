@@ -0,0 +1,41 @@
+use std::fs;
+use std::os::unix::fs as unixfs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, ResourceLimits, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup a symlink to a data directory.
+    let data = tempdir.join("data");
+    let symlink = tempdir.join("symlink");
+    fs::create_dir(&data).unwrap();
+    unixfs::symlink(&data, &symlink).unwrap();
+
+    // Setup sandbox exceptions.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Read(symlink)).unwrap();
+    sandbox.add_exception(Exception::Environment("HOME".into())).unwrap();
+    let limits = ResourceLimits::new().max_open_files(64);
+    sandbox.add_exception(Exception::ResourceLimits(limits)).unwrap();
+    let limits = ResourceLimits::new().max_processes(16);
+    sandbox.add_exception(Exception::ResourceLimits(limits)).unwrap();
+
+    // Failed exceptions are not recorded.
+    let missing = tempdir.join("missing");
+    assert!(sandbox.add_exception(Exception::Read(missing)).is_err());
+
+    // Exceptions are normalized.
+    let exceptions = sandbox.exceptions();
+    assert_eq!(exceptions.len(), 3);
+    let canonical_data = fs::canonicalize(&data).unwrap();
+    assert!(matches!(&exceptions[0], Exception::Read(path) if path == &canonical_data));
+    assert!(matches!(&exceptions[1], Exception::Environment(key) if key == "HOME"));
+    let limits = ResourceLimits::new().max_open_files(64).max_processes(16);
+    assert!(matches!(&exceptions[2], Exception::ResourceLimits(merged) if merged == &limits));
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {}
@@ -0,0 +1,63 @@
+use std::fs::{self, File};
+use std::os::unix::fs as unixfs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use birdcage::{Birdcage, Exception, Sandbox, SandboxWarning};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    chain: Vec<PathBuf>,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Create a chain of symlinks, including a symlinked directory:
+    //
+    // bin/true -> alias/true -> real/true -> /usr/bin/true
+    //
+    // with `alias` being a symlink to `real`.
+    let bin_dir = tempdir.join("bin");
+    let real_dir = tempdir.join("real");
+    let alias_dir = tempdir.join("alias");
+    fs::create_dir(&bin_dir).unwrap();
+    fs::create_dir(&real_dir).unwrap();
+    unixfs::symlink(&real_dir, &alias_dir).unwrap();
+    unixfs::symlink("/usr/bin/true", real_dir.join("true")).unwrap();
+    unixfs::symlink(alias_dir.join("true"), bin_dir.join("true")).unwrap();
+
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ExecuteAndRead(bin_dir.join("true"))).unwrap();
+
+    // Exceptions for paths removed after they were added are reported.
+    let removed = tempdir.join("removed");
+    File::create(&removed).unwrap();
+    sandbox.add_exception(Exception::Read(removed.clone())).unwrap();
+    fs::remove_file(&removed).unwrap();
+    let warnings = sandbox.validate().unwrap();
+    assert!(warnings.contains(&SandboxWarning::PathDoesNotExist(removed)));
+
+    // Serialize test data.
+    let chain = vec![bin_dir.join("true"), alias_dir.join("true"), real_dir.join("true")];
+    let data = TestData { chain };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Ensure every path in the chain can be executed.
+    for path in data.chain {
+        let cmd = Command::new(&path).status().unwrap();
+        assert!(cmd.success(), "failed to execute {path:?}");
+    }
+
+    // Ensure original executable works.
+    let cmd = Command::new("/usr/bin/true").status().unwrap();
+    assert!(cmd.success());
+}
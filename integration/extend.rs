@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    base_file: PathBuf,
+    plugin_file: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let base_file = tempdir.join("base");
+    let plugin_file = tempdir.join("plugin");
+    fs::write(&base_file, "base").unwrap();
+    fs::write(&plugin_file, "plugin").unwrap();
+    env::set_var("BIRDCAGE_EXTEND", "removed");
+
+    // Setup base sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Read(base_file.clone())).unwrap();
+    sandbox.add_exception(Exception::Environment("BIRDCAGE_EXTEND".into())).unwrap();
+
+    // Setup plugin policy fragment.
+    let custom_env = HashMap::from([("PLUGIN".to_string(), "plugin".to_string())]);
+    let mut plugin = Birdcage::new();
+    plugin.add_exception(Exception::Read(plugin_file.clone())).unwrap();
+    plugin.add_exception(Exception::CustomEnvironment(custom_env)).unwrap();
+
+    // Combine both policies.
+    assert!(!plugin.is_subset_of(&sandbox));
+    sandbox.extend(&plugin).unwrap();
+    assert_eq!(sandbox.exceptions().len(), 4);
+    assert!(plugin.is_subset_of(&sandbox));
+
+    // Identical exceptions are only added once.
+    sandbox.extend(&plugin).unwrap();
+    assert_eq!(sandbox.exceptions().len(), 4);
+
+    // Exceptions are implied by exceptions granting more access.
+    let mut parent = Birdcage::new();
+    parent.add_exception(Exception::WriteAndRead(tempdir.clone())).unwrap();
+    parent.add_exception(Exception::FullEnvironment).unwrap();
+    let mut child = Birdcage::new();
+    child.add_exception(Exception::Read(base_file.clone())).unwrap();
+    child.add_exception(Exception::Environment("BIRDCAGE_EXTEND".into())).unwrap();
+    assert!(child.is_subset_of(&parent));
+
+    // Custom environments are only implied by identical exceptions.
+    assert!(!plugin.is_subset_of(&parent));
+
+    // Denied paths only restrict access.
+    let secret = tempdir.join("secret");
+    fs::create_dir(&secret).unwrap();
+    let mut read = Birdcage::new();
+    read.add_exception(Exception::Read(tempdir.clone())).unwrap();
+    let mut restricted = Birdcage::new();
+    restricted.add_exception(Exception::Read(tempdir.clone())).unwrap();
+    restricted.add_exception(Exception::Deny(secret.clone())).unwrap();
+    assert!(restricted.is_subset_of(&read));
+    assert!(!read.is_subset_of(&restricted));
+
+    // Protocol restrictions do not imply host networking.
+    let mut networking = Birdcage::new();
+    networking.add_exception(Exception::Networking).unwrap();
+    let mut tcp = Birdcage::new();
+    tcp.add_exception(Exception::NetworkProtocol { tcp: true, udp: false }).unwrap();
+    assert!(tcp.is_subset_of(&networking));
+    assert!(!networking.is_subset_of(&tcp));
+
+    // Full environment access subsumes all other environment exceptions.
+    let mut full_env = Birdcage::new();
+    full_env.add_exception(Exception::EnvironmentPrefix("BIRDCAGE_".into())).unwrap();
+    full_env.add_exception(Exception::FullEnvironment).unwrap();
+    full_env.add_exception(Exception::Environment("PATH".into())).unwrap();
+    assert!(matches!(full_env.exceptions(), [Exception::FullEnvironment]));
+
+    // Merging stops at the first invalid exception.
+    let result = sandbox.merge([Exception::Read(tempdir.join("missing")), Exception::Networking]);
+    assert!(result.is_err());
+    assert_eq!(sandbox.exceptions().len(), 4);
+
+    // Serialize test data.
+    let data = TestData { base_file, plugin_file };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Paths from both policies are accessible.
+    assert_eq!(fs::read_to_string(data.base_file).unwrap(), "base");
+    assert_eq!(fs::read_to_string(data.plugin_file).unwrap(), "plugin");
+
+    // Custom environment takes precedence over environment exceptions.
+    assert_eq!(env::var("PLUGIN").unwrap(), "plugin");
+    assert!(env::var_os("BIRDCAGE_EXTEND").is_none());
+}
@@ -0,0 +1,51 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content\n";
+const APPENDED_CONTENT: &str = "appended content\n";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    path: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test files.
+    let path = tempdir.join("fs_append_only");
+    fs::write(&path, FILE_CONTENT.as_bytes()).unwrap();
+
+    // Activate our sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::AppendOnly(path.clone())).unwrap();
+
+    // Serialize test data.
+    let data = TestData { path };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Appending is allowed.
+    let mut file = OpenOptions::new().append(true).open(&data.path).unwrap();
+    file.write_all(APPENDED_CONTENT.as_bytes()).unwrap();
+
+    // Truncation is prohibited.
+    let result = OpenOptions::new().write(true).truncate(true).open(&data.path);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+    let result = file.set_len(0);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+
+    // Read access is allowed.
+    let content = fs::read_to_string(data.path).unwrap();
+    assert_eq!(content, format!("{FILE_CONTENT}{APPENDED_CONTENT}"));
+}
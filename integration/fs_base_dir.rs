@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    src_file: PathBuf,
+    target: PathBuf,
+    private_file: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let project = tempdir.join("project");
+    let src_file = project.join("src").join("file");
+    fs::create_dir_all(src_file.parent().unwrap()).unwrap();
+    fs::write(&src_file, FILE_CONTENT).unwrap();
+    let target = project.join("target");
+    fs::create_dir(&target).unwrap();
+    let private_file = project.join("private");
+    fs::write(&private_file, FILE_CONTENT).unwrap();
+
+    let mut sandbox = Birdcage::new();
+
+    // Only directories can be used as base.
+    let result = sandbox.set_base_dir(src_file.clone());
+    assert!(matches!(result, Err(Error::InvalidPath(_))));
+
+    // Relative paths are resolved against the base directory.
+    sandbox.set_base_dir(project.clone()).unwrap();
+    sandbox.add_exception(Exception::Read("src".into())).unwrap();
+    sandbox.add_exception(Exception::WriteAndRead("target".into())).unwrap();
+    let canonical_target = target.canonicalize().unwrap();
+    assert!(sandbox.exceptions().contains(&Exception::WriteAndRead(canonical_target)));
+
+    // Absolute paths are not affected.
+    sandbox.add_exception(Exception::Read("/etc".into())).unwrap();
+
+    // Serialize test data.
+    let data = TestData { src_file, target, private_file };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Relative read exception is accessible.
+    let content = fs::read_to_string(&data.src_file).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+
+    // Relative write exception is writable.
+    fs::write(data.target.join("output"), FILE_CONTENT).unwrap();
+
+    // Other files in the base directory remain inaccessible.
+    assert!(fs::read_to_string(&data.private_file).is_err());
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    source: PathBuf,
+    target: PathBuf,
+    writable_target: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let source = tempdir.join("source");
+    fs::create_dir(&source).unwrap();
+    fs::write(source.join("file"), FILE_CONTENT).unwrap();
+    let writable_source = tempdir.join("writable_source");
+    fs::create_dir(&writable_source).unwrap();
+    let target = tempdir.join("target");
+    let writable_target = tempdir.join("writable").join("target");
+
+    let mut sandbox = Birdcage::new();
+    sandbox.bind_mount(source.clone(), target.clone(), true).unwrap();
+    sandbox.bind_mount(writable_source.clone(), writable_target.clone(), false).unwrap();
+
+    // Targets must be absolute.
+    let result = sandbox.bind_mount(source.clone(), "target".into(), true);
+    assert!(matches!(result, Err(Error::InvalidPath(_))));
+
+    // Targets are never created on the host.
+    assert!(!target.exists());
+
+    // Serialize test data.
+    let data = TestData { source, target, writable_target };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Source is available at the target.
+    let content = fs::read_to_string(data.target.join("file")).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+
+    // Read-only targets cannot be modified.
+    assert!(fs::write(data.target.join("file"), "x").is_err());
+    assert!(fs::write(data.target.join("new"), "x").is_err());
+
+    // Writable targets can be modified.
+    fs::write(data.writable_target.join("new"), FILE_CONTENT).unwrap();
+
+    // Source remains inaccessible.
+    assert!(fs::read_to_string(data.source.join("file")).is_err());
+}
@@ -27,10 +27,10 @@ pub fn setup(tempdir: PathBuf) -> TestSetup {
     fs::remove_file(&tempfile_path).unwrap();
     assert!(!tempfile_path.exists());
 
-    // Sandbox exception fails with invalid path error.
+    // Sandbox exception fails with path not found error.
     let mut sandbox = Birdcage::new();
     let result = sandbox.add_exception(Exception::Read(symlink.clone()));
-    assert!(matches!(result, Err(Error::InvalidPath(_))));
+    assert!(matches!(result, Err(Error::PathNotFound(_))));
 
     // Serialize test data.
     let data = TestData { symlink };
@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox, SandboxWarning};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    public_path: PathBuf,
+    private_file: PathBuf,
+    private_dir_file: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let public_path = tempdir.join("public");
+    fs::write(&public_path, FILE_CONTENT.as_bytes()).unwrap();
+    let private_file = tempdir.join("private");
+    fs::write(&private_file, FILE_CONTENT.as_bytes()).unwrap();
+    let private_dir = tempdir.join("private-dir");
+    fs::create_dir(&private_dir).unwrap();
+    let private_dir_file = private_dir.join("file");
+    fs::write(&private_dir_file, FILE_CONTENT.as_bytes()).unwrap();
+
+    // Setup sandbox, allowing read for the dir, but denying private paths.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Read(tempdir)).unwrap();
+    sandbox.add_exception(Exception::Deny(private_file.clone())).unwrap();
+    sandbox.add_exception(Exception::Deny(private_dir.clone())).unwrap();
+
+    // Exceptions beneath denied paths are ignored.
+    sandbox.add_exception(Exception::Read(private_dir_file.clone())).unwrap();
+
+    // Allowing the exact denied path is rejected.
+    let result = sandbox.add_exception(Exception::Read(private_dir.clone()));
+    assert!(matches!(result, Err(Error::ConflictingPath(_))));
+
+    // Denied paths beneath an allowed parent are not reported.
+    let warnings = sandbox.validate().unwrap();
+    assert!(!warnings.iter().any(|warning| matches!(warning, SandboxWarning::DenyIgnored(_))));
+
+    // Denied paths without any allowed parent have no effect.
+    let mut ignored = Birdcage::new();
+    ignored.add_exception(Exception::Deny(public_path.clone())).unwrap();
+    let warnings = ignored.validate().unwrap();
+    let public_path_canonical = public_path.canonicalize().unwrap();
+    assert!(warnings.contains(&SandboxWarning::DenyIgnored(public_path_canonical)));
+
+    // Serialize test data.
+    let data = TestData { public_path, private_file, private_dir_file };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Access to the public file is allowed.
+    let content = fs::read_to_string(data.public_path).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+
+    // Access to the private file is prohibited.
+    let result = fs::read_to_string(data.private_file);
+    assert!(result.is_err());
+
+    // Access to the private directory is prohibited.
+    let result = fs::read_to_string(data.private_dir_file);
+    assert!(result.is_err());
+}
@@ -0,0 +1,48 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, DeviceKind, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Device(DeviceKind::Null)).unwrap();
+    sandbox.add_exception(Exception::Device(DeviceKind::Urandom)).unwrap();
+    let zero = DeviceKind::Custom("/dev/zero".into());
+    sandbox.add_exception(Exception::Device(zero)).unwrap();
+
+    // Custom devices must be located beneath `/dev`.
+    let result = sandbox.add_exception(Exception::Device(DeviceKind::Custom(tempdir)));
+    assert!(matches!(result, Err(Error::InvalidPath(_))));
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Writing to `/dev/null` is allowed.
+    fs::write("/dev/null", "blub").unwrap();
+
+    // Reading from `/dev/urandom` is allowed.
+    let mut buf = [0; 16];
+    File::open("/dev/urandom").unwrap().read_exact(&mut buf).unwrap();
+
+    // Writing to `/dev/urandom` is denied.
+    assert!(OpenOptions::new().write(true).open("/dev/urandom").is_err());
+
+    // Custom devices can be written to and controlled with ioctls.
+    let mut zero = OpenOptions::new().read(true).write(true).open("/dev/zero").unwrap();
+    let nonblocking: libc::c_int = 1;
+    let result = unsafe { libc::ioctl(zero.as_raw_fd(), libc::FIONBIO, &nonblocking) };
+    assert_eq!(result, 0);
+    zero.write_all(b"blub").unwrap();
+    zero.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [0; 16]);
+
+    // Other devices are inaccessible.
+    assert!(File::open("/dev/random").is_err());
+    assert!(File::open("/dev/sda").is_err());
+}
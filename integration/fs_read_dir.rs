@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    dir: PathBuf,
+    dir_file: PathBuf,
+    allowed_file: PathBuf,
+    single_file: PathBuf,
+    sibling_file: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let dir = tempdir.join("dir");
+    fs::create_dir(&dir).unwrap();
+    let dir_file = dir.join("file");
+    fs::write(&dir_file, FILE_CONTENT.as_bytes()).unwrap();
+    let allowed_file = dir.join("allowed");
+    fs::write(&allowed_file, FILE_CONTENT.as_bytes()).unwrap();
+    let single_file = tempdir.join("single");
+    fs::write(&single_file, FILE_CONTENT.as_bytes()).unwrap();
+    let sibling_file = tempdir.join("sibling");
+    fs::write(&sibling_file, FILE_CONTENT.as_bytes()).unwrap();
+
+    // Setup sandbox, allowing listing the dir and reading a single file.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ReadDir(dir.clone())).unwrap();
+    sandbox.add_exception(Exception::Read(allowed_file.clone())).unwrap();
+    sandbox.add_exception(Exception::ReadFile(single_file.clone())).unwrap();
+
+    // Exceptions are rejected for the wrong file type.
+    let result = sandbox.add_exception(Exception::ReadFile(dir.clone()));
+    assert!(matches!(result, Err(Error::InvalidPath(_))));
+    let result = sandbox.add_exception(Exception::ReadDir(single_file.clone()));
+    assert!(matches!(result, Err(Error::InvalidPath(_))));
+
+    // Serialize test data.
+    let data = TestData { dir, dir_file, allowed_file, single_file, sibling_file };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Directory content can be listed.
+    let mut entries: Vec<_> =
+        fs::read_dir(&data.dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    entries.sort_unstable();
+    assert_eq!(entries, ["allowed", "file"]);
+
+    // Metadata is accessible, but file content is not.
+    assert!(data.dir_file.metadata().unwrap().is_file());
+    let result = fs::read_to_string(&data.dir_file);
+    assert!(result.is_err());
+
+    // Read exceptions beneath the directory take precedence.
+    let content = fs::read_to_string(&data.allowed_file).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+
+    // Single file is readable, without exposing its siblings.
+    let content = fs::read_to_string(&data.single_file).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+    let result = fs::read_to_string(&data.sibling_file);
+    assert!(result.is_err());
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    file: PathBuf,
+    sibling_file: PathBuf,
+    dir: PathBuf,
+    dir_file: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let file = tempdir.join("file.txt");
+    fs::write(&file, FILE_CONTENT.as_bytes()).unwrap();
+    let sibling_file = tempdir.join("other.txt");
+    fs::write(&sibling_file, FILE_CONTENT.as_bytes()).unwrap();
+    let dir = tempdir.join("dir");
+    fs::create_dir(&dir).unwrap();
+    let dir_file = dir.join("file");
+    fs::write(&dir_file, FILE_CONTENT.as_bytes()).unwrap();
+
+    // Setup sandbox, allowing access to a single file and directory.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ReadExact(file.clone())).unwrap();
+    sandbox.add_exception(Exception::ReadExact(dir.clone())).unwrap();
+
+    // Serialize test data.
+    let data = TestData { file, sibling_file, dir, dir_file };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Exact file can be read.
+    let content = fs::read_to_string(&data.file).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+
+    // Sibling file is inaccessible.
+    let result = fs::read_to_string(&data.sibling_file);
+    assert!(result.is_err());
+
+    // Exact directory exists, but its entries are hidden.
+    assert!(data.dir.is_dir());
+    let entries = fs::read_dir(&data.dir).map_or(0, |entries| entries.count());
+    assert_eq!(entries, 0);
+    let result = fs::read_to_string(&data.dir_file);
+    assert!(result.is_err());
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    allowed: Vec<PathBuf>,
+    denied: Vec<PathBuf>,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let names = ["lib3.10", "lib3.11", "lib4.0", "liba", "libb", "libz", ".lib3.12", "other"];
+    let mut paths = Vec::new();
+    for name in names {
+        let dir = tempdir.join(name);
+        fs::create_dir(&dir).unwrap();
+        let file = dir.join("file");
+        fs::write(&file, FILE_CONTENT).unwrap();
+        paths.push(file);
+    }
+
+    let mut sandbox = Birdcage::new();
+
+    // `*` matches any number of characters, but no leading dot.
+    let pattern = tempdir.join("*lib3.*").to_string_lossy().into_owned();
+    sandbox.add_exception(Exception::ReadGlob(pattern)).unwrap();
+
+    // `?` matches exactly one character.
+    let pattern = tempdir.join("lib?.?").to_string_lossy().into_owned();
+    sandbox.add_exception(Exception::ReadGlob(pattern)).unwrap();
+
+    // Character classes match a single character from a set or range.
+    let pattern = tempdir.join("lib[!b-y]").to_string_lossy().into_owned();
+    sandbox.add_exception(Exception::ReadGlob(pattern)).unwrap();
+
+    // Wildcards can be used in intermediate components.
+    let pattern = tempdir.join("oth[a-f]r/fil?").to_string_lossy().into_owned();
+    sandbox.add_exception(Exception::ReadGlob(pattern)).unwrap();
+
+    // Patterns without matches are rejected.
+    let pattern = tempdir.join("missing*").to_string_lossy().into_owned();
+    let result = sandbox.add_exception(Exception::ReadGlob(pattern));
+    assert!(matches!(result, Err(Error::PathNotFound(_))));
+
+    // Serialize test data.
+    let allowed = [0, 1, 2, 3, 5, 7].iter().map(|i| paths[*i].clone()).collect();
+    let denied = [4, 6].iter().map(|i| paths[*i].clone()).collect();
+    let data = TestData { allowed, denied };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Files beneath matching paths are readable.
+    for path in &data.allowed {
+        let content = fs::read_to_string(path).unwrap();
+        assert_eq!(content, FILE_CONTENT);
+    }
+
+    // Files beneath other paths are inaccessible.
+    for path in &data.denied {
+        assert!(fs::read_to_string(path).is_err());
+    }
+}
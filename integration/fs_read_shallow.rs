@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    dir: PathBuf,
+    file: PathBuf,
+    subdir: PathBuf,
+    nested_file: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let dir = tempdir.join("shallow");
+    let subdir = dir.join("subdir");
+    fs::create_dir_all(&subdir).unwrap();
+    let file = dir.join("file");
+    fs::write(&file, FILE_CONTENT).unwrap();
+    let nested_file = subdir.join("nested_file");
+    fs::write(&nested_file, FILE_CONTENT).unwrap();
+
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::ReadShallow(dir.clone())).unwrap();
+
+    // Only directories are supported.
+    let result = sandbox.add_exception(Exception::ReadShallow(file.clone()));
+    assert!(matches!(result, Err(Error::InvalidPath(_))));
+
+    // Serialize test data.
+    let data = TestData { dir, file, subdir, nested_file };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Directory can be listed.
+    let mut entries: Vec<_> =
+        fs::read_dir(&data.dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    entries.sort_unstable();
+    assert_eq!(entries, [data.file.clone(), data.subdir.clone()]);
+
+    // Immediate files can be read.
+    let content = fs::read_to_string(&data.file).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+
+    // Subdirectories are visible, without exposing their content.
+    assert!(data.subdir.is_dir());
+    assert!(fs::read_to_string(&data.nested_file).is_err());
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    redundant: PathBuf,
+    writable: PathBuf,
+    restricted: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let tempdir = tempdir.canonicalize().unwrap();
+    let redundant = tempdir.join("redundant");
+    let writable = tempdir.join("writable");
+    let restricted = writable.join("restricted");
+    fs::create_dir(&redundant).unwrap();
+    fs::create_dir_all(&restricted).unwrap();
+    fs::write(redundant.join("file"), "data").unwrap();
+
+    // Setup sandbox exceptions, with a child matching its parent's permissions.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Read(tempdir)).unwrap();
+    sandbox.add_exception(Exception::Read(redundant.clone())).unwrap();
+    sandbox.add_exception(Exception::WriteAndRead(writable.clone())).unwrap();
+    sandbox.add_exception(Exception::Read(restricted.clone())).unwrap();
+
+    // Redundant exceptions are merged into their parent.
+    let preview = sandbox.profile_preview();
+    assert!(!preview.contains(redundant.to_str().unwrap()));
+    assert!(preview.contains(restricted.to_str().unwrap()));
+
+    // Serialize test data.
+    let data = TestData { redundant, writable, restricted };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Redundant path is still readable.
+    let content = fs::read_to_string(data.redundant.join("file")).unwrap();
+    assert_eq!(content, "data");
+
+    // Restricting children of more permissive parents still works.
+    fs::write(data.writable.join("file"), "data").unwrap();
+    assert!(fs::write(data.restricted.join("file"), "data").is_err());
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::os::unix::fs as unixfs;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+const FILE_CONTENT: &str = "expected content";
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    escape: PathBuf,
+    inner: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup a tree with one symlink inside and one outside of it.
+    let secret = tempdir.join("secret");
+    fs::write(&secret, FILE_CONTENT).unwrap();
+    let tree = tempdir.join("tree");
+    fs::create_dir(&tree).unwrap();
+    let file = tree.join("file");
+    fs::write(&file, FILE_CONTENT).unwrap();
+    let inner = tree.join("inner");
+    unixfs::symlink(&file, &inner).unwrap();
+    let escape = tree.join("escape");
+    unixfs::symlink(&secret, &escape).unwrap();
+
+    let mut sandbox = Birdcage::new();
+    sandbox.restrict_symlink_escape(true);
+
+    // Escaping symlinks are rejected.
+    let result = sandbox.add_exception(Exception::Read(escape.clone()));
+    assert!(matches!(result, Err(Error::SymlinkEscape(path)) if path == escape));
+
+    // Escaping entries are skipped for shallow exceptions.
+    sandbox.add_exception(Exception::ReadShallow(tree)).unwrap();
+
+    // Serialize test data.
+    let data = TestData { escape, inner };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Symlinks within the tree can be followed.
+    let content = fs::read_to_string(&data.inner).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+
+    // Symlinks escaping the tree cannot be followed.
+    assert!(fs::read_to_string(&data.escape).is_err());
+}
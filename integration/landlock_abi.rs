@@ -0,0 +1,63 @@
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+use std::ptr;
+
+use birdcage::{Birdcage, Exception, Sandbox, SandboxWarning};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    abi: u32,
+    path: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Query the kernel's Landlock ABI version.
+    let abi = unsafe {
+        libc::syscall(libc::SYS_landlock_create_ruleset, ptr::null::<u8>(), 0, 1 << 0)
+    };
+    let abi = abi.try_into().unwrap_or(0);
+
+    // Sandbox reports the same ABI version.
+    let mut sandbox = Birdcage::new();
+    assert_eq!(sandbox.landlock_abi_version(), abi);
+    assert_eq!(sandbox.landlock_abi(), (abi > 0).then_some(abi));
+
+    // Missing Landlock support is reported as warning.
+    let warnings = sandbox.validate().unwrap();
+    assert_eq!(warnings.contains(&SandboxWarning::LandlockUnavailable), abi == 0);
+
+    // Strict mode rejects missing Landlock support.
+    let mut strict_sandbox = Birdcage::new();
+    strict_sandbox.strict_landlock(true);
+    assert_eq!(strict_sandbox.validate().is_ok(), abi > 0);
+
+    // Append-only exceptions are rejected without truncation support.
+    let path = tempdir.join("data");
+    fs::write(&path, "data").unwrap();
+    let mut append_sandbox = Birdcage::new();
+    append_sandbox.add_exception(Exception::AppendOnly(path.clone())).unwrap();
+    assert_eq!(append_sandbox.validate().is_ok(), abi >= 3);
+    if abi >= 3 {
+        sandbox = append_sandbox;
+    }
+
+    // Serialize test data.
+    let data = TestData { abi, path };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Truncation is denied if the ABI version supports it.
+    if data.abi >= 3 {
+        let result = OpenOptions::new().write(true).truncate(true).open(&data.path);
+        assert!(result.is_err());
+    }
+}
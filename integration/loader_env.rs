@@ -0,0 +1,34 @@
+use std::env;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox, SandboxWarning};
+
+use crate::TestSetup;
+
+#[cfg(target_os = "linux")]
+const LOADER_VAR: &str = "LD_LIBRARY_PATH";
+#[cfg(target_os = "macos")]
+const LOADER_VAR: &str = "DYLD_LIBRARY_PATH";
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    env::set_var(LOADER_VAR, "/opt/lib");
+    let warning = SandboxWarning::LoaderEnvironmentRemoved(LOADER_VAR.into());
+
+    // Loader variables are only relevant for executables.
+    let mut sandbox = Birdcage::new();
+    assert!(!sandbox.validate().unwrap().contains(&warning));
+
+    // Filtered loader variables are reported.
+    sandbox.add_exception(Exception::ExecuteAndRead("/usr".into())).unwrap();
+    assert!(sandbox.validate().unwrap().contains(&warning));
+
+    // Preserving the loader environment resolves the warning.
+    sandbox.preserve_loader_env().unwrap();
+    assert!(!sandbox.validate().unwrap().contains(&warning));
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    assert_eq!(env::var(LOADER_VAR).unwrap(), "/opt/lib");
+}
@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+
+const FILE_CONTENT: &str = "expected content";
+
+fn main() {
+    // Reexecute the binary, since locking cannot be undone.
+    let mut args = std::env::args().skip(1);
+    if let Some(tempdir) = args.next() {
+        validate(PathBuf::from(tempdir));
+        return;
+    }
+
+    // Setup our test files.
+    let tempdir = tempfile::tempdir().unwrap();
+    fs::write(tempdir.path().join("private"), FILE_CONTENT.as_bytes()).unwrap();
+    fs::write(tempdir.path().join("public"), FILE_CONTENT.as_bytes()).unwrap();
+
+    let current_exe = std::env::current_exe().unwrap();
+    let status = Command::new(current_exe).arg(tempdir.path()).status().unwrap();
+    assert!(status.success());
+}
+
+fn validate(tempdir: PathBuf) {
+    let public_path = tempdir.join("public");
+    let private_path = tempdir.join("private");
+
+    // Rejected settings do not prevent locking afterwards.
+    #[cfg(target_os = "linux")]
+    {
+        let mut sandbox = Birdcage::new();
+        sandbox.set_cgroup_memory_limit(32 * 1024 * 1024);
+        let result = sandbox.lock();
+        assert!(matches!(result, Err(Error::ActivationFailed(_))));
+    }
+
+    // Lock down the current process.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Read(public_path.clone())).unwrap();
+    sandbox.lock().unwrap();
+
+    // Access to the public file is allowed.
+    let content = fs::read_to_string(public_path).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+
+    // Access to the private file is prohibited.
+    let result = fs::read_to_string(private_path);
+    assert!(result.is_err());
+
+    // Locking is only possible once.
+    let result = Birdcage::new().lock();
+    assert!(matches!(result, Err(Error::AlreadyLocked)));
+}
@@ -13,10 +13,14 @@ pub fn setup(_tempdir: PathBuf) -> TestSetup {
 
     // Ensure it is appropriately reported that exception was NOT added.
     match result {
-        Err(Error::InvalidPath(path)) => assert_eq!(path, PathBuf::from("/does/not/exist")),
+        Err(Error::PathNotFound(path)) => assert_eq!(path, PathBuf::from("/does/not/exist")),
         _ => panic!("expected path error"),
     }
 
+    // Missing paths are ignored by lenient exceptions.
+    sandbox.add_exception_lenient(Exception::Read("/does/not/exist".into())).unwrap();
+    assert!(sandbox.exceptions().is_empty());
+
     TestSetup { sandbox, data: String::new() }
 }
 
@@ -0,0 +1,72 @@
+use std::io::ErrorKind;
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    allowed: SocketAddr,
+    allowed_v6: Option<SocketAddr>,
+    denied: SocketAddr,
+    bind: SocketAddr,
+}
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup listeners, which stay open until the sandboxee exits.
+    let allowed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let denied = TcpListener::bind("127.0.0.1:0").unwrap();
+    let allowed_v6 = TcpListener::bind("[::1]:0").ok();
+
+    // Find an unused port for binding.
+    let bind = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+    // Setup sandbox exceptions.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::NetworkConnect(allowed.local_addr().unwrap())).unwrap();
+    if let Some(allowed_v6) = &allowed_v6 {
+        let addr = allowed_v6.local_addr().unwrap();
+        sandbox.add_exception(Exception::NetworkConnect(addr)).unwrap();
+    }
+    sandbox.add_exception(Exception::NetworkBind(bind)).unwrap();
+
+    // Serialize test data.
+    let data = TestData {
+        allowed: allowed.local_addr().unwrap(),
+        allowed_v6: allowed_v6.as_ref().map(|listener| listener.local_addr().unwrap()),
+        denied: denied.local_addr().unwrap(),
+        bind,
+    };
+    let data = serde_json::to_string(&data).unwrap();
+
+    mem::forget((allowed, denied, allowed_v6));
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Connecting to allowed addresses works.
+    TcpStream::connect(data.allowed).unwrap();
+    if let Some(allowed_v6) = data.allowed_v6 {
+        TcpStream::connect(allowed_v6).unwrap();
+    }
+
+    // Connecting to other addresses is refused.
+    let result = TcpStream::connect(data.denied);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::ConnectionRefused);
+
+    // Binding to the allowed address works.
+    let listener = TcpListener::bind(data.bind).unwrap();
+    assert_eq!(listener.local_addr().unwrap(), data.bind);
+
+    // Binding to other addresses is denied.
+    let result = TcpListener::bind("127.0.0.1:0");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+}
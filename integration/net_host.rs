@@ -0,0 +1,47 @@
+use std::io::ErrorKind;
+use std::mem;
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    allowed_port: u16,
+    denied_port: u16,
+}
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup listeners, which stay open until the sandboxee exits.
+    let allowed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let denied = TcpListener::bind("127.0.0.1:0").unwrap();
+    let allowed_port = allowed.local_addr().unwrap().port();
+    let denied_port = denied.local_addr().unwrap().port();
+    mem::forget((allowed, denied));
+
+    // Setup sandbox exceptions.
+    let mut sandbox = Birdcage::new();
+    let host = String::from("localhost");
+    sandbox.add_exception(Exception::NetworkHost { host, port: Some(allowed_port) }).unwrap();
+
+    // Serialize test data.
+    let data = TestData { allowed_port, denied_port };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Connecting to the allowed host and port works.
+    TcpStream::connect(("localhost", data.allowed_port)).unwrap();
+
+    // Connecting to other ports is refused.
+    let result = TcpStream::connect(("localhost", data.denied_port));
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::ConnectionRefused);
+}
@@ -0,0 +1,44 @@
+use std::io::ErrorKind;
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    addr: SocketAddr,
+}
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup listener, which stays open until the sandboxee exits.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+    // Setup sandbox exceptions.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::NetworkInbound).unwrap();
+
+    // Serialize test data.
+    let data = TestData { addr: listener.local_addr().unwrap() };
+    let data = serde_json::to_string(&data).unwrap();
+
+    mem::forget(listener);
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Binding to any address works.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    assert_ne!(listener.local_addr().unwrap().port(), 0);
+
+    // Outgoing connections are refused.
+    let result = TcpStream::connect(data.addr);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::ConnectionRefused);
+}
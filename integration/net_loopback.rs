@@ -0,0 +1,38 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::thread;
+
+use birdcage::{Birdcage, Exception, NetworkNamespace, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup sandbox with networking restricted to its own loopback interface.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Networking).unwrap();
+    sandbox.with_network_namespace(NetworkNamespace::LoopbackOnly);
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Binding and connecting to loopback works.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(b"loopback").unwrap();
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "loopback");
+    server.join().unwrap();
+
+    // Addresses outside of the loopback interface are unavailable.
+    let result = TcpListener::bind("192.0.2.1:0");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::AddrNotAvailable);
+    assert!(TcpStream::connect("1.1.1.1:80").is_err());
+}
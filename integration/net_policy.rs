@@ -0,0 +1,37 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::thread;
+
+use birdcage::{Birdcage, NetworkPolicy, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup sandbox with networking restricted to loopback addresses.
+    let mut sandbox = Birdcage::new();
+    sandbox.merge(NetworkPolicy::new().allow_loopback()).unwrap();
+    assert_eq!(sandbox.exceptions().len(), 4);
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Binding and connecting to loopback works.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(b"loopback").unwrap();
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "loopback");
+    server.join().unwrap();
+
+    // Other addresses are unreachable.
+    assert!(TcpStream::connect("8.8.8.8:53").is_err());
+    assert!(TcpListener::bind("0.0.0.0:0").is_err());
+}
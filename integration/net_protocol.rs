@@ -0,0 +1,31 @@
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup sandbox exceptions.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::NetworkProtocol { tcp: true, udp: false }).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // TCP is allowed.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+    // UDP is denied.
+    let result = UdpSocket::bind("127.0.0.1:0");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+    let result = UdpSocket::bind("[::1]:0");
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+
+    // UNIX domain sockets are not affected.
+    UnixDatagram::unbound().unwrap();
+}
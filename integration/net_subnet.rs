@@ -0,0 +1,52 @@
+use std::io::ErrorKind;
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    allowed: SocketAddr,
+    denied: SocketAddr,
+}
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup listeners inside and outside of the subnet.
+    let allowed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let denied = TcpListener::bind("127.0.0.2:0").unwrap();
+
+    // Setup sandbox exceptions.
+    let mut sandbox = Birdcage::new();
+    let addr = "127.0.0.0".parse().unwrap();
+    sandbox.add_exception(Exception::NetworkSubnet { addr, prefix_len: 31 }).unwrap();
+
+    // Prefixes must fit the address.
+    let result = sandbox.add_exception(Exception::NetworkSubnet { addr, prefix_len: 33 });
+    assert!(matches!(result, Err(Error::InvalidSubnet(_))));
+
+    // Serialize test data.
+    let data =
+        TestData { allowed: allowed.local_addr().unwrap(), denied: denied.local_addr().unwrap() };
+    let data = serde_json::to_string(&data).unwrap();
+
+    mem::forget((allowed, denied));
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Connecting to addresses within the subnet works.
+    TcpStream::connect(data.allowed).unwrap();
+
+    // Connecting to other addresses is refused.
+    let result = TcpStream::connect(data.denied);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::ConnectionRefused);
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::mem;
+use std::os::unix::fs as unixfs;
+use std::net::TcpStream;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox};
+use serde::{Deserialize, Serialize};
+
+use crate::TestSetup;
+
+#[derive(Serialize, Deserialize)]
+struct TestData {
+    allowed: PathBuf,
+    symlink: PathBuf,
+    denied: PathBuf,
+}
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup listeners, which stay open until the sandboxee exits.
+    let allowed = tempdir.join("allowed.sock");
+    let denied = tempdir.join("denied.sock");
+    let listeners = (UnixListener::bind(&allowed).unwrap(), UnixListener::bind(&denied).unwrap());
+    mem::forget(listeners);
+
+    // Setup a symlink to the allowed socket.
+    let symlink = tempdir.join("symlink.sock");
+    unixfs::symlink(&allowed, &symlink).unwrap();
+
+    let mut sandbox = Birdcage::new();
+
+    // Non-socket paths are rejected.
+    let file = tempdir.join("file");
+    fs::write(&file, "").unwrap();
+    let result = sandbox.add_exception(Exception::UnixSocket(file));
+    assert!(matches!(result, Err(Error::InvalidPath(_))));
+
+    // Setup sandbox exceptions.
+    sandbox.add_exception(Exception::Read(tempdir)).unwrap();
+    sandbox.add_exception(Exception::UnixSocket(symlink.clone())).unwrap();
+
+    // Serialize test data.
+    let data = TestData { allowed, symlink, denied };
+    let data = serde_json::to_string(&data).unwrap();
+
+    TestSetup { sandbox, data }
+}
+
+pub fn validate(data: String) {
+    // Deserialize test data.
+    let data: TestData = serde_json::from_str(&data).unwrap();
+
+    // Connecting to the symlink target and the symlink itself works.
+    UnixStream::connect(&data.allowed).unwrap();
+    UnixStream::connect(&data.symlink).unwrap();
+
+    // Connecting to other sockets is denied, even if they're readable.
+    assert!(UnixStream::connect(&data.denied).is_err());
+
+    // Internet access is still blocked.
+    assert!(TcpStream::connect("1.1.1.1:80").is_err());
+}
@@ -0,0 +1,30 @@
+use std::env;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Home directory is resolved from the environment.
+    let home = env::var_os("HOME").unwrap();
+    let exception = Exception::home_directory().unwrap();
+    assert!(matches!(&exception, Exception::Read(path) if path.as_os_str() == home));
+
+    // Temporary directory is writable.
+    let exception = Exception::temp_directory();
+    assert!(matches!(&exception, Exception::WriteAndRead(path) if path == &env::temp_dir()));
+
+    // Platform libraries only include existing paths.
+    let libraries = Exception::platform_libraries();
+    assert!(!libraries.is_empty());
+    let mut sandbox = Birdcage::new();
+    for exception in libraries {
+        assert!(matches!(&exception, Exception::ExecuteAndRead(path) if path.exists()));
+        sandbox.add_exception(exception).unwrap();
+    }
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {}
@@ -0,0 +1,21 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, ProcAccess, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup sandbox without access to procfs.
+    let mut sandbox = Birdcage::new();
+    sandbox.proc_access(ProcAccess::Hidden);
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Procfs is replaced by an empty directory.
+    assert!(fs::metadata("/proc/self/status").is_err());
+    assert!(fs::read("/proc/cpuinfo").is_err());
+    assert_eq!(fs::read_dir("/proc").unwrap().count(), 0);
+}
@@ -0,0 +1,26 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, ProcAccess, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup sandbox without procfs, apart from the sandboxee's own process.
+    let mut sandbox = Birdcage::new();
+    sandbox.proc_access(ProcAccess::Hidden);
+    sandbox.add_exception(Exception::ProcSelf).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Own process can be inspected.
+    assert!(!fs::read_to_string("/proc/self/maps").unwrap().is_empty());
+    assert!(fs::read_link("/proc/self/exe").is_ok());
+
+    // Other processes and system-wide files are hidden.
+    assert!(fs::read_to_string("/proc/1/maps").is_err());
+    assert!(fs::metadata("/proc/1").is_err());
+    assert!(fs::read("/proc/cpuinfo").is_err());
+}
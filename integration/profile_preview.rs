@@ -0,0 +1,46 @@
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::os::unix::fs as unixfs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test files.
+    let path = tempdir.join("profile_preview");
+    fs::write(&path, b"").unwrap();
+
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::WriteAndRead(path.clone())).unwrap();
+    sandbox.add_exception(Exception::Environment("PREVIEW_VAR".into())).unwrap();
+
+    // Ensure preview contains all exceptions.
+    let preview = sandbox.profile_preview();
+    let canonical_path = path.canonicalize().unwrap();
+    assert!(preview.contains(&format!("{canonical_path:?}")), "missing path in:\n{preview}");
+    assert!(preview.contains("PREVIEW_VAR"), "missing variable in:\n{preview}");
+
+    // Ensure description lists exceptions alongside their rules.
+    let description = sandbox.to_policy_description();
+    let exception = format!("WriteAndRead({canonical_path:?})");
+    assert!(description.contains(&exception), "missing exception in:\n{description}");
+    assert!(description.contains("PREVIEW_VAR"), "missing variable in:\n{description}");
+
+    // Ensure followed symlinks are described.
+    #[cfg(target_os = "linux")]
+    {
+        let symlink = tempdir.join("profile_preview_symlink");
+        unixfs::symlink(&path, &symlink).unwrap();
+        sandbox.add_exception(Exception::Read(symlink.clone())).unwrap();
+
+        let description = sandbox.to_policy_description();
+        let rule = format!("symlink {symlink:?} -> {canonical_path:?}");
+        assert!(description.contains(&rule), "missing symlink in:\n{description}");
+    }
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {}
@@ -0,0 +1,86 @@
+use std::env;
+use std::fs::File;
+use std::os::unix::process::ExitStatusExt;
+
+use birdcage::error::Error;
+use birdcage::process::Command;
+use birdcage::{Birdcage, Exception, ResourceLimits, Sandbox};
+
+const MEMORY_LIMIT: u64 = 256 * 1024 * 1024;
+const FILE_LIMIT: u64 = 16;
+
+fn main() {
+    // Validate restrictions inside the sandboxee.
+    let mut args = env::args().skip(1);
+    match args.next().as_deref() {
+        Some("memory") => exceed_memory(),
+        Some("files") => exceed_files(),
+        Some("cpu") => exceed_cpu(),
+        Some(mode) => unreachable!("invalid mode: {mode:?}"),
+        None => (),
+    }
+
+    // Exceeding the memory limit kills the process.
+    let limits = ResourceLimits::new().max_memory_bytes(MEMORY_LIMIT);
+    let status = spawn(limits, "memory").wait().unwrap();
+    assert!(!status.success());
+    assert!(status.signal().is_some());
+
+    // Exceeding the file limit fails to open new files.
+    let limits = ResourceLimits::new().max_open_files(FILE_LIMIT);
+    let status = spawn(limits, "files").wait().unwrap();
+    assert!(status.success());
+
+    // Exceeding the CPU time limit kills the process.
+    let limits = ResourceLimits::new().max_cpu_seconds(1);
+    let status = spawn(limits, "cpu").wait().unwrap();
+    assert!(!status.success());
+    assert!(status.signal().is_some());
+
+    // Limits above the hard limit are rejected.
+    let limits = ResourceLimits::new().max_open_files(u64::MAX - 1);
+    let result = Birdcage::new().add_exception(Exception::ResourceLimits(limits)).map(|_| ());
+    assert!(matches!(result, Err(Error::InvalidResourceLimit(_))));
+}
+
+/// Spawn the current executable with resource limits.
+fn spawn(limits: ResourceLimits, mode: &str) -> birdcage::process::Child {
+    // Add exceptions to allow self-execution.
+    let mut sandbox = Birdcage::new();
+    let current_exe = env::current_exe().unwrap();
+    for path in [current_exe.clone(), "/usr/lib".into(), "/lib64".into(), "/lib".into()] {
+        sandbox.add_exception_lenient(Exception::ExecuteAndRead(path)).unwrap();
+    }
+    sandbox.add_exception(Exception::Read("/dev/null".into())).unwrap();
+    sandbox.add_exception(Exception::ResourceLimits(limits)).unwrap();
+
+    let mut command = Command::new(current_exe);
+    command.arg(mode);
+    sandbox.spawn_isolated(command).unwrap()
+}
+
+fn exceed_memory() -> ! {
+    let memory = vec![1u8; 2 * MEMORY_LIMIT as usize];
+    println!("{}", memory.iter().map(|byte| *byte as u64).sum::<u64>());
+    std::process::exit(0);
+}
+
+fn exceed_files() -> ! {
+    let mut files = Vec::new();
+    for _ in 0..FILE_LIMIT {
+        match File::open("/dev/null") {
+            Ok(file) => files.push(file),
+            // Opening fails with EMFILE once the limit is reached.
+            Err(err) if err.raw_os_error() == Some(24) => std::process::exit(0),
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+    panic!("file limit was not enforced");
+}
+
+fn exceed_cpu() -> ! {
+    let mut counter = 0u64;
+    loop {
+        counter = std::hint::black_box(counter.wrapping_add(1));
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+use std::ptr;
+
+use birdcage::{Birdcage, Sandbox, SeccompAction, Syscall};
+
+use crate::TestSetup;
+
+/// Size of the kernel log buffer action for `syslog`.
+const SYSLOG_ACTION_SIZE_BUFFER: libc::c_long = 10;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Out of range errno values are rejected.
+    let mut sandbox = Birdcage::new();
+    sandbox.deny_syscall_with(Syscall::from_raw(libc::SYS_getppid), -1);
+    assert!(sandbox.validate().is_err());
+
+    // Rules conflicting with the sandbox's own rules are rejected.
+    let mut sandbox = Birdcage::new();
+    let wait4 = Syscall::from_raw(libc::SYS_wait4);
+    assert!(sandbox.add_syscall_rule(wait4, SeccompAction::ErrnoReturn(libc::EPERM)).is_err());
+    let unshare = Syscall::from_raw(libc::SYS_unshare);
+    assert!(sandbox.add_syscall_rule(unshare, SeccompAction::Allow).is_err());
+
+    let mut sandbox = Birdcage::new();
+    sandbox.deny_syscall_with(Syscall::from_raw(libc::SYS_getppid), libc::ENOSYS);
+    sandbox.allow_syscall(Syscall::from_raw(libc::SYS_syslog));
+    let deny_eperm = SeccompAction::ErrnoReturn(libc::EPERM);
+    sandbox.add_syscall_rule(Syscall::PROCESS_VM_READV, deny_eperm).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Denied syscall returns the custom errno.
+    let result = unsafe { libc::syscall(libc::SYS_getppid) };
+    assert_eq!(result, -1);
+    assert_eq!(errno(), libc::ENOSYS);
+
+    // Syscalls outside the default filter can be allowed.
+    let result =
+        unsafe { libc::syscall(libc::SYS_syslog, SYSLOG_ACTION_SIZE_BUFFER, ptr::null::<u8>(), 0) };
+    assert!(result >= 0 || errno() != libc::EACCES);
+
+    // Custom rules are applied.
+    let result = unsafe {
+        libc::syscall(libc::SYS_process_vm_readv, 1, ptr::null::<u8>(), 0, ptr::null::<u8>(), 0, 0)
+    };
+    assert_eq!(result, -1);
+    assert_eq!(errno(), libc::EPERM);
+
+    // Other syscalls are still denied.
+    let result = unsafe { libc::syscall(libc::SYS_vhangup) };
+    assert_eq!(result, -1);
+    assert_eq!(errno(), libc::EACCES);
+}
+
+fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap()
+}
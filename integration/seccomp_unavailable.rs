@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::error::Error;
+use birdcage::{Birdcage, Exception, Sandbox, SandboxWarning};
+
+use crate::TestSetup;
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Simulate a container denying the `seccomp` syscall.
+    let filter = [
+        // Load the syscall number.
+        libc::sock_filter { code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16, jt: 0, jf: 0, k: 0 },
+        // Return EPERM for `seccomp`, allow everything else.
+        libc::sock_filter {
+            code: (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+            jt: 0,
+            jf: 1,
+            k: libc::SYS_seccomp as u32,
+        },
+        libc::sock_filter {
+            code: (libc::BPF_RET | libc::BPF_K) as u16,
+            jt: 0,
+            jf: 0,
+            k: libc::SECCOMP_RET_ERRNO | libc::EPERM as u32,
+        },
+        libc::sock_filter {
+            code: (libc::BPF_RET | libc::BPF_K) as u16,
+            jt: 0,
+            jf: 0,
+            k: libc::SECCOMP_RET_ALLOW,
+        },
+    ];
+    let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_ptr() as _ };
+    unsafe {
+        assert_eq!(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0), 0);
+        let result = libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &program);
+        assert_eq!(result, 0);
+    }
+
+    // Sandbox activation fails by default.
+    let sandbox = Birdcage::new();
+    assert!(matches!(sandbox.validate(), Err(Error::SeccompUnavailable)));
+
+    // Settings enforced with seccomp fail even in best-effort mode.
+    let mut sandbox = Birdcage::new();
+    sandbox.seccomp_best_effort(true).allow_subprocesses(false);
+    assert!(matches!(sandbox.validate(), Err(Error::SeccompUnavailable)));
+
+    let mut sandbox = Birdcage::new();
+    sandbox.seccomp_best_effort(true);
+    sandbox.add_exception(Exception::NetworkConnect("127.0.0.1:80".parse().unwrap())).unwrap();
+    assert!(matches!(sandbox.validate(), Err(Error::SeccompUnavailable)));
+
+    // Best-effort mode is reported as warning.
+    let path = tempdir.join("data");
+    fs::write(&path, "data").unwrap();
+    let mut sandbox = Birdcage::new();
+    sandbox.seccomp_best_effort(true);
+    let warnings = sandbox.validate().unwrap();
+    assert!(warnings.contains(&SandboxWarning::SeccompUnavailable));
+    assert!(sandbox.profile_preview().contains("seccomp: unavailable"));
+
+    TestSetup { sandbox, data: path.to_string_lossy().into_owned() }
+}
+
+pub fn validate(data: String) {
+    // Filesystem exceptions are still enforced.
+    let result = fs::read_to_string(data);
+    assert!(result.is_err());
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use birdcage::{
+    Birdcage, CapabilitySet, DeviceKind, Exception, Policy, ResourceLimits, Sandbox, SandboxConfig,
+};
+
+#[test]
+fn exception_roundtrip() {
+    let mut custom_env = HashMap::new();
+    custom_env.insert("PATH".into(), "/usr/bin".into());
+
+    let config = SandboxConfig {
+        description: Some("test sandbox".into()),
+        version: Some("1.0.0".into()),
+        exceptions: vec![
+            Exception::Read("/etc".into()),
+            Exception::ReadFile("/etc/hosts".into()),
+            Exception::ReadExact("/etc/passwd".into()),
+            Exception::ReadGlob("/usr/lib/python3.*".into()),
+            Exception::ReadShallow("/etc".into()),
+            Exception::ReadDir("/home".into()),
+            Exception::WriteAndRead("/tmp".into()),
+            Exception::ExecuteAndRead("/usr".into()),
+            Exception::AppendOnly("/var".into()),
+            Exception::Deny("/etc/ssl".into()),
+            Exception::Environment("HOME".into()),
+            Exception::FullEnvironment,
+            Exception::ProcSelf,
+            Exception::CustomEnvironment(custom_env.clone()),
+            Exception::SetEnvironment(custom_env),
+            Exception::Networking,
+            Exception::UnixSocket("/run/daemon.sock".into()),
+            Exception::NetworkConnect("127.0.0.1:80".parse().unwrap()),
+            Exception::NetworkBind("[::1]:0".parse().unwrap()),
+            Exception::NetworkOutbound,
+            Exception::NetworkInbound,
+            Exception::NetworkHost { host: "localhost".into(), port: Some(443) },
+            Exception::NetworkSubnet { addr: "10.0.0.0".parse().unwrap(), prefix_len: 8 },
+            Exception::NetworkProtocol { tcp: true, udp: false },
+            Exception::ResourceLimits(ResourceLimits::new().max_open_files(64)),
+            Exception::Device(DeviceKind::Null),
+            Exception::Device(DeviceKind::Custom("/dev/fuse".into())),
+            Exception::SharedMemory("/plugin_buffer".into()),
+            Exception::SharedMemoryCreate("plugin_output".into()),
+            Exception::DropCapabilities(CapabilitySet::SYS_TIME.insert(CapabilitySet::NET_RAW)),
+            Exception::DropAllCapabilities,
+        ],
+    };
+
+    let json = serde_json::to_string(&config).unwrap();
+    let deserialized: SandboxConfig = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(format!("{config:?}"), format!("{deserialized:?}"));
+}
+
+#[test]
+fn readable_format() {
+    let json = r#"{
+        "exceptions": [
+            { "type": "Read", "path": "/etc" },
+            { "type": "NetworkConnect", "addr": "127.0.0.1:80" },
+            { "type": "NetworkHost", "host": "localhost" },
+            { "type": "Networking" },
+            { "type": "ReadWrite", "path": "/tmp" }
+        ]
+    }"#;
+    let config: SandboxConfig = serde_json::from_str(json).unwrap();
+
+    assert!(config.description.is_none());
+    assert!(config.version.is_none());
+    assert!(matches!(&config.exceptions[0], Exception::Read(path) if path.as_os_str() == "/etc"));
+    assert!(matches!(&config.exceptions[1], Exception::NetworkConnect(addr) if addr.port() == 80));
+    assert!(matches!(&config.exceptions[2], Exception::NetworkHost { port: None, .. }));
+    assert!(matches!(&config.exceptions[3], Exception::Networking));
+    assert!(
+        matches!(&config.exceptions[4], Exception::WriteAndRead(path) if path.as_os_str() == "/tmp")
+    );
+
+    Birdcage::from_config(config).unwrap();
+}
+
+#[test]
+fn invalid_config_exception() {
+    let config = SandboxConfig {
+        exceptions: vec![Exception::Read("/nonexistent/birdcage/path".into())],
+        ..SandboxConfig::default()
+    };
+
+    assert!(Birdcage::from_config(config).is_err());
+}
+
+#[test]
+fn deterministic_format() {
+    let custom_env = [("b", "2"), ("c", "3"), ("a", "1")];
+    let custom_env = custom_env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let exceptions = vec![
+        Exception::CustomEnvironment(custom_env),
+        Exception::Read("./relative/../path".into()),
+        Exception::ResourceLimits(ResourceLimits::new().max_open_files(64)),
+    ];
+
+    let json = serde_json::to_string(&Policy::from(exceptions)).unwrap();
+
+    assert_eq!(
+        json,
+        concat!(
+            r#"[{"type":"CustomEnvironment","env":{"a":"1","b":"2","c":"3"}},"#,
+            r#"{"type":"Read","path":"./relative/../path"},"#,
+            r#"{"type":"ResourceLimits","max_memory_bytes":null,"max_cpu_seconds":null,"#,
+            r#""max_open_files":64,"max_file_size_bytes":null,"max_processes":null}]"#,
+        )
+    );
+}
+
+#[test]
+fn apply_policy() {
+    let json = r#"[{ "type": "Read", "path": "/" }, { "type": "FullEnvironment" }]"#;
+    let policy: Policy = serde_json::from_str(json).unwrap();
+
+    let mut sandbox = Birdcage::new();
+    policy.apply(&mut sandbox).unwrap();
+
+    assert_eq!(sandbox.exceptions().len(), 2);
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Remove all existing variables.
+    for (key, _) in env::vars_os() {
+        env::remove_var(key);
+    }
+
+    // Setup our environment variables
+    env::set_var("KEPT", "GOOD");
+    env::set_var("OVERRIDDEN", "BAD");
+    env::set_var("REMOVED", "BAD");
+
+    // Activate our sandbox.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::Environment("KEPT".into())).unwrap();
+    sandbox.add_exception(Exception::Environment("OVERRIDDEN".into())).unwrap();
+    let env = HashMap::from([("OVERRIDDEN".into(), "BAD".into()), ("ADDED".into(), "BAD".into())]);
+    sandbox.add_exception(Exception::SetEnvironment(env)).unwrap();
+
+    // Later variables take precedence.
+    let env = HashMap::from([("OVERRIDDEN".into(), "GOOD".into()), ("ADDED".into(), "GOOD".into())]);
+    sandbox.add_exception(Exception::SetEnvironment(env)).unwrap();
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Variables are added on top of the filtered environment.
+    let mut env: Vec<_> = env::vars().collect();
+    env.sort_unstable();
+    assert_eq!(env, vec![
+        ("ADDED".into(), "GOOD".into()),
+        ("KEPT".into(), "GOOD".into()),
+        ("OVERRIDDEN".into(), "GOOD".into())
+    ]);
+}
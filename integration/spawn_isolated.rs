@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use std::{env, fs};
+
+use birdcage::process::Command;
+use birdcage::{Birdcage, Exception, Sandbox};
+
+const FILE_CONTENT: &str = "expected content";
+
+fn main() {
+    // Validate restrictions inside the sandboxee.
+    let mut args = env::args().skip(1);
+    if let Some(private_path) = args.next() {
+        validate(PathBuf::from(private_path));
+        return;
+    }
+
+    // Setup our test files.
+    let tempdir = tempfile::tempdir().unwrap();
+    let private_path = tempdir.path().join("private");
+    fs::write(&private_path, FILE_CONTENT.as_bytes()).unwrap();
+    env::set_var("BIRDCAGE_ISOLATED", "1");
+
+    // Add exceptions to allow self-execution.
+    let mut sandbox = Birdcage::new();
+    let current_exe = env::current_exe().unwrap();
+    for path in [current_exe.clone(), "/usr/lib".into(), "/lib64".into(), "/lib".into()] {
+        sandbox.add_exception_lenient(Exception::ExecuteAndRead(path)).unwrap();
+    }
+
+    // Reexecute test with sandbox enabled.
+    let mut command = Command::new(current_exe);
+    command.arg(&private_path);
+    let child = sandbox.spawn_isolated(command).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    // Current process is not restricted.
+    assert_eq!(env::var("BIRDCAGE_ISOLATED").as_deref(), Ok("1"));
+    let content = fs::read_to_string(private_path).unwrap();
+    assert_eq!(content, FILE_CONTENT);
+}
+
+fn validate(private_path: PathBuf) {
+    // Environment variables are restricted.
+    assert!(env::var_os("BIRDCAGE_ISOLATED").is_none());
+
+    // Access to the private file is prohibited.
+    let result = fs::read_to_string(private_path);
+    assert!(result.is_err());
+}
@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+
+use birdcage::{Birdcage, Exception, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup sandbox denying subprocesses.
+    let mut sandbox = Birdcage::new();
+    for path in ["/bin", "/usr"] {
+        sandbox.add_exception_lenient(Exception::ExecuteAndRead(path.into())).unwrap();
+    }
+    sandbox.allow_subprocesses(false);
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Threads can still be spawned.
+    assert_eq!(thread::spawn(|| 1).join().unwrap(), 1);
+
+    // Spawning a subprocess fails.
+    assert!(Command::new("/bin/true").status().is_err());
+}
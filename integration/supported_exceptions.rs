@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Capabilities can be queried without a sandbox.
+    let supported = Birdcage::supported_exceptions();
+    assert!(supported.resource_limits);
+    assert!(supported.environment_patterns);
+    assert_eq!(supported.seccomp_filtering, cfg!(target_os = "linux"));
+    assert_eq!(supported.remote_network_addresses, cfg!(target_os = "linux"));
+
+    // The test environment supports all features.
+    let capabilities = birdcage::capabilities();
+    assert!(capabilities.path_exceptions);
+    assert!(capabilities.network_filtering);
+    assert!(capabilities.env_filtering);
+    assert!(capabilities.subprocess_control);
+
+    TestSetup { sandbox: Birdcage::new(), data: String::new() }
+}
+
+pub fn validate(_data: String) {}
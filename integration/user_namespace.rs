@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, IdMap, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    let euid = unsafe { libc::geteuid() };
+    let egid = unsafe { libc::getegid() };
+
+    // IDs other than the caller's are rejected.
+    let invalid_map = vec![IdMap { inside_id: 0, outside_id: euid + 1, count: 1 }];
+    let mut sandbox = Birdcage::new();
+    sandbox.with_user_namespace(invalid_map, Vec::new());
+    assert!(sandbox.validate().is_err());
+
+    // Create file owned by the caller.
+    let path = tempdir.join("data");
+    fs::write(&path, "data").unwrap();
+
+    // Map the caller to root inside the sandbox.
+    let uid_map = vec![IdMap { inside_id: 0, outside_id: euid, count: 1 }];
+    let gid_map = vec![IdMap { inside_id: 0, outside_id: egid, count: 1 }];
+    let mut sandbox = Birdcage::new();
+    sandbox.with_user_namespace(uid_map, gid_map);
+    sandbox.add_exception(Exception::Read(tempdir)).unwrap();
+
+    TestSetup { sandbox, data: path.to_string_lossy().into() }
+}
+
+pub fn validate(data: String) {
+    // Sandboxee appears as root.
+    assert_eq!(unsafe { libc::getuid() }, 0);
+    assert_eq!(unsafe { libc::getgid() }, 0);
+    assert_eq!(unsafe { libc::geteuid() }, 0);
+    assert_eq!(unsafe { libc::getegid() }, 0);
+
+    // Fake root cannot modify read-only paths.
+    let path = PathBuf::from(data);
+    assert!(fs::write(&path, "test").is_err());
+    assert!(fs::remove_file(&path).is_err());
+    assert!(fs::write(path.with_file_name("other"), "test").is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "data");
+
+    // Fake root cannot access the rest of the host filesystem.
+    assert!(fs::metadata("/etc/passwd").is_err());
+}
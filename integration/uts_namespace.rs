@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Sandbox};
+
+use crate::TestSetup;
+
+pub fn setup(_tempdir: PathBuf) -> TestSetup {
+    // Setup sandbox with a custom hostname.
+    let mut sandbox = Birdcage::new();
+    sandbox.with_uts_namespace(Some("birdcage-test".into()));
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {
+    // Sandbox uses the custom hostname.
+    let mut buf = [0u8; 64];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(result, 0);
+    let len = buf.iter().position(|byte| *byte == 0).unwrap();
+    assert_eq!(&buf[..len], b"birdcage-test");
+
+    // Hostname cannot be changed from inside the sandbox.
+    let hostname = "changed";
+    let result = unsafe { libc::sethostname(hostname.as_ptr().cast(), hostname.len()) };
+    assert_eq!(result, -1);
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use birdcage::{Birdcage, Exception, Sandbox, SandboxWarning};
+
+use crate::TestSetup;
+
+pub fn setup(tempdir: PathBuf) -> TestSetup {
+    // Setup our test tree.
+    let tempdir = tempdir.canonicalize().unwrap();
+    let child_dir = tempdir.join("child");
+    fs::create_dir(&child_dir).unwrap();
+    let data_file = tempdir.join("data");
+    fs::write(&data_file, "data").unwrap();
+
+    // A sandbox without exceptions has no warnings.
+    let sandbox = Birdcage::new();
+    assert_eq!(sandbox.validate().unwrap(), Vec::new());
+
+    // Add conflicting exceptions.
+    let mut sandbox = Birdcage::new();
+    sandbox.add_exception(Exception::WriteAndRead(tempdir.clone())).unwrap();
+    sandbox.add_exception(Exception::Read(child_dir.clone())).unwrap();
+    sandbox.add_exception(Exception::ExecuteAndRead(data_file.clone())).unwrap();
+    sandbox.add_exception(Exception::FullEnvironment).unwrap();
+    sandbox.add_exception(Exception::CustomEnvironment(HashMap::new())).unwrap();
+
+    // Loader variables set by cargo are covered by the `loader_env` test.
+    let mut warnings = sandbox.validate().unwrap();
+    warnings.retain(|warning| !matches!(warning, SandboxWarning::LoaderEnvironmentRemoved(_)));
+    assert!(warnings.contains(&SandboxWarning::EnvironmentIgnored));
+    assert!(warnings.contains(&SandboxWarning::NotExecutable(data_file.clone())));
+    assert!(warnings.contains(&SandboxWarning::RestrictedSubpath {
+        path: child_dir,
+        parent: tempdir.clone(),
+    }));
+    assert!(warnings.contains(&SandboxWarning::RestrictedSubpath {
+        path: data_file,
+        parent: tempdir,
+    }));
+    assert_eq!(warnings.len(), 4);
+
+    // Address exceptions are ignored with full network access.
+    let mut network_sandbox = Birdcage::new();
+    network_sandbox.add_exception(Exception::Networking).unwrap();
+    let addr = "127.0.0.1:80".parse().unwrap();
+    network_sandbox.add_exception(Exception::NetworkConnect(addr)).unwrap();
+    let warnings = network_sandbox.validate().unwrap();
+    assert_eq!(warnings, vec![SandboxWarning::NetworkExceptionsIgnored]);
+
+    // Address exceptions are redundant if all addresses are allowed.
+    let mut outbound_sandbox = Birdcage::new();
+    outbound_sandbox.add_exception(Exception::NetworkOutbound).unwrap();
+    outbound_sandbox.add_exception(Exception::NetworkBind(addr)).unwrap();
+    assert_eq!(outbound_sandbox.validate().unwrap(), Vec::new());
+    outbound_sandbox.add_exception(Exception::NetworkConnect(addr)).unwrap();
+    let warnings = outbound_sandbox.validate().unwrap();
+    assert_eq!(warnings, vec![SandboxWarning::NetworkExceptionsRedundant]);
+
+    TestSetup { sandbox, data: String::new() }
+}
+
+pub fn validate(_data: String) {}
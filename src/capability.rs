@@ -0,0 +1,224 @@
+//! Linux capabilities.
+
+#[cfg(target_os = "linux")]
+use std::io;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Set of Linux capabilities.
+///
+/// See `capabilities(7)` for the privileges granted by each capability.
+///
+/// # Examples
+///
+/// ```rust
+/// use birdcage::{Birdcage, CapabilitySet, Exception, Sandbox};
+///
+/// let dropped = CapabilitySet::all().remove(CapabilitySet::NET_BIND_SERVICE);
+///
+/// let mut sandbox = Birdcage::new();
+/// sandbox.add_exception(Exception::DropCapabilities(dropped)).unwrap();
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct CapabilitySet(u64);
+
+impl CapabilitySet {
+    /// `CAP_AUDIT_CONTROL`.
+    pub const AUDIT_CONTROL: Self = Self(1 << 30);
+    /// `CAP_AUDIT_READ`.
+    pub const AUDIT_READ: Self = Self(1 << 37);
+    /// `CAP_AUDIT_WRITE`.
+    pub const AUDIT_WRITE: Self = Self(1 << 29);
+    /// `CAP_BLOCK_SUSPEND`.
+    pub const BLOCK_SUSPEND: Self = Self(1 << 36);
+    /// `CAP_BPF`.
+    pub const BPF: Self = Self(1 << 39);
+    /// `CAP_CHECKPOINT_RESTORE`.
+    pub const CHECKPOINT_RESTORE: Self = Self(1 << 40);
+    /// `CAP_CHOWN`.
+    pub const CHOWN: Self = Self(1 << 0);
+    /// `CAP_DAC_OVERRIDE`.
+    pub const DAC_OVERRIDE: Self = Self(1 << 1);
+    /// `CAP_DAC_READ_SEARCH`.
+    pub const DAC_READ_SEARCH: Self = Self(1 << 2);
+    /// `CAP_FOWNER`.
+    pub const FOWNER: Self = Self(1 << 3);
+    /// `CAP_FSETID`.
+    pub const FSETID: Self = Self(1 << 4);
+    /// `CAP_IPC_LOCK`.
+    pub const IPC_LOCK: Self = Self(1 << 14);
+    /// `CAP_IPC_OWNER`.
+    pub const IPC_OWNER: Self = Self(1 << 15);
+    /// `CAP_KILL`.
+    pub const KILL: Self = Self(1 << 5);
+    /// `CAP_LEASE`.
+    pub const LEASE: Self = Self(1 << 28);
+    /// `CAP_LINUX_IMMUTABLE`.
+    pub const LINUX_IMMUTABLE: Self = Self(1 << 9);
+    /// `CAP_MAC_ADMIN`.
+    pub const MAC_ADMIN: Self = Self(1 << 33);
+    /// `CAP_MAC_OVERRIDE`.
+    pub const MAC_OVERRIDE: Self = Self(1 << 32);
+    /// `CAP_MKNOD`.
+    pub const MKNOD: Self = Self(1 << 27);
+    /// `CAP_NET_ADMIN`.
+    pub const NET_ADMIN: Self = Self(1 << 12);
+    /// `CAP_NET_BIND_SERVICE`.
+    pub const NET_BIND_SERVICE: Self = Self(1 << 10);
+    /// `CAP_NET_BROADCAST`.
+    pub const NET_BROADCAST: Self = Self(1 << 11);
+    /// `CAP_NET_RAW`.
+    pub const NET_RAW: Self = Self(1 << 13);
+    /// `CAP_PERFMON`.
+    pub const PERFMON: Self = Self(1 << 38);
+    /// `CAP_SETFCAP`.
+    pub const SETFCAP: Self = Self(1 << 31);
+    /// `CAP_SETGID`.
+    pub const SETGID: Self = Self(1 << 6);
+    /// `CAP_SETPCAP`.
+    pub const SETPCAP: Self = Self(1 << 8);
+    /// `CAP_SETUID`.
+    pub const SETUID: Self = Self(1 << 7);
+    /// `CAP_SYSLOG`.
+    pub const SYSLOG: Self = Self(1 << 34);
+    /// `CAP_SYS_ADMIN`.
+    pub const SYS_ADMIN: Self = Self(1 << 21);
+    /// `CAP_SYS_BOOT`.
+    pub const SYS_BOOT: Self = Self(1 << 22);
+    /// `CAP_SYS_CHROOT`.
+    pub const SYS_CHROOT: Self = Self(1 << 18);
+    /// `CAP_SYS_MODULE`.
+    pub const SYS_MODULE: Self = Self(1 << 16);
+    /// `CAP_SYS_NICE`.
+    pub const SYS_NICE: Self = Self(1 << 23);
+    /// `CAP_SYS_PACCT`.
+    pub const SYS_PACCT: Self = Self(1 << 20);
+    /// `CAP_SYS_PTRACE`.
+    pub const SYS_PTRACE: Self = Self(1 << 19);
+    /// `CAP_SYS_RAWIO`.
+    pub const SYS_RAWIO: Self = Self(1 << 17);
+    /// `CAP_SYS_RESOURCE`.
+    pub const SYS_RESOURCE: Self = Self(1 << 24);
+    /// `CAP_SYS_TIME`.
+    pub const SYS_TIME: Self = Self(1 << 25);
+    /// `CAP_SYS_TTY_CONFIG`.
+    pub const SYS_TTY_CONFIG: Self = Self(1 << 26);
+    /// `CAP_WAKE_ALARM`.
+    pub const WAKE_ALARM: Self = Self(1 << 35);
+
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a set of all capabilities.
+    ///
+    /// This includes capabilities added by kernels newer than this crate.
+    pub fn all() -> Self {
+        Self(u64::MAX)
+    }
+
+    /// Add all capabilities of `other` to the set.
+    pub fn insert(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Remove all capabilities of `other` from the set.
+    pub fn remove(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// Remove all capabilities which are not part of `other`.
+    pub fn retain_only(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Check if all capabilities of `other` are part of the set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Check if the set contains no capabilities.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Drop the capabilities from all capability sets of the current process.
+    ///
+    /// Capabilities unsupported by the kernel are ignored. Since this removes
+    /// the capabilities from the bounding set, they cannot be regained by
+    /// executing a new program.
+    ///
+    /// This is async-signal-safe, so it can be used after `fork`.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn drop(&self) -> io::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        // Remove capabilities from the bounding set, which requires CAP_SETPCAP.
+        for cap in 0..u64::BITS {
+            if self.0 & (1 << cap) == 0 {
+                continue;
+            }
+
+            let cap = libc::c_ulong::from(cap);
+            match unsafe { libc::prctl(libc::PR_CAPBSET_READ, cap, 0, 0, 0) } {
+                // Capability is not supported by the kernel.
+                -1 => continue,
+                0 => (),
+                _ => {
+                    if unsafe { libc::prctl(libc::PR_CAPBSET_DROP, cap, 0, 0, 0) } != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                },
+            }
+        }
+
+        // Remove capabilities from the effective, permitted and inheritable sets.
+        let mut header = CapUserHeader { version: LINUX_CAPABILITY_VERSION_3, pid: 0 };
+        let mut data = [CapUserData::default(); 2];
+        if unsafe { libc::syscall(libc::SYS_capget, &mut header, data.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        for (i, data) in data.iter_mut().enumerate() {
+            let keep = !(self.0 >> (32 * i)) as u32;
+            data.effective &= keep;
+            data.permitted &= keep;
+            data.inheritable &= keep;
+        }
+
+        if unsafe { libc::syscall(libc::SYS_capset, &mut header, data.as_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Capability API version supporting 64-bit capability sets.
+#[cfg(target_os = "linux")]
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+/// Header of the `capget` and `capset` syscalls.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct CapUserHeader {
+    version: u32,
+    pid: libc::c_int,
+}
+
+/// Capability sets of the `capget` and `capset` syscalls.
+///
+/// Version 3 of the API splits each set into two 32-bit halves.
+#[cfg(target_os = "linux")]
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+struct CapUserData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
@@ -0,0 +1,202 @@
+//! Serialization format for sandbox exceptions.
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CapabilitySet, DeviceKind, Exception, ResourceLimits};
+
+/// Serialized representation of an [`Exception`].
+///
+/// Every exception is an object with its variant stored in the `type` field,
+/// like `{ "type": "Read", "path": "/usr" }`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum ExceptionRepr {
+    Read {
+        path: PathBuf,
+    },
+    ReadFile {
+        path: PathBuf,
+    },
+    ReadExact {
+        path: PathBuf,
+    },
+    ReadGlob {
+        pattern: String,
+    },
+    ReadShallow {
+        path: PathBuf,
+    },
+    ReadDir {
+        path: PathBuf,
+    },
+    #[serde(alias = "ReadWrite")]
+    WriteAndRead {
+        path: PathBuf,
+    },
+    ExecuteAndRead {
+        path: PathBuf,
+    },
+    AppendOnly {
+        path: PathBuf,
+    },
+    Deny {
+        path: PathBuf,
+    },
+    Environment {
+        key: String,
+    },
+    EnvironmentPrefix {
+        prefix: String,
+    },
+    EnvironmentPattern {
+        pattern: String,
+    },
+    FullEnvironment,
+    ProcSelf,
+    // Sorted map ensures the output is reproducible.
+    CustomEnvironment {
+        env: BTreeMap<String, String>,
+    },
+    SetEnvironment {
+        env: BTreeMap<String, String>,
+    },
+    Networking,
+    UnixSocket {
+        path: PathBuf,
+    },
+    NetworkConnect {
+        addr: SocketAddr,
+    },
+    NetworkBind {
+        addr: SocketAddr,
+    },
+    NetworkOutbound,
+    NetworkInbound,
+    NetworkProtocol {
+        #[serde(default)]
+        tcp: bool,
+        #[serde(default)]
+        udp: bool,
+    },
+    NetworkHost {
+        host: String,
+        port: Option<u16>,
+    },
+    NetworkSubnet {
+        addr: IpAddr,
+        prefix_len: u8,
+    },
+    ResourceLimits {
+        #[serde(flatten)]
+        limits: ResourceLimits,
+    },
+    Device {
+        device: DeviceKind,
+    },
+    SharedMemory {
+        name: String,
+    },
+    SharedMemoryCreate {
+        name: String,
+    },
+    DropCapabilities {
+        capabilities: CapabilitySet,
+    },
+    DropAllCapabilities,
+}
+
+impl From<Exception> for ExceptionRepr {
+    fn from(exception: Exception) -> Self {
+        match exception {
+            Exception::Read(path) => Self::Read { path },
+            Exception::ReadFile(path) => Self::ReadFile { path },
+            Exception::ReadExact(path) => Self::ReadExact { path },
+            Exception::ReadGlob(pattern) => Self::ReadGlob { pattern },
+            Exception::ReadShallow(path) => Self::ReadShallow { path },
+            Exception::ReadDir(path) => Self::ReadDir { path },
+            Exception::WriteAndRead(path) => Self::WriteAndRead { path },
+            Exception::ExecuteAndRead(path) => Self::ExecuteAndRead { path },
+            Exception::AppendOnly(path) => Self::AppendOnly { path },
+            Exception::Deny(path) => Self::Deny { path },
+            Exception::Environment(key) => Self::Environment { key },
+            Exception::EnvironmentPrefix(prefix) => Self::EnvironmentPrefix { prefix },
+            Exception::EnvironmentPattern(pattern) => Self::EnvironmentPattern { pattern },
+            Exception::FullEnvironment => Self::FullEnvironment,
+            Exception::ProcSelf => Self::ProcSelf,
+            Exception::CustomEnvironment(env) => {
+                Self::CustomEnvironment { env: env.into_iter().collect() }
+            },
+            Exception::SetEnvironment(env) => {
+                Self::SetEnvironment { env: env.into_iter().collect() }
+            },
+            Exception::Networking => Self::Networking,
+            Exception::UnixSocket(path) => Self::UnixSocket { path },
+            Exception::NetworkConnect(addr) => Self::NetworkConnect { addr },
+            Exception::NetworkBind(addr) => Self::NetworkBind { addr },
+            Exception::NetworkOutbound => Self::NetworkOutbound,
+            Exception::NetworkInbound => Self::NetworkInbound,
+            Exception::NetworkProtocol { tcp, udp } => Self::NetworkProtocol { tcp, udp },
+            Exception::NetworkHost { host, port } => Self::NetworkHost { host, port },
+            Exception::NetworkSubnet { addr, prefix_len } => {
+                Self::NetworkSubnet { addr, prefix_len }
+            },
+            Exception::ResourceLimits(limits) => Self::ResourceLimits { limits },
+            Exception::Device(device) => Self::Device { device },
+            Exception::SharedMemory(name) => Self::SharedMemory { name },
+            Exception::SharedMemoryCreate(name) => Self::SharedMemoryCreate { name },
+            Exception::DropCapabilities(capabilities) => Self::DropCapabilities { capabilities },
+            Exception::DropAllCapabilities => Self::DropAllCapabilities,
+        }
+    }
+}
+
+impl From<ExceptionRepr> for Exception {
+    fn from(repr: ExceptionRepr) -> Self {
+        match repr {
+            ExceptionRepr::Read { path } => Self::Read(path),
+            ExceptionRepr::ReadFile { path } => Self::ReadFile(path),
+            ExceptionRepr::ReadExact { path } => Self::ReadExact(path),
+            ExceptionRepr::ReadGlob { pattern } => Self::ReadGlob(pattern),
+            ExceptionRepr::ReadShallow { path } => Self::ReadShallow(path),
+            ExceptionRepr::ReadDir { path } => Self::ReadDir(path),
+            ExceptionRepr::WriteAndRead { path } => Self::WriteAndRead(path),
+            ExceptionRepr::ExecuteAndRead { path } => Self::ExecuteAndRead(path),
+            ExceptionRepr::AppendOnly { path } => Self::AppendOnly(path),
+            ExceptionRepr::Deny { path } => Self::Deny(path),
+            ExceptionRepr::Environment { key } => Self::Environment(key),
+            ExceptionRepr::EnvironmentPrefix { prefix } => Self::EnvironmentPrefix(prefix),
+            ExceptionRepr::EnvironmentPattern { pattern } => Self::EnvironmentPattern(pattern),
+            ExceptionRepr::FullEnvironment => Self::FullEnvironment,
+            ExceptionRepr::ProcSelf => Self::ProcSelf,
+            ExceptionRepr::CustomEnvironment { env } => {
+                Self::CustomEnvironment(env.into_iter().collect())
+            },
+            ExceptionRepr::SetEnvironment { env } => {
+                Self::SetEnvironment(env.into_iter().collect())
+            },
+            ExceptionRepr::Networking => Self::Networking,
+            ExceptionRepr::UnixSocket { path } => Self::UnixSocket(path),
+            ExceptionRepr::NetworkConnect { addr } => Self::NetworkConnect(addr),
+            ExceptionRepr::NetworkBind { addr } => Self::NetworkBind(addr),
+            ExceptionRepr::NetworkOutbound => Self::NetworkOutbound,
+            ExceptionRepr::NetworkInbound => Self::NetworkInbound,
+            ExceptionRepr::NetworkProtocol { tcp, udp } => Self::NetworkProtocol { tcp, udp },
+            ExceptionRepr::NetworkHost { host, port } => Self::NetworkHost { host, port },
+            ExceptionRepr::NetworkSubnet { addr, prefix_len } => {
+                Self::NetworkSubnet { addr, prefix_len }
+            },
+            ExceptionRepr::ResourceLimits { limits } => Self::ResourceLimits(limits),
+            ExceptionRepr::Device { device } => Self::Device(device),
+            ExceptionRepr::SharedMemory { name } => Self::SharedMemory(name),
+            ExceptionRepr::SharedMemoryCreate { name } => Self::SharedMemoryCreate(name),
+            ExceptionRepr::DropCapabilities { capabilities } => {
+                Self::DropCapabilities(capabilities)
+            },
+            ExceptionRepr::DropAllCapabilities => Self::DropAllCapabilities,
+        }
+    }
+}
@@ -2,9 +2,7 @@
 
 use std::error::Error as StdError;
 use std::fmt::{self, Display, Formatter};
-use std::io::Error as IoError;
-#[cfg(target_os = "linux")]
-use std::io::ErrorKind as IoErrorKind;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::path::PathBuf;
 use std::result::Result as StdResult;
 
@@ -21,14 +19,62 @@ pub enum Error {
     #[cfg(target_os = "linux")]
     Seccomp(SeccompError),
 
+    /// Seccomp filters cannot be installed.
+    ///
+    /// See `LinuxSandbox::seccomp_best_effort`.
+    #[cfg(target_os = "linux")]
+    SeccompUnavailable,
+
     /// Invalid sandbox exception path.
     InvalidPath(PathBuf),
 
+    /// Sandbox exception path does not exist.
+    PathNotFound(PathBuf),
+
+    /// Path has both an allow and a deny exception.
+    ConflictingPath(PathBuf),
+
+    /// Symlink resolves outside of the directory containing it.
+    ///
+    /// See [`Sandbox::restrict_symlink_escape`].
+    ///
+    /// [`Sandbox::restrict_symlink_escape`]: crate::Sandbox::restrict_symlink_escape
+    SymlinkEscape(PathBuf),
+
     /// I/O error.
     Io(IoError),
 
     /// Sandbox activation failed.
     ActivationFailed(String),
+
+    /// Exception cannot be enforced on this platform.
+    UnsupportedException(String),
+
+    /// Resource limit cannot be applied.
+    InvalidResourceLimit(String),
+
+    /// Subnet prefix length exceeds the size of its address.
+    InvalidSubnet(String),
+
+    /// Environment variable cannot be set, due to an empty key, a `=` in its
+    /// key or a NUL byte in its key or value.
+    InvalidEnvVar(String),
+
+    /// Working directory of the sandboxee is not covered by any exception.
+    CurrentDirNotAccessible(PathBuf),
+
+    /// Current process was already sandboxed.
+    AlreadyLocked,
+}
+
+impl Error {
+    /// Create an error for a path which could not be resolved.
+    pub(crate) fn from_path(path: PathBuf, error: &IoError) -> Self {
+        match error.kind() {
+            IoErrorKind::NotFound => Self::PathNotFound(path),
+            _ => Self::InvalidPath(path),
+        }
+    }
 }
 
 impl StdError for Error {}
@@ -38,7 +84,16 @@ impl Display for Error {
         match self {
             #[cfg(target_os = "linux")]
             Self::Seccomp(error) => write!(f, "seccomp error: {error}"),
+            #[cfg(target_os = "linux")]
+            Self::SeccompUnavailable => write!(f, "seccomp is unavailable"),
             Self::InvalidPath(path) => write!(f, "invalid path: {path:?}"),
+            Self::PathNotFound(path) => write!(f, "path not found: {path:?}"),
+            Self::ConflictingPath(path) => {
+                write!(f, "conflicting allow and deny exceptions for path: {path:?}")
+            },
+            Self::SymlinkEscape(path) => {
+                write!(f, "symlink escapes its parent directory: {path:?}")
+            },
             #[cfg(target_os = "linux")]
             Self::Io(error) if error.kind() == IoErrorKind::Unsupported => {
                 write!(
@@ -50,6 +105,14 @@ impl Display for Error {
             Self::ActivationFailed(error) => {
                 write!(f, "failed to initialize a sufficient sandbox: {error}")
             },
+            Self::UnsupportedException(error) => write!(f, "unsupported exception: {error}"),
+            Self::InvalidResourceLimit(error) => write!(f, "invalid resource limit: {error}"),
+            Self::InvalidSubnet(subnet) => write!(f, "invalid subnet: {subnet}"),
+            Self::InvalidEnvVar(key) => write!(f, "invalid environment variable: {key:?}"),
+            Self::CurrentDirNotAccessible(path) => {
+                write!(f, "working directory {path:?} is not accessible inside the sandbox")
+            },
+            Self::AlreadyLocked => write!(f, "current process is already sandboxed"),
         }
     }
 }
@@ -0,0 +1,184 @@
+//! Path glob expansion.
+//!
+//! Globs are expanded against the current filesystem, since neither Landlock
+//! nor seatbelt profiles can match paths using wildcards.
+
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+use std::{env, fs};
+
+use crate::error::{Error, Result};
+
+/// Get all existing paths matching a glob pattern.
+///
+/// Relative patterns are resolved against the current working directory.
+/// Fails with [`Error::PathNotFound`] if no path matches the pattern.
+pub(crate) fn expand(pattern: &str) -> Result<Vec<PathBuf>> {
+    let pattern_path = Path::new(pattern);
+    let mut paths =
+        if pattern_path.is_absolute() { vec![PathBuf::new()] } else { vec![env::current_dir()?] };
+
+    for component in pattern_path.components() {
+        let segment = match component {
+            Component::Normal(segment) => segment,
+            component => {
+                paths.iter_mut().for_each(|path| path.push(component));
+                continue;
+            },
+        };
+
+        // Avoid listing directories for components without wildcards.
+        let segment_pattern = match segment.to_str() {
+            Some(segment) if has_wildcards(segment) => segment,
+            _ => {
+                paths.iter_mut().for_each(|path| path.push(segment));
+                continue;
+            },
+        };
+
+        let mut matches = Vec::new();
+        for path in &paths {
+            let Ok(entries) = fs::read_dir(path) else { continue };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                if segment_matches(segment_pattern, &name) {
+                    matches.push(path.join(name));
+                }
+            }
+        }
+        matches.sort_unstable();
+        paths = matches;
+    }
+
+    paths.retain(|path| path.exists());
+    if paths.is_empty() {
+        return Err(Error::PathNotFound(pattern.into()));
+    }
+
+    Ok(paths)
+}
+
+/// Check if a path segment contains any glob wildcards.
+fn has_wildcards(segment: &str) -> bool {
+    segment.contains(['*', '?', '['])
+}
+
+/// Check if a file name matches the glob pattern for a single path segment.
+///
+/// Like in shells, wildcards do not match a leading `.`.
+fn segment_matches(pattern: &str, name: &OsStr) -> bool {
+    let Some(name) = name.to_str() else { return false };
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+
+    let pattern = tokenize(pattern);
+    let text: Vec<char> = name.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was matched against.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(Token::Star) => {
+                backtrack = Some((p, t));
+                p += 1;
+            },
+            Some(token) if token.matches(text[t]) => {
+                p += 1;
+                t += 1;
+            },
+            // Let the last `*` consume one more character.
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|token| *token == Token::Star)
+}
+
+/// Split a glob pattern into its tokens.
+///
+/// Unterminated character classes are treated as literal characters.
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut tokens = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => tokens.push(Token::Star),
+            '?' => tokens.push(Token::Any),
+            '[' => match parse_class(&chars[i + 1..]) {
+                Some((class, len)) => {
+                    tokens.push(class);
+                    i += len;
+                },
+                None => tokens.push(Token::Literal('[')),
+            },
+            c => tokens.push(Token::Literal(c)),
+        }
+        i += 1;
+    }
+
+    tokens
+}
+
+/// Parse a character class following its opening `[`.
+///
+/// Returns the class and the number of characters consumed, including the
+/// closing `]`.
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    let mut i = usize::from(negated);
+
+    let mut ranges = Vec::new();
+    // A leading `]` is part of the class instead of closing it.
+    let start = i;
+    while i < chars.len() && (chars[i] != ']' || i == start) {
+        match chars.get(i + 1..i + 3) {
+            Some(['-', end]) if *end != ']' => {
+                ranges.push((chars[i], *end));
+                i += 3;
+            },
+            _ => {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            },
+        }
+    }
+
+    (i < chars.len()).then_some((Token::Class { negated, ranges }, i + 1))
+}
+
+/// Glob pattern token.
+#[derive(PartialEq, Eq)]
+enum Token {
+    /// Any number of characters (`*`).
+    Star,
+    /// Exactly one character (`?`).
+    Any,
+    /// One character of a class like `[a-z]`, or none of it like `[!a-z]`.
+    Class { negated: bool, ranges: Vec<(char, char)> },
+    /// A literal character.
+    Literal(char),
+}
+
+impl Token {
+    /// Check if a single character matches this token.
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Self::Star | Self::Any => true,
+            Self::Class { negated, ranges } => {
+                ranges.iter().any(|(start, end)| (*start..=*end).contains(&c)) != *negated
+            },
+            Self::Literal(literal) => *literal == c,
+        }
+    }
+}
@@ -7,6 +7,7 @@
 //!
 //! ```rust
 //! use std::collections::HashMap;
+//!
 //! use birdcage::process::Command;
 //! use birdcage::{Birdcage, Exception, Sandbox};
 //!
@@ -15,8 +16,9 @@
 //!
 //! // Allow access to our test executable
 //! sandbox.add_exception(Exception::ExecuteAndRead("/bin/cat".into())).unwrap();
-//! let _ = sandbox.add_exception(Exception::ExecuteAndRead("/lib64".into()));
-//! let _ = sandbox.add_exception(Exception::ExecuteAndRead("/lib".into()));
+//! for exception in Exception::platform_libraries() {
+//!     sandbox.add_exception(exception).unwrap();
+//! }
 //!
 //! // Set custom environment variables (replaces all existing environment)
 //! let mut custom_env = HashMap::new();
@@ -33,24 +35,107 @@
 //! let status = child.wait().unwrap();
 //! assert!(!status.success()); // Should fail due to file access restrictions
 //! ```
+//!
+//! # Capturing Output
+//!
+//! Like with [`std::process::Command`], the sandboxee's standard streams can
+//! be redirected using [`Stdio`](process::Stdio). Piped streams are available
+//! on the returned [`Child`]:
+//!
+//! ```rust
+//! use birdcage::process::{Command, Stdio};
+//! use birdcage::{Birdcage, Exception, Sandbox};
+//!
+//! let mut sandbox = Birdcage::new();
+//! sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+//!
+//! let mut command = Command::new("echo");
+//! command.arg("hello");
+//! command.stdout(Stdio::piped());
+//! let child = sandbox.spawn(command).unwrap();
+//!
+//! let output = child.wait_with_output().unwrap();
+//! assert_eq!(output.stdout, b"hello\n");
+//! ```
+//!
+//! # Configuration Files
+//!
+//! With the `serde` feature enabled, a [`SandboxConfig`] can be loaded from
+//! any format supported by serde and turned into a sandbox using
+//! [`Sandbox::from_config`]. In TOML, a configuration looks like this:
+//!
+//! ```toml
+//! description = "Allow building crates"
+//! version = "1.0.0"
+//! exceptions = [
+//!     { type = "ExecuteAndRead", path = "/usr" },
+//!     { type = "WriteAndRead", path = "./target" },
+//!     { type = "Environment", key = "PATH" },
+//!     { type = "NetworkHost", host = "index.crates.io", port = 443 },
+//!     { type = "NetworkConnect", addr = "127.0.0.1:8080" },
+//!     { type = "Networking" },
+//! ]
+//! ```
+//!
+//! Each exception is an object with its variant in the `type` field. Paths are
+//! stored as-is, relative paths are resolved when the exception is added.
+//!
+//! A plain list of exceptions can be loaded as [`Policy`] instead.
+//!
+//! # Logging
+//!
+//! With the `log-events` feature enabled, every major step of the sandbox
+//! setup is logged at the debug level using the [`log`] crate. This includes
+//! all applied path exceptions, Landlock rulesets, seccomp filters,
+//! environment filtering and the final execution of the sandboxee.
+//!
+//! Events use the `birdcage` target, with platform-specific steps using
+//! `birdcage::linux` or `birdcage::macos`.
 
 use std::collections::HashMap;
 use std::env;
-use std::path::PathBuf;
+use std::fmt::{self, Display, Formatter};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Component, Path, PathBuf};
 
-use crate::error::Result;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use crate::capability::CapabilitySet;
+use crate::error::{Error, Result};
 #[cfg(target_os = "linux")]
 use crate::linux::LinuxSandbox;
+#[cfg(target_os = "linux")]
+pub use crate::linux::{
+    IdMap, NetworkNamespace, ProcAccess, SandboxMode, SandboxViolation, SeccompAction, Syscall,
+};
+#[cfg(target_os = "macos")]
+pub use crate::macos::MacBackend;
 #[cfg(target_os = "macos")]
 use crate::macos::MacSandbox;
-use crate::process::{Child, Command};
+use crate::process::{Child, Command, Output};
+pub use crate::resource::ResourceLimits;
+
+/// Log a sandbox setup event, if the `log-events` feature is enabled.
+macro_rules! event {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log-events")]
+        log::debug!($($arg)+);
+    };
+}
 
+mod capability;
+#[cfg(feature = "serde")]
+mod config;
 pub mod error;
+mod glob;
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 pub mod process;
+mod resource;
 
 /// Default platform sandbox.
 ///
@@ -66,6 +151,25 @@ pub type Birdcage = LinuxSandbox;
 #[cfg(target_os = "macos")]
 pub type Birdcage = MacSandbox;
 
+/// Detect which sandbox features can be enforced on the current system.
+///
+/// Unlike [`Sandbox::supported_exceptions`], this probes the kernel's
+/// configuration at call time, allowing applications to degrade gracefully
+/// before attempting to spawn a sandboxee.
+///
+/// ```rust
+/// use birdcage::{Birdcage, Exception, Sandbox};
+///
+/// let mut sandbox = Birdcage::new();
+/// if !birdcage::capabilities().network_filtering {
+///     // Fall back to full network access.
+///     sandbox.add_exception(Exception::Networking).unwrap();
+/// }
+/// ```
+pub fn capabilities() -> Capabilities {
+    Birdcage::capabilities()
+}
+
 pub trait Sandbox: Sized {
     /// Setup the sandboxing environment.
     fn new() -> Self;
@@ -73,108 +177,1982 @@ pub trait Sandbox: Sized {
     /// Add a new exception to the sandbox.
     ///
     /// Exceptions added for symlinks will also automatically apply to the
-    /// symlink's target.
+    /// symlink's target. Chains of symlinks are resolved completely, keeping
+    /// every intermediate symlink accessible.
     fn add_exception(&mut self, exception: Exception) -> Result<&mut Self>;
 
+    /// Add a new exception to the sandbox, taking ownership of it.
+    ///
+    /// This behaves like [`Sandbox::add_exception`], but allows building a
+    /// sandbox in a single expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// # fn main() -> birdcage::error::Result<()> {
+    /// let sandbox = Birdcage::new()
+    ///     .with_exception(Exception::ExecuteAndRead("/usr".into()))?
+    ///     .with_exception(Exception::WriteAndRead("/tmp".into()))?
+    ///     .with_exception(Exception::Environment("PATH".into()))?
+    ///     .with_exception(Exception::Networking)?;
+    /// # assert_eq!(sandbox.exceptions().len(), 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn with_exception(mut self, exception: Exception) -> Result<Self> {
+        self.add_exception(exception)?;
+        Ok(self)
+    }
+
+    /// Create a builder for the sandbox.
+    ///
+    /// See [`SandboxBuilder`].
+    fn builder() -> SandboxBuilder<Self> {
+        SandboxBuilder { sandbox: Self::new() }
+    }
+
+    /// Add a new exception to the sandbox, ignoring missing paths.
+    ///
+    /// This behaves like [`Sandbox::add_exception`], but silently skips
+    /// exceptions which fail with [`Error::PathNotFound`]. This is useful for
+    /// optional paths which do not exist on all systems.
+    ///
+    /// All other errors are still returned.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception_lenient(Exception::ExecuteAndRead("/lib64".into())).unwrap();
+    /// sandbox.add_exception_lenient(Exception::Read("/does/not/exist".into())).unwrap();
+    /// ```
+    fn add_exception_lenient(&mut self, exception: Exception) -> Result<&mut Self> {
+        match self.add_exception(exception).map(|_| ()) {
+            Ok(()) | Err(Error::PathNotFound(_)) => Ok(self),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Setup the sandboxing environment from a configuration.
+    ///
+    /// All exceptions are added in order, like they would be with
+    /// [`Sandbox::add_exception`].
+    fn from_config(config: SandboxConfig) -> Result<Self> {
+        let mut sandbox = Self::new();
+        sandbox.merge(config.exceptions)?;
+        Ok(sandbox)
+    }
+
+    /// Add multiple exceptions to the sandbox.
+    ///
+    /// All exceptions are added in order, like they would be with
+    /// [`Sandbox::add_exception`]. Conflicting exceptions are resolved the
+    /// same way, so an [`Exception::CustomEnvironment`] still takes precedence
+    /// over any [`Exception::Environment`].
+    ///
+    /// Stops at the first exception which could not be added.
+    ///
+    /// Since all rules are only created once the sandbox is activated, adding
+    /// exceptions for many paths individually has no additional cost for
+    /// the kernel's ruleset.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// let paths = [std::env::temp_dir(), std::env::current_dir().unwrap()];
+    /// sandbox.merge(paths.map(Exception::WriteAndRead)).unwrap();
+    /// # assert_eq!(sandbox.exceptions().len(), 2);
+    /// ```
+    fn merge(&mut self, exceptions: impl IntoIterator<Item = Exception>) -> Result<&mut Self> {
+        for exception in exceptions {
+            self.add_exception(exception)?;
+        }
+        Ok(self)
+    }
+
+    /// Allow access to multiple environment variables.
+    ///
+    /// This adds an [`Exception::Environment`] for every variable name.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.allow_env_vars(["PATH", "HOME", "LANG"].map(String::from)).unwrap();
+    /// # assert_eq!(sandbox.exceptions().len(), 3);
+    /// ```
+    fn allow_env_vars(&mut self, keys: impl IntoIterator<Item = String>) -> Result<&mut Self> {
+        self.merge(keys.into_iter().map(Exception::Environment))
+    }
+
+    /// Allow access to all environment variables, except for `denied` ones.
+    ///
+    /// This adds an [`Exception::Environment`] for every variable of the
+    /// current process which is not part of `denied`. Variables set after
+    /// calling this method are removed from the sandboxee's environment.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.inherit_env_except(&["AWS_SECRET_ACCESS_KEY".into()]).unwrap();
+    /// ```
+    fn inherit_env_except(&mut self, denied: &[String]) -> Result<&mut Self> {
+        let keys = env::vars_os().filter_map(|(key, _)| key.into_string().ok());
+        self.allow_env_vars(keys.filter(|key| !denied.contains(key)))
+    }
+
+    /// Allow the dynamic loader's library search path variables.
+    ///
+    /// Since environment variables are removed unless allowed by an
+    /// exception, executables relying on variables like `LD_LIBRARY_PATH` to
+    /// locate their shared libraries might fail to start inside the sandbox.
+    /// [`Sandbox::validate`] reports these variables with
+    /// [`SandboxWarning::LoaderEnvironmentRemoved`].
+    ///
+    /// This adds an [`Exception::Environment`] for `LD_LIBRARY_PATH` on Linux
+    /// and for `DYLD_LIBRARY_PATH`, `DYLD_FALLBACK_LIBRARY_PATH` and
+    /// `DYLD_FRAMEWORK_PATH` on macOS. Variables affecting which libraries
+    /// are loaded, like `LD_PRELOAD`, are not included.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::ExecuteAndRead("/usr/bin".into())).unwrap();
+    /// sandbox.preserve_loader_env().unwrap();
+    /// ```
+    fn preserve_loader_env(&mut self) -> Result<&mut Self> {
+        self.allow_env_vars(LOADER_ENV_VARS.iter().map(|key| key.to_string()))
+    }
+
+    /// Add all exceptions of another sandbox to this sandbox.
+    ///
+    /// This allows combining reusable policy fragments, see
+    /// [`Sandbox::merge`]. Exceptions which are already part of this sandbox
+    /// are skipped. The combined sandbox is checked using
+    /// [`Sandbox::validate`], ignoring all warnings.
+    fn extend(&mut self, other: &Self) -> Result<&mut Self> {
+        self.merge(other.exceptions().to_vec())?;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Check if this sandbox grants no access beyond another sandbox.
+    ///
+    /// Every exception granting access must be implied by an exception of
+    /// `other` granting at least the same access, like an [`Exception::Read`]
+    /// for a parent directory or [`Exception::FullEnvironment`] for an
+    /// [`Exception::Environment`]. Restrictions like [`Exception::Deny`] or
+    /// [`Exception::ResourceLimits`] only ever reduce access, so they are not
+    /// required in `other`.
+    ///
+    /// Paths denied by `other` must also be denied by this sandbox, if they
+    /// overlap with any of its path exceptions. Host networking must be
+    /// restricted to at least the same protocols with
+    /// [`Exception::NetworkProtocol`]. Settings other than exceptions are not
+    /// compared.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// # fn main() -> birdcage::error::Result<()> {
+    /// let base = Birdcage::new().with_exception(Exception::Read("/usr".into()))?;
+    /// let plugin = Birdcage::new().with_exception(Exception::Read("/usr/share".into()))?;
+    ///
+    /// assert!(plugin.is_subset_of(&base));
+    /// assert!(!base.is_subset_of(&plugin));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn is_subset_of(&self, other: &Self) -> bool {
+        let (exceptions, other_exceptions) = (self.exceptions(), other.exceptions());
+
+        let protocols = host_network_protocols(exceptions);
+        let other_protocols = host_network_protocols(other_exceptions);
+        match (protocols, other_protocols) {
+            (None, _) => (),
+            (Some(_), None) => return false,
+            (Some(protocols), Some(other_protocols)) => {
+                if !protocols.is_subset_of(&other_protocols) {
+                    return false;
+                }
+            },
+        }
+
+        exceptions.iter().filter(|exception| exception.is_grant()).all(|exception| {
+            // Host networking does not imply TCP exceptions if TCP is not allowed.
+            let tcp = other_protocols.is_some_and(|protocols| protocols.tcp);
+            let implied = other_exceptions.iter().any(|other| {
+                other.implies(exception) && (tcp || !matches!(other, Exception::Networking))
+            });
+
+            // Paths denied by `other` must be denied by this sandbox too.
+            let denied = exception.grant_path().is_some_and(|path| {
+                other_exceptions.iter().any(|other| match other {
+                    Exception::Deny(denied) if denied.starts_with(path) || path.starts_with(denied) => {
+                        !exceptions.iter().any(|exception| {
+                            matches!(exception, Exception::Deny(own) if denied.starts_with(own))
+                        })
+                    },
+                    _ => false,
+                })
+            });
+
+            implied && !denied
+        })
+    }
+
+    /// Create a copy of the sandbox's configuration.
+    ///
+    /// Since spawning consumes the sandbox, this allows spawning multiple
+    /// sandboxees with the same policy. Restrictions are only set up once a
+    /// sandbox is spawned, so no OS resources are shared between the copies.
+    ///
+    /// On Linux, temporary directories are not copied. The copy can still
+    /// access them, but they are removed together with the original sandbox.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use birdcage::process::Command;
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    ///
+    /// let first = sandbox.clone_config().spawn(Command::new("true")).unwrap();
+    /// let second = sandbox.spawn(Command::new("true")).unwrap();
+    /// # assert!(first.wait_with_output().unwrap().status.success());
+    /// # assert!(second.wait_with_output().unwrap().status.success());
+    /// ```
+    fn clone_config(&self) -> Self;
+
+    /// Control whether the sandboxee can spawn subprocesses.
+    ///
+    /// Subprocesses are allowed by default. If they are denied, the sandboxee
+    /// can still replace itself using `execve` and create threads, but any
+    /// attempt to fork fails. Executables allowed by
+    /// [`Exception::ExecuteAndRead`] can then only be run by replacing the
+    /// sandboxee itself.
+    ///
+    /// On Linux, this uses seccomp to deny `fork`, `vfork` and `clone`
+    /// without `CLONE_THREAD`. On macOS, `process-fork` is denied by the
+    /// sandbox profile, which is only supported by
+    /// [`Sandbox::spawn_isolated`] and [`Sandbox::lock`].
+    fn allow_subprocesses(&mut self, allow: bool) -> &mut Self;
+
+    /// Reject path exceptions reaching outside of their tree through symlinks.
+    ///
+    /// By default, exceptions for symlinks also apply to their targets, see
+    /// [`Sandbox::add_exception`]. If an attacker can place symlinks in a
+    /// directory which is later allowed using [`Exception::ReadShallow`] or
+    /// [`Exception::ReadGlob`], or in a path passed to an exception, they
+    /// could point them at files like `/etc/shadow` to grant the sandboxee
+    /// access to arbitrary files.
+    ///
+    /// When enabled, every symlink in an exception's path must resolve to a
+    /// location beneath the directory containing the symlink. Otherwise
+    /// adding the exception fails with [`Error::SymlinkEscape`], while
+    /// [`Exception::ReadShallow`] skips the offending entries. Symlinks
+    /// beneath an allowed directory are not affected, since their targets are
+    /// only accessible if they are allowed by another exception.
+    ///
+    /// This must be enabled before adding exceptions and is disabled by
+    /// default.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.restrict_symlink_escape(true);
+    /// sandbox.add_exception(Exception::Read("/usr/share".into())).unwrap();
+    /// ```
+    fn restrict_symlink_escape(&mut self, restrict: bool) -> &mut Self;
+
+    /// Resolve relative exception paths against a base directory.
+    ///
+    /// Relative paths of exceptions added afterwards are joined to `path`,
+    /// instead of being resolved against the current working directory. This
+    /// includes the patterns of [`Exception::ReadGlob`].
+    ///
+    /// Fails with [`Error::InvalidPath`] if `path` is not a directory.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.set_base_dir("/usr".into()).unwrap();
+    /// sandbox.add_exception(Exception::Read("bin".into())).unwrap();
+    /// # assert!(sandbox.exceptions().contains(&Exception::Read("/usr/bin".into())));
+    /// ```
+    fn set_base_dir(&mut self, path: PathBuf) -> Result<&mut Self>;
+
+    /// Query which exceptions and features are supported.
+    ///
+    /// This can be used to detect platform capabilities at runtime, without
+    /// constructing a sandbox. Some features depend on the running kernel, so
+    /// the result might differ between systems using the same platform.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let log = std::env::temp_dir().join("birdcage.log");
+    /// # std::fs::write(&log, "").unwrap();
+    ///
+    /// // Fall back to write access if truncation cannot be prevented.
+    /// let mut sandbox = Birdcage::new();
+    /// if Birdcage::supported_exceptions().append_only {
+    ///     sandbox.add_exception(Exception::AppendOnly(log)).unwrap();
+    /// } else {
+    ///     sandbox.add_exception(Exception::WriteAndRead(log)).unwrap();
+    /// }
+    /// ```
+    fn supported_exceptions() -> SupportedExceptions;
+
     /// Setup sandbox and spawn a new process.
     ///
     /// This will setup the sandbox in the **CURRENT** process, before launching
     /// the sandboxee. Since most of the restrictions will also be applied to
     /// the calling process, it is recommended to create a separate process
-    /// before calling this method. The calling process is **NOT** fully
-    /// sandboxed.
+    /// before calling this method or to use [`Sandbox::spawn_isolated`]
+    /// instead. The calling process is **NOT** fully sandboxed.
     ///
     /// # Errors
     ///
     /// Sandboxing will fail if the calling process is not single-threaded.
     ///
+    /// If the sandboxee's working directory is not accessible through any
+    /// exception, spawning fails with [`Error::CurrentDirNotAccessible`]
+    /// before any restrictions are applied.
+    ///
     /// After failure, the calling process might still be affected by partial
     /// sandboxing restrictions.
     fn spawn(self, sandboxee: Command) -> Result<Child>;
-}
 
-/// Sandboxing exception rule.
-///
-/// An exception excludes certain resources from the sandbox, allowing sandboxed
-/// applications to still access these resources.
-#[derive(Debug, Clone)]
-pub enum Exception {
-    /// Allow read access to the path and anything beneath it.
-    Read(PathBuf),
+    /// Setup sandbox and spawn a new process, without restricting the current
+    /// process.
+    ///
+    /// This behaves like [`Sandbox::spawn`], but none of the restrictions will
+    /// be applied to the calling process.
+    ///
+    /// On Linux, the sandbox is already set up in a separate init process, so
+    /// this only moves filtering of environment variables into it.
+    ///
+    /// On macOS, the Seatbelt profile is applied in the forked child right
+    /// before the sandboxee is executed, while environment variables are
+    /// passed to the child explicitly.
+    ///
+    /// # Errors
+    ///
+    /// Sandboxing will fail if the calling process is not single-threaded.
+    ///
+    /// Like [`Sandbox::spawn`], an inaccessible working directory is rejected
+    /// with [`Error::CurrentDirNotAccessible`].
+    fn spawn_isolated(self, sandboxee: Command) -> Result<Child>;
 
-    /// Allow writing and reading the path and anything beneath it.
-    WriteAndRead(PathBuf),
+    /// Run a sandboxed process to completion, collecting all of its output.
+    ///
+    /// Like [`std::process::Command::output`], the sandboxee's stdout and
+    /// stderr are always captured. The sandboxee is spawned using
+    /// [`Sandbox::spawn_isolated`], so the calling process is not restricted.
+    ///
+    /// ```rust
+    /// use birdcage::process::Command;
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::ExecuteAndRead("/".into())).unwrap();
+    ///
+    /// let mut command = Command::new("echo");
+    /// command.arg("hello");
+    /// let output = sandbox.output(command).unwrap();
+    ///
+    /// assert!(output.status.success());
+    /// assert_eq!(output.stdout, b"hello\n");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Spawning fails for the same reasons as [`Sandbox::spawn_isolated`].
+    fn output(self, mut sandboxee: Command) -> Result<Output> {
+        sandboxee.stdout(process::Stdio::piped());
+        sandboxee.stderr(process::Stdio::piped());
+        let child = self.spawn_isolated(sandboxee)?;
+        Ok(child.wait_with_output()?)
+    }
 
-    /// Allow executing and reading the path and anything beneath it.
+    /// Setup sandbox for the current process.
     ///
-    /// This is grouped with reading as a convenience, since execution will
-    /// always also require read access.
-    ExecuteAndRead(PathBuf),
+    /// This will apply all restrictions to the **CURRENT** process, without
+    /// spawning a new one. There is no way to lift the restrictions
+    /// afterwards, so any resources required later on must be allowed by an
+    /// exception.
+    ///
+    /// On Linux, the process stays in its original PID namespace, so the
+    /// host's procfs remains accessible.
+    ///
+    /// # Errors
+    ///
+    /// Sandboxing will fail if the calling process is not single-threaded.
+    ///
+    /// Only one sandbox can be locked per process, additional calls will
+    /// return [`Error::AlreadyLocked`]. Calls rejecting unsupported settings
+    /// before any restrictions were applied can be retried.
+    ///
+    /// After failure, the calling process might still be affected by partial
+    /// sandboxing restrictions.
+    ///
+    /// [`Error::AlreadyLocked`]: crate::error::Error::AlreadyLocked
+    fn lock(self) -> Result<()>;
 
-    /// Allow reading an environment variable.
-    Environment(String),
+    /// Check the sandbox's exceptions for problems.
+    ///
+    /// Returns warnings for exceptions which are valid, but likely do not
+    /// behave as intended. Errors are returned for exceptions which cannot be
+    /// enforced.
+    ///
+    /// This is automatically called by [`Sandbox::spawn`],
+    /// [`Sandbox::spawn_isolated`] and [`Sandbox::lock`], which will fail on
+    /// errors but ignore all warnings.
+    fn validate(&self) -> Result<Vec<SandboxWarning>>;
 
-    /// Allow reading **all** environment variables.
-    FullEnvironment,
+    /// Describe the restrictions which would be applied by [`Sandbox::spawn`].
+    ///
+    /// On Linux this is a summary of all mounts, environment filtering and
+    /// system call filters, while on macOS it is the generated Seatbelt
+    /// profile. The format is intended for humans and may change between
+    /// releases.
+    fn profile_preview(&self) -> String;
 
-    /// Replace all environment variables with a custom map.
+    /// Get all exceptions added to the sandbox.
     ///
-    /// This completely replaces the environment with the provided variables.
-    /// If this exception is set, `Environment` and `FullEnvironment` exceptions
-    /// are ignored. If multiple `CustomEnvironment` exceptions are added, the
-    /// last one takes precedence.
+    /// Exceptions are returned in their normalized form, with canonicalized
+    /// paths and all resource limits merged into a single exception.
+    fn exceptions(&self) -> &[Exception];
+
+    /// Describe the sandbox's policy for security audits.
     ///
-    /// # Examples
+    /// This lists all exceptions with their resolved paths, followed by the
+    /// rules generated for the platform, as described by
+    /// [`Sandbox::profile_preview`]. On Linux, symlinks followed to resolve an
+    /// exception's path are listed with their targets. Like the preview, the
+    /// format is intended for humans and may change between releases.
     ///
     /// ```rust
-    /// use std::collections::HashMap;
     /// use birdcage::{Birdcage, Exception, Sandbox};
     ///
-    /// let mut custom_env = HashMap::new();
-    /// custom_env.insert("PATH".to_string(), "/usr/bin".to_string());
-    /// custom_env.insert("HOME".to_string(), "/tmp".to_string());
-    ///
     /// let mut sandbox = Birdcage::new();
-    /// sandbox.add_exception(Exception::CustomEnvironment(custom_env)).unwrap();
+    /// sandbox.add_exception(Exception::Read("/usr/bin".into())).unwrap();
+    ///
+    /// let description = sandbox.to_policy_description();
+    /// assert!(description.contains("Read(\"/usr/bin\")"));
     /// ```
-    CustomEnvironment(HashMap<String, String>),
+    fn to_policy_description(&self) -> String {
+        let mut description = String::from("exceptions:\n");
+        if self.exceptions().is_empty() {
+            description.push_str("  none\n");
+        }
+        for exception in self.exceptions() {
+            description.push_str(&format!("  {exception:?}\n"));
+        }
 
-    /// Allow networking.
-    Networking,
+        description.push_str("\nrules:\n");
+        for line in self.profile_preview().lines() {
+            description.push_str(&format!("  {line}\n"));
+        }
+
+        description
+    }
 }
 
-/// Restrict access to environment variables.
-pub(crate) fn restrict_env_variables(exceptions: &[String]) {
-    restrict_env_variables_with_custom(exceptions, None);
+/// Sandbox configuration.
+///
+/// This allows storing sandbox exceptions alongside other configuration, see
+/// [`Sandbox::from_config`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct SandboxConfig {
+    /// Human-readable description of the sandbox.
+    pub description: Option<String>,
+
+    /// Version of the configuration.
+    pub version: Option<String>,
+
+    /// Sandbox exceptions.
+    pub exceptions: Vec<Exception>,
 }
 
-/// Restrict access to environment variables, optionally replacing with custom map.
+/// List of sandbox exceptions.
 ///
-/// If `custom_env` is provided, all existing environment variables are cleared
-/// and replaced with the variables from the map. Otherwise, variables not in
-/// the `exceptions` list are removed.
-pub(crate) fn restrict_env_variables_with_custom(
-    exceptions: &[String],
-    custom_env: Option<&HashMap<String, String>>,
-) {
-    match custom_env {
-        Some(env_map) => {
-            // Clear all existing environment variables
-            for (key, _) in env::vars() {
-                env::remove_var(key);
-            }
+/// Unlike [`SandboxConfig`], this is serialized as a plain list of exceptions.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct Policy {
+    /// Sandbox exceptions.
+    pub exceptions: Vec<Exception>,
+}
 
-            // Set custom environment variables
-            for (key, value) in env_map {
-                env::set_var(key, value);
-            }
-        }
-        None => {
-            // Invalid unicode will cause `env::vars()` to panic, so we don't have to worry
-            // about them getting ignored.
-            for (key, _) in env::vars().filter(|(key, _)| !exceptions.contains(key)) {
-                env::remove_var(key);
-            }
-        }
+impl Policy {
+    /// Add all exceptions to a sandbox.
+    ///
+    /// Exceptions are added in order, stopping at the first error.
+    pub fn apply(self, sandbox: &mut impl Sandbox) -> Result<()> {
+        sandbox.merge(self.exceptions)?;
+        Ok(())
+    }
+}
+
+impl From<Vec<Exception>> for Policy {
+    fn from(exceptions: Vec<Exception>) -> Self {
+        Self { exceptions }
+    }
+}
+
+/// Builder for network exceptions.
+///
+/// A network policy combines multiple network exceptions, which can be added
+/// to a sandbox using [`Sandbox::merge`].
+///
+/// # Examples
+///
+/// ```rust
+/// use birdcage::{Birdcage, NetworkPolicy, Sandbox};
+///
+/// let policy = NetworkPolicy::new()
+///     .allow_loopback()
+///     .allow_host("index.crates.io", Some(443))
+///     .allow_tcp_connect("192.0.2.1:8080".parse().unwrap());
+///
+/// let mut sandbox = Birdcage::new();
+/// sandbox.merge(policy).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkPolicy {
+    exceptions: Vec<Exception>,
+}
+
+impl NetworkPolicy {
+    /// Create a policy without any network access.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow all networking.
+    ///
+    /// See [`Exception::Networking`].
+    pub fn allow_all(self) -> Self {
+        self.with(Exception::Networking)
+    }
+
+    /// Allow outgoing TCP connections to any address.
+    ///
+    /// See [`Exception::NetworkOutbound`].
+    pub fn allow_outbound(self) -> Self {
+        self.with(Exception::NetworkOutbound)
+    }
+
+    /// Allow binding TCP sockets to any address.
+    ///
+    /// See [`Exception::NetworkInbound`].
+    pub fn allow_inbound(self) -> Self {
+        self.with(Exception::NetworkInbound)
+    }
+
+    /// Allow outgoing TCP connections to an address.
+    ///
+    /// See [`Exception::NetworkConnect`].
+    pub fn allow_tcp_connect(self, addr: SocketAddr) -> Self {
+        self.with(Exception::NetworkConnect(addr))
+    }
+
+    /// Allow binding TCP sockets to an address.
+    ///
+    /// See [`Exception::NetworkBind`].
+    pub fn allow_tcp_bind(self, addr: SocketAddr) -> Self {
+        self.with(Exception::NetworkBind(addr))
+    }
+
+    /// Allow outgoing TCP connections to a host.
+    ///
+    /// See [`Exception::NetworkHost`].
+    pub fn allow_host(self, host: impl Into<String>, port: Option<u16>) -> Self {
+        self.with(Exception::NetworkHost { host: host.into(), port })
+    }
+
+    /// Allow outgoing TCP connections to all addresses of a subnet.
+    ///
+    /// See [`Exception::NetworkSubnet`].
+    pub fn allow_subnet(self, addr: IpAddr, prefix_len: u8) -> Self {
+        self.with(Exception::NetworkSubnet { addr, prefix_len })
+    }
+
+    /// Allow TCP connections on the IPv4 and IPv6 loopback addresses.
+    ///
+    /// Sockets can be bound to and connect to any port of `127.0.0.1` and
+    /// `::1`, while all other addresses remain unreachable.
+    pub fn allow_loopback(self) -> Self {
+        let loopback = [IpAddr::from(Ipv4Addr::LOCALHOST), IpAddr::from(Ipv6Addr::LOCALHOST)];
+        loopback
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, 0))
+            .fold(self, |policy, addr| policy.allow_tcp_connect(addr).allow_tcp_bind(addr))
+    }
+
+    /// Add an exception to the policy.
+    fn with(mut self, exception: Exception) -> Self {
+        self.exceptions.push(exception);
+        self
+    }
+}
+
+impl IntoIterator for NetworkPolicy {
+    type IntoIter = std::vec::IntoIter<Exception>;
+    type Item = Exception;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.exceptions.into_iter()
+    }
+}
+
+/// Fluent builder for sandboxes.
+///
+/// Exceptions are validated as soon as they are added, like they would be with
+/// [`Sandbox::add_exception`]. [`SandboxBuilder::build`] additionally
+/// ensures the combined exceptions can be enforced, while converting the
+/// builder with [`From`] skips this check.
+///
+/// # Examples
+///
+/// ```rust
+/// use birdcage::{Exception, Sandbox, SandboxBuilder};
+///
+/// # fn main() -> birdcage::error::Result<()> {
+/// let sandbox = SandboxBuilder::new()
+///     .exception(Exception::ExecuteAndRead("/usr".into()))?
+///     .exceptions(["PATH", "HOME"].map(|key| Exception::Environment(key.into())))?
+///     .build()?;
+/// # assert_eq!(sandbox.exceptions().len(), 3);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SandboxBuilder<S = Birdcage> {
+    sandbox: S,
+}
+
+impl SandboxBuilder {
+    /// Create a builder for the platform's default sandbox.
+    pub fn new() -> Self {
+        Birdcage::builder()
+    }
+}
+
+impl Default for SandboxBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Sandbox> SandboxBuilder<S> {
+    /// Add a new exception to the sandbox.
+    ///
+    /// See [`Sandbox::add_exception`].
+    pub fn exception(mut self, exception: Exception) -> Result<Self> {
+        self.sandbox.add_exception(exception)?;
+        Ok(self)
+    }
+
+    /// Add multiple exceptions to the sandbox.
+    ///
+    /// See [`Sandbox::merge`].
+    pub fn exceptions(mut self, exceptions: impl IntoIterator<Item = Exception>) -> Result<Self> {
+        self.sandbox.merge(exceptions)?;
+        Ok(self)
+    }
+
+    /// Get the sandbox, ensuring it can be enforced.
+    ///
+    /// This fails if [`Sandbox::validate`] returns an error, warnings are
+    /// ignored.
+    pub fn build(self) -> Result<S> {
+        self.sandbox.validate()?;
+        Ok(self.sandbox)
+    }
+}
+
+impl From<SandboxBuilder> for Birdcage {
+    fn from(builder: SandboxBuilder) -> Self {
+        builder.sandbox
+    }
+}
+
+/// Sandboxing exception rule.
+///
+/// An exception excludes certain resources from the sandbox, allowing sandboxed
+/// applications to still access these resources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "config::ExceptionRepr", into = "config::ExceptionRepr")
+)]
+pub enum Exception {
+    /// Allow read access to the path and anything beneath it.
+    ///
+    /// For files, only the file itself is exposed. Its parent directories
+    /// can be traversed, but listing them will not show any other entries.
+    Read(PathBuf),
+
+    /// Allow reading the content of a single file.
+    ///
+    /// This behaves like [`Exception::Read`], but fails with
+    /// [`Error::InvalidPath`] if the path is not a regular file, guaranteeing
+    /// that no directory listing is exposed.
+    ReadFile(PathBuf),
+
+    /// Allow access to a path, without exposing anything beneath it.
+    ///
+    /// For files, this behaves like [`Exception::Read`]. Directories can be
+    /// traversed and their metadata queried, but none of their entries are
+    /// visible unless they are covered by another exception.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::ReadExact("/etc".into())).unwrap();
+    /// sandbox.add_exception(Exception::ReadExact("/etc/hosts".into())).unwrap();
+    /// ```
+    ReadExact(PathBuf),
+
+    /// Allow read access to all paths matching a glob pattern.
+    ///
+    /// Wildcards only match within a single path component: `*` matches any
+    /// number of characters, `?` exactly one character and `[...]` one
+    /// character of a class like `[a-z]`, or none of it like `[!0-9]`. Like
+    /// in shells, names starting with `.` are only matched by components
+    /// starting with a literal `.`.
+    ///
+    /// The pattern is expanded when the exception is added, afterwards it
+    /// behaves like an [`Exception::Read`] exception for every matching path.
+    /// Paths created later on are not covered. If nothing matches the
+    /// pattern, adding the exception fails with [`Error::PathNotFound`].
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception_lenient(Exception::ReadGlob("/usr/lib/python3.*".into())).unwrap();
+    /// ```
+    ReadGlob(String),
+
+    /// Allow reading a directory and its immediate entries.
+    ///
+    /// The directory can be listed and files directly inside it can be read.
+    /// Subdirectories are visible like with [`Exception::ReadExact`], but
+    /// none of their entries are exposed. This fails with
+    /// [`Error::InvalidPath`] if the path is not a directory.
+    ///
+    /// Since access rules are always applied to entire file hierarchies, the
+    /// directory's entries are enumerated when the exception is added. Entries
+    /// created afterwards are not accessible. Entries which are explicitly
+    /// denied with [`Exception::Deny`] are skipped.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::ReadShallow("/etc".into())).unwrap();
+    /// ```
+    ReadShallow(PathBuf),
+
+    /// Allow the sandboxee to inspect its own process, like `/proc/self`.
+    ///
+    /// Many runtimes read files like `/proc/self/maps` or `/proc/self/exe`
+    /// for backtraces and memory management.
+    ///
+    /// On Linux, `/proc/self` is accessible by default. This exception only
+    /// exposes it again when `/proc` is hidden with `ProcAccess::Hidden`,
+    /// which is then replaced by `ProcAccess::SelfOnly`. Processes outside
+    /// of the sandboxee's control remain hidden either way.
+    ///
+    /// On macOS, this allows `process-info*` operations targeting the
+    /// sandboxee itself.
+    ProcSelf,
+
+    /// Allow listing the directory and anything beneath it, without access to
+    /// the content of its files.
+    ///
+    /// Metadata of the directory's entries can be queried, but files cannot
+    /// be opened for reading. If the path or one of its parents also has a
+    /// read exception, the wider read access takes precedence.
+    ///
+    /// On Linux this requires Landlock, without it the sandbox will fail to
+    /// spawn.
+    ReadDir(PathBuf),
+
+    /// Allow writing and reading the path and anything beneath it.
+    WriteAndRead(PathBuf),
+
+    /// Allow executing and reading the path and anything beneath it.
+    ///
+    /// This is grouped with reading as a convenience, since execution will
+    /// always also require read access.
+    ///
+    /// Executables depending on loader variables like `LD_LIBRARY_PATH` to
+    /// find their shared libraries fail to start once these variables are
+    /// filtered from the environment. See [`Sandbox::preserve_loader_env`].
+    ExecuteAndRead(PathBuf),
+
+    /// Allow appending to and reading the path and anything beneath it.
+    ///
+    /// Files can be opened for writing, but existing content cannot be
+    /// truncated. If the path or one of its parents also has a
+    /// [`Exception::WriteAndRead`] exception, the wider write access takes
+    /// precedence.
+    ///
+    /// On Linux this requires Landlock ABI 3 (Linux 6.2), without it the
+    /// sandbox will fail to spawn. Since Landlock cannot distinguish
+    /// between writes with and without `O_APPEND`, data can still be
+    /// overwritten in place. While an append-only exception is present,
+    /// files outside of [`Exception::WriteAndRead`] exceptions can no
+    /// longer be truncated either.
+    ///
+    /// On macOS this is not supported, adding the exception returns
+    /// [`Error::UnsupportedException`].
+    ///
+    /// [`Error::UnsupportedException`]: crate::error::Error::UnsupportedException
+    AppendOnly(PathBuf),
+
+    /// Deny all access to the path and anything beneath it.
+    ///
+    /// This takes precedence over all other path exceptions, allowing access
+    /// to be revoked for parts of a directory which was previously allowed.
+    /// Exceptions beneath a denied path are ignored.
+    ///
+    /// Since it is ambiguous which exception should win, adding an allow and a
+    /// deny exception for the exact same path will return an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::Read("/etc".into())).unwrap();
+    /// sandbox.add_exception(Exception::Deny("/etc/ssl".into())).unwrap();
+    ///
+    /// let result = sandbox.add_exception(Exception::Read("/etc/ssl".into()));
+    /// assert!(result.is_err());
+    /// ```
+    Deny(PathBuf),
+
+    /// Allow reading an environment variable.
+    Environment(String),
+
+    /// Allow reading all environment variables starting with a prefix.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::EnvironmentPrefix("CARGO_".into())).unwrap();
+    /// ```
+    EnvironmentPrefix(String),
+
+    /// Allow reading all environment variables matching a wildcard pattern.
+    ///
+    /// The pattern must match the entire variable name, with `*` matching any
+    /// number of characters and `?` matching exactly one character.
+    ///
+    /// Like all other environment exceptions, patterns are ignored if an
+    /// [`Exception::CustomEnvironment`] is present.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::EnvironmentPattern("MY_APP_*".into())).unwrap();
+    /// ```
+    EnvironmentPattern(String),
+
+    /// Allow reading **all** environment variables.
+    FullEnvironment,
+
+    /// Replace all environment variables with a custom map.
+    ///
+    /// This completely replaces the environment with the provided variables.
+    /// If this exception is set, `Environment`, `EnvironmentPrefix`,
+    /// `EnvironmentPattern` and `FullEnvironment` exceptions are ignored. If
+    /// multiple `CustomEnvironment` exceptions are added, the last one
+    /// takes precedence.
+    ///
+    /// Variables set explicitly on the [`Command`] are applied on top of the
+    /// custom environment.
+    ///
+    /// Adding the exception fails with [`Error::InvalidEnvVar`] if a key is
+    /// empty, contains `=` or a NUL byte, or if a value contains a NUL byte.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut custom_env = HashMap::new();
+    /// custom_env.insert("PATH".to_string(), "/usr/bin".to_string());
+    /// custom_env.insert("HOME".to_string(), "/tmp".to_string());
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::CustomEnvironment(custom_env)).unwrap();
+    /// ```
+    CustomEnvironment(HashMap<String, String>),
+
+    /// Set environment variables, keeping the remaining environment.
+    ///
+    /// Unlike [`Exception::CustomEnvironment`], the variables are added on top
+    /// of the environment allowed by `Environment`, `EnvironmentPrefix`,
+    /// `EnvironmentPattern` and `FullEnvironment` exceptions, or on top of the
+    /// custom environment if one is set. Variables are always set, even if no
+    /// other exception allows them. If multiple `SetEnvironment` exceptions
+    /// set the same variable, the last one takes precedence.
+    ///
+    /// Variables set explicitly on the [`Command`] are applied on top of these
+    /// variables. Like with [`Exception::CustomEnvironment`], malformed
+    /// variables are rejected with [`Error::InvalidEnvVar`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut env = HashMap::new();
+    /// env.insert("RUST_LOG".to_string(), "debug".to_string());
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::FullEnvironment).unwrap();
+    /// sandbox.add_exception(Exception::SetEnvironment(env)).unwrap();
+    /// ```
+    SetEnvironment(HashMap<String, String>),
+
+    /// Allow networking.
+    Networking,
+
+    /// Allow connecting to a UNIX domain socket.
+    ///
+    /// The path must be an existing socket, otherwise adding the exception
+    /// will fail. Symlinks are resolved to the socket they point to. This is
+    /// independent of internet access, so it can be used to talk to local
+    /// daemons without allowing any other networking.
+    ///
+    /// Once a UNIX socket exception is added, connecting to any other socket
+    /// path is refused. On Linux, abstract UNIX domain sockets are only
+    /// reachable with [`Exception::Networking`] and datagrams sent without
+    /// connecting are not restricted.
+    UnixSocket(PathBuf),
+
+    /// Allow outgoing TCP connections to an address.
+    ///
+    /// A port of `0` allows connections to any port on the address.
+    /// IPv4-mapped IPv6 addresses are treated like their IPv4 equivalent.
+    ///
+    /// Only TCP is supported, all other internet protocols like UDP remain
+    /// blocked unless [`Exception::Networking`] is used. Connections to
+    /// addresses without exception are refused with `ECONNREFUSED`.
+    ///
+    /// On Linux, this is not supported by [`Sandbox::lock`].
+    ///
+    /// On macOS, only loopback addresses can be allowed, adding an exception
+    /// for other addresses returns [`Error::UnsupportedException`].
+    ///
+    /// [`Error::UnsupportedException`]: crate::error::Error::UnsupportedException
+    NetworkConnect(SocketAddr),
+
+    /// Allow outgoing TCP connections to any address.
+    ///
+    /// This behaves like a [`Exception::NetworkConnect`] exception for every
+    /// address, while binding sockets remains restricted. Since DNS servers
+    /// are not reachable, hostnames should be allowed with
+    /// [`Exception::NetworkHost`] instead.
+    ///
+    /// [`Exception::Networking`] allows both directions and all protocols.
+    NetworkOutbound,
+
+    /// Allow binding TCP sockets to any address, to accept incoming
+    /// connections.
+    ///
+    /// This behaves like a [`Exception::NetworkBind`] exception for every
+    /// address, while outgoing connections remain restricted.
+    ///
+    /// [`Exception::Networking`] allows both directions and all protocols.
+    NetworkInbound,
+
+    /// Allow binding TCP sockets to an address.
+    ///
+    /// A port of `0` allows binding to any port on the address, including
+    /// ephemeral ports.
+    ///
+    /// Binding to addresses without exception fails with `EACCES`. The same
+    /// platform limitations as for [`Exception::NetworkConnect`] apply.
+    NetworkBind(SocketAddr),
+
+    /// Allow networking, restricted to specific internet protocols.
+    ///
+    /// Like [`Exception::Networking`], this allows access to the host's
+    /// network, but only TCP and/or UDP sockets can be created for IPv4 and
+    /// IPv6. All other internet protocols like ICMP or SCTP are denied. This
+    /// also restricts access granted by [`Exception::Networking`].
+    ///
+    /// Since the protocol is restricted on socket creation, this also applies
+    /// to loopback connections. Other socket families like UNIX domain sockets
+    /// are not affected.
+    ///
+    /// Exceptions for specific addresses like [`Exception::NetworkConnect`]
+    /// only ever allow TCP, so they do not need this exception.
+    ///
+    /// On Linux, `io_uring` is unavailable while this exception is present.
+    NetworkProtocol { tcp: bool, udp: bool },
+
+    /// Allow outgoing TCP connections to a host.
+    ///
+    /// If `port` is `None`, connections to any port are allowed.
+    ///
+    /// The hostname is resolved when the exception is added, afterwards it
+    /// behaves like a [`Exception::NetworkConnect`] exception for each of its
+    /// addresses. Since filtering happens by IP address, other hosts sharing
+    /// the same address are reachable too, while addresses the host moves to
+    /// later on are not.
+    ///
+    /// DNS servers are not reachable from inside the sandbox. On Linux, the
+    /// sandbox's `/etc/hosts` is replaced with one pinning all hostnames to
+    /// their resolved addresses instead. For name resolution to work, the
+    /// resolver must consult `/etc/hosts`, which is the default for glibc
+    /// and musl.
+    ///
+    /// On macOS, only hosts resolving to loopback addresses are supported.
+    NetworkHost { host: String, port: Option<u16> },
+
+    /// Allow outgoing TCP connections to all addresses of a subnet.
+    ///
+    /// The subnet is given in CIDR notation, so `10.0.0.0/8` is represented
+    /// as an `addr` of `10.0.0.0` with a `prefix_len` of `8`. Connections to
+    /// any port are allowed. Prefix lengths above 32 for IPv4 or 128 for IPv6
+    /// fail with [`Error::InvalidSubnet`].
+    ///
+    /// Otherwise this behaves like a [`Exception::NetworkConnect`] exception
+    /// for every address of the subnet, with the same platform limitations.
+    /// On macOS, only subnets within the loopback range are supported.
+    ///
+    /// [`Error::InvalidSubnet`]: crate::error::Error::InvalidSubnet
+    NetworkSubnet { addr: IpAddr, prefix_len: u8 },
+
+    /// Restrict the sandboxee's resource usage.
+    ///
+    /// The limits are validated when the exception is added, see
+    /// [`ResourceLimits::validate`]. If multiple `ResourceLimits` exceptions
+    /// are added, limits set by later exceptions take precedence.
+    ///
+    /// With [`Sandbox::lock`], the limits are applied to the current process.
+    ResourceLimits(ResourceLimits),
+
+    /// Allow access to a device file in `/dev`.
+    ///
+    /// This avoids exposing the entire `/dev` tree for common devices like
+    /// `/dev/null`. See [`DeviceKind`] for the access granted to each device.
+    ///
+    /// Opened devices can be controlled with `ioctl`, the sandbox does not
+    /// restrict any `ioctl` requests on them.
+    ///
+    /// On Linux, writes to read-only devices can only be prevented with
+    /// Landlock. Without it, devices like `/dev/urandom` remain writable.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, DeviceKind, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::Device(DeviceKind::Null)).unwrap();
+    /// sandbox.add_exception(Exception::Device(DeviceKind::Urandom)).unwrap();
+    /// ```
+    Device(DeviceKind),
+
+    /// Allow reading and writing an existing POSIX shared memory object.
+    ///
+    /// The name is the one passed to `shm_open(3)`, like `/plugin_buffer`.
+    /// Unlike [`DeviceKind::Shm`], all other shared memory objects remain
+    /// inaccessible.
+    ///
+    /// On Linux, the object's file in `/dev/shm` is bind mounted into the
+    /// sandbox. So the object must exist when the exception is added and
+    /// cannot be removed by the sandboxee.
+    ///
+    /// On macOS, POSIX shared memory is not restricted by the sandbox, so all
+    /// objects remain accessible.
+    SharedMemory(String),
+
+    /// Allow creating a POSIX shared memory object.
+    ///
+    /// Since files which do not exist yet cannot be bind mounted on Linux, an
+    /// empty object is created when the sandbox is spawned or locked, unless it
+    /// exists already. Afterwards this behaves like
+    /// [`Exception::SharedMemory`], so `shm_open(3)` with `O_CREAT |
+    /// O_EXCL` fails inside the sandbox.
+    ///
+    /// The object is not removed once the sandbox exits, the caller owns it
+    /// and must remove it with `shm_unlink(3)`.
+    SharedMemoryCreate(String),
+
+    /// Drop Linux capabilities before the sandboxee is executed.
+    ///
+    /// The capabilities are removed from the bounding, effective, permitted
+    /// and inheritable sets of the sandboxee, so they cannot be regained by
+    /// executing other programs. The ambient set is always cleared.
+    ///
+    /// Since the sandboxee runs in its own user namespace, capabilities only
+    /// grant privileges over resources owned by the sandbox. If multiple
+    /// `DropCapabilities` exceptions are added, all of their capabilities
+    /// are dropped.
+    ///
+    /// On macOS, this exception is ignored.
+    DropCapabilities(CapabilitySet),
+
+    /// Drop all Linux capabilities before the sandboxee is executed.
+    ///
+    /// This is equivalent to [`Exception::DropCapabilities`] with
+    /// [`CapabilitySet::all`].
+    DropAllCapabilities,
+}
+
+impl Exception {
+    /// Allow read access to the current user's home directory.
+    ///
+    /// The directory is taken from the `HOME` environment variable.
+    pub fn home_directory() -> Result<Self> {
+        match env::var_os("HOME") {
+            Some(home) if !home.is_empty() => Ok(Self::Read(home.into())),
+            _ => Err(IoError::new(IoErrorKind::NotFound, "HOME is not set").into()),
+        }
+    }
+
+    /// Allow read and write access to a path.
+    ///
+    /// This is an alias for [`Exception::WriteAndRead`].
+    ///
+    /// ```rust
+    /// use birdcage::Exception;
+    ///
+    /// let exception = Exception::read_write("/tmp");
+    /// assert_eq!(exception, Exception::WriteAndRead("/tmp".into()));
+    /// ```
+    pub fn read_write(path: impl Into<PathBuf>) -> Self {
+        Self::WriteAndRead(path.into())
+    }
+
+    /// Allow read and write access to the system's temporary directory.
+    ///
+    /// See [`std::env::temp_dir`].
+    pub fn temp_directory() -> Self {
+        Self::WriteAndRead(env::temp_dir())
+    }
+
+    /// Allow executing the platform's dynamic linker and shared libraries.
+    ///
+    /// Only paths which exist on the current system are included, so all
+    /// exceptions can be added without [`Sandbox::add_exception_lenient`].
+    pub fn platform_libraries() -> Vec<Self> {
+        #[cfg(target_os = "linux")]
+        let paths =
+            ["/lib", "/lib64", "/usr/lib", "/lib/x86_64-linux-gnu", "/usr/lib/x86_64-linux-gnu"];
+        #[cfg(target_os = "macos")]
+        let paths = ["/usr/lib"];
+
+        paths
+            .iter()
+            .map(Path::new)
+            .filter(|path| path.exists())
+            .map(|path| Self::ExecuteAndRead(path.to_path_buf()))
+            .collect()
+    }
+
+    /// Check if this exception grants at least the access of `other`.
+    ///
+    /// Paths are compared as-is, so both exceptions should be normalized.
+    pub(crate) fn implies(&self, other: &Self) -> bool {
+        if self == other {
+            return true;
+        }
+
+        match (self, other) {
+            // Read access is granted by all recursive path exceptions.
+            (
+                Self::Read(parent) | Self::WriteAndRead(parent) | Self::ExecuteAndRead(parent),
+                Self::Read(path)
+                | Self::ReadFile(path)
+                | Self::ReadExact(path)
+                | Self::ReadShallow(path)
+                | Self::ReadDir(path),
+            ) => path.starts_with(parent),
+            (Self::WriteAndRead(parent), Self::WriteAndRead(path) | Self::AppendOnly(path))
+            | (Self::ExecuteAndRead(parent), Self::ExecuteAndRead(path))
+            | (Self::AppendOnly(parent), Self::AppendOnly(path)) => path.starts_with(parent),
+            (
+                Self::FullEnvironment,
+                Self::Environment(_) | Self::EnvironmentPrefix(_) | Self::EnvironmentPattern(_),
+            ) => true,
+            (
+                Self::EnvironmentPrefix(prefix),
+                Self::Environment(key) | Self::EnvironmentPrefix(key),
+            ) => key.starts_with(prefix.as_str()),
+            (Self::EnvironmentPattern(pattern), Self::Environment(key)) => {
+                wildcard_match(pattern, key)
+            },
+            (
+                Self::Networking,
+                Self::NetworkConnect(_)
+                | Self::NetworkBind(_)
+                | Self::NetworkOutbound
+                | Self::NetworkInbound
+                | Self::NetworkHost { .. }
+                | Self::NetworkSubnet { .. },
+            ) => true,
+            (
+                Self::NetworkOutbound,
+                Self::NetworkConnect(_) | Self::NetworkHost { .. } | Self::NetworkSubnet { .. },
+            ) => true,
+            (Self::NetworkSubnet { addr, prefix_len }, Self::NetworkConnect(other)) => {
+                subnet_contains(*addr, *prefix_len, other.ip())
+            },
+            (
+                Self::NetworkSubnet { addr, prefix_len },
+                Self::NetworkSubnet { addr: other, prefix_len: other_len },
+            ) => other_len >= prefix_len && subnet_contains(*addr, *prefix_len, *other),
+            (Self::NetworkInbound, Self::NetworkBind(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Check if this exception grants access, rather than restricting it.
+    ///
+    /// Host networking is handled separately, since
+    /// [`Exception::NetworkProtocol`] both grants and restricts it.
+    fn is_grant(&self) -> bool {
+        !matches!(
+            self,
+            Self::Deny(_)
+                | Self::ResourceLimits(_)
+                | Self::DropCapabilities(_)
+                | Self::DropAllCapabilities
+                | Self::Networking
+                | Self::NetworkProtocol { .. }
+        )
+    }
+
+    /// Get the path access is granted to, if any.
+    fn grant_path(&self) -> Option<&Path> {
+        match self {
+            Self::Read(path)
+            | Self::ReadFile(path)
+            | Self::ReadExact(path)
+            | Self::ReadShallow(path)
+            | Self::ReadDir(path)
+            | Self::WriteAndRead(path)
+            | Self::ExecuteAndRead(path)
+            | Self::AppendOnly(path)
+            | Self::UnixSocket(path) => Some(path),
+            Self::Device(device) => Some(device.path()),
+            _ => None,
+        }
+    }
+}
+
+/// Device file which can be allowed with [`Exception::Device`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DeviceKind {
+    /// `/dev/null`, with read and write access.
+    Null,
+    /// `/dev/zero`, with read and write access.
+    Zero,
+    /// `/dev/urandom`, with read access.
+    Urandom,
+    /// `/dev/random`, with read access.
+    Random,
+    /// `/dev/tty`, with read and write access.
+    Tty,
+    /// `/dev/stdin`, with read access.
+    ///
+    /// On Linux, this is a symlink to `/proc/self/fd/0` like on the host.
+    /// Opening it requires access to the file the descriptor refers to,
+    /// unless it is a pipe or socket.
+    Stdin,
+    /// `/dev/stdout`, with read and write access.
+    ///
+    /// The same limitations as for [`DeviceKind::Stdin`] apply.
+    Stdout,
+    /// `/dev/stderr`, with read and write access.
+    ///
+    /// The same limitations as for [`DeviceKind::Stdin`] apply.
+    Stderr,
+    /// `/dev/shm`, with read and write access to shared memory objects.
+    Shm,
+    /// `/dev/dri`, with read and write access to all GPU render and card
+    /// nodes.
+    ///
+    /// This is sufficient for GPU access through an already known node like
+    /// `/dev/dri/renderD128`. Libraries enumerating GPUs through sysfs also
+    /// require read access to `/sys`.
+    ///
+    /// This is only supported on Linux, on macOS adding the exception fails
+    /// with [`Error::UnsupportedException`].
+    Dri,
+    /// Any other device, with read and write access.
+    ///
+    /// The path must resolve to a file beneath `/dev`, otherwise adding the
+    /// exception fails with [`Error::InvalidPath`].
+    Custom(PathBuf),
+}
+
+impl DeviceKind {
+    /// Get the device's path.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Null => Path::new("/dev/null"),
+            Self::Zero => Path::new("/dev/zero"),
+            Self::Urandom => Path::new("/dev/urandom"),
+            Self::Random => Path::new("/dev/random"),
+            Self::Tty => Path::new("/dev/tty"),
+            Self::Stdin => Path::new("/dev/stdin"),
+            Self::Stdout => Path::new("/dev/stdout"),
+            Self::Stderr => Path::new("/dev/stderr"),
+            Self::Shm => Path::new("/dev/shm"),
+            Self::Dri => Path::new("/dev/dri"),
+            Self::Custom(path) => path,
+        }
+    }
+
+    /// Check if the device can be written to.
+    pub(crate) fn is_writable(&self) -> bool {
+        !matches!(self, Self::Urandom | Self::Random | Self::Stdin)
+    }
+
+    /// Get the file descriptor for standard streams.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn stdio_fd(&self) -> Option<i32> {
+        match self {
+            Self::Stdin => Some(0),
+            Self::Stdout => Some(1),
+            Self::Stderr => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Ensure custom devices are located beneath `/dev`.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let path = match self {
+            Self::Custom(path) => path,
+            _ => return Ok(()),
+        };
+
+        let canonical_path =
+            path.canonicalize().map_err(|err| Error::from_path(path.clone(), &err))?;
+        if canonical_path.starts_with("/dev") {
+            Ok(())
+        } else {
+            Err(Error::InvalidPath(path.clone()))
+        }
+    }
+}
+
+/// Exceptions and features supported by a sandbox.
+///
+/// See [`Sandbox::supported_exceptions`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SupportedExceptions {
+    /// [`Exception::UnixSocket`] can allow individual UNIX sockets.
+    pub unix_sockets: bool,
+    /// [`Exception::NetworkOutbound`] and [`Exception::NetworkInbound`] can
+    /// be used independently.
+    pub network_direction_split: bool,
+    /// Network exceptions for specific addresses accept non-loopback
+    /// addresses.
+    pub remote_network_addresses: bool,
+    /// [`Exception::NetworkProtocol`] can restrict internet protocols.
+    pub network_protocols: bool,
+    /// [`Exception::AppendOnly`] can prevent truncation.
+    pub append_only: bool,
+    /// [`Exception::ReadDir`] can allow listing without file reads.
+    pub list_only_directories: bool,
+    /// Individual syscalls can be allowed or denied.
+    pub seccomp_filtering: bool,
+    /// The sandbox is isolated using user namespaces.
+    pub user_namespaces: bool,
+    /// [`Exception::ResourceLimits`] can limit resource usage.
+    pub resource_limits: bool,
+    /// [`Exception::EnvironmentPrefix`] and [`Exception::EnvironmentPattern`]
+    /// can allow groups of environment variables.
+    pub environment_patterns: bool,
+}
+
+/// Sandbox features which can be enforced on the current system.
+///
+/// See [`capabilities`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Capabilities {
+    /// Filesystem access can be restricted using path exceptions.
+    pub path_exceptions: bool,
+    /// Network access can be denied or restricted to specific addresses.
+    pub network_filtering: bool,
+    /// The sandboxee's environment variables can be filtered.
+    pub env_filtering: bool,
+    /// Creation of subprocesses can be denied using
+    /// [`Sandbox::allow_subprocesses`].
+    pub subprocess_control: bool,
+}
+
+/// Potential sandbox misconfiguration.
+///
+/// See [`Sandbox::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SandboxWarning {
+    /// [`Exception::Environment`], [`Exception::EnvironmentPrefix`],
+    /// [`Exception::EnvironmentPattern`] and [`Exception::FullEnvironment`]
+    /// are ignored, since an [`Exception::CustomEnvironment`] is present.
+    EnvironmentIgnored,
+
+    /// Exceptions for specific network addresses are ignored, since all
+    /// networking is allowed.
+    NetworkExceptionsIgnored,
+
+    /// Exceptions for specific network addresses are redundant, since
+    /// [`Exception::NetworkOutbound`] or [`Exception::NetworkInbound`] allow
+    /// all addresses in the same direction.
+    NetworkExceptionsRedundant,
+
+    /// Path has less permissions than its parent.
+    ///
+    /// Since exceptions for more specific paths take precedence, access to
+    /// `path` and anything beneath it is more restricted than for `parent`.
+    RestrictedSubpath { path: PathBuf, parent: PathBuf },
+
+    /// Path allows execution, but is a file without any execute permissions.
+    NotExecutable(PathBuf),
+
+    /// [`Exception::Deny`] has no effect, since no parent path is accessible.
+    DenyIgnored(PathBuf),
+
+    /// Landlock is not supported by the kernel (Linux 5.13+) or disabled.
+    ///
+    /// The sandbox is enforced by namespaces and seccomp only, exceptions
+    /// which require Landlock cannot be used.
+    LandlockUnavailable,
+
+    /// Cgroup limits are ignored, since the calling process is not in the
+    /// host's writable cgroup v2 root or it lacks the required controllers.
+    CgroupUnavailable,
+
+    /// Syscalls are not filtered, since seccomp is unavailable.
+    ///
+    /// See `LinuxSandbox::seccomp_best_effort`.
+    SeccompUnavailable,
+
+    /// Exception path was removed after the exception was added.
+    ///
+    /// The exception is ignored, since its path cannot be resolved anymore.
+    PathDoesNotExist(PathBuf),
+
+    /// Dynamic loader variable of the current process is removed from the
+    /// sandboxee's environment, while executables are allowed.
+    ///
+    /// See [`Sandbox::preserve_loader_env`].
+    LoaderEnvironmentRemoved(String),
+}
+
+impl Display for SandboxWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EnvironmentIgnored => {
+                write!(f, "environment exceptions are overridden by a custom environment")
+            },
+            Self::NetworkExceptionsIgnored => {
+                write!(f, "network address exceptions are overridden by full network access")
+            },
+            Self::NetworkExceptionsRedundant => {
+                write!(
+                    f,
+                    "network address exceptions are redundant with directional network access"
+                )
+            },
+            Self::RestrictedSubpath { path, parent } => {
+                write!(f, "{path:?} has less permissions than its parent {parent:?}")
+            },
+            Self::NotExecutable(path) => write!(f, "executable path {path:?} has no execute bit"),
+            Self::DenyIgnored(path) => {
+                write!(f, "denied path {path:?} has no accessible parent")
+            },
+            Self::LandlockUnavailable => write!(f, "landlock is unavailable"),
+            Self::CgroupUnavailable => write!(f, "cgroup v2 is unavailable"),
+            Self::SeccompUnavailable => write!(f, "seccomp is unavailable"),
+            Self::PathDoesNotExist(path) => write!(f, "exception path {path:?} does not exist"),
+            Self::LoaderEnvironmentRemoved(key) => {
+                write!(f, "dynamic loader variable {key:?} is removed from the environment")
+            },
+        }
+    }
+}
+
+/// Record a successfully added exception in its normalized form.
+///
+/// Duplicate exceptions and environment exceptions subsumed by
+/// [`Exception::FullEnvironment`] are not recorded.
+pub(crate) fn record_exception(exceptions: &mut Vec<Exception>, exception: Exception) {
+    let canonicalize = |path: PathBuf| path.canonicalize().unwrap_or(path);
+    let exception = match exception {
+        Exception::Read(path) => Exception::Read(canonicalize(path)),
+        Exception::ReadFile(path) => Exception::ReadFile(canonicalize(path)),
+        Exception::ReadExact(path) => Exception::ReadExact(canonicalize(path)),
+        Exception::ReadShallow(path) => Exception::ReadShallow(canonicalize(path)),
+        Exception::ReadDir(path) => Exception::ReadDir(canonicalize(path)),
+        Exception::WriteAndRead(path) => Exception::WriteAndRead(canonicalize(path)),
+        Exception::ExecuteAndRead(path) => Exception::ExecuteAndRead(canonicalize(path)),
+        Exception::AppendOnly(path) => Exception::AppendOnly(canonicalize(path)),
+        Exception::Deny(path) => Exception::Deny(canonicalize(path)),
+        Exception::UnixSocket(path) => Exception::UnixSocket(canonicalize(path)),
+        Exception::ResourceLimits(limits) => {
+            let existing = exceptions.iter_mut().find_map(|exception| match exception {
+                Exception::ResourceLimits(existing) => Some(existing),
+                _ => None,
+            });
+
+            match existing {
+                Some(existing) => {
+                    existing.merge(limits);
+                    return;
+                },
+                None => Exception::ResourceLimits(limits),
+            }
+        },
+        Exception::FullEnvironment => {
+            exceptions.retain(|existing| {
+                !matches!(
+                    existing,
+                    Exception::Environment(_)
+                        | Exception::EnvironmentPrefix(_)
+                        | Exception::EnvironmentPattern(_)
+                )
+            });
+            Exception::FullEnvironment
+        },
+        exception => exception,
+    };
+
+    let subsumed = matches!(
+        exception,
+        Exception::Environment(_)
+            | Exception::EnvironmentPrefix(_)
+            | Exception::EnvironmentPattern(_)
+    ) && exceptions.contains(&Exception::FullEnvironment);
+    if subsumed || exceptions.contains(&exception) {
+        return;
+    }
+
+    exceptions.push(exception);
+}
+
+/// Get the path of a POSIX shared memory object in `/dev/shm`.
+///
+/// Fails with [`Error::InvalidPath`] if the name cannot be used with
+/// `shm_open(3)`.
+pub(crate) fn shm_path(name: &str) -> Result<PathBuf> {
+    let file_name = name.strip_prefix('/').unwrap_or(name);
+    if matches!(file_name, "" | "." | "..") || file_name.contains(['/', '\0']) {
+        return Err(Error::InvalidPath(name.into()));
+    }
+    Ok(Path::new("/dev/shm").join(file_name))
+}
+
+/// Get the canonical base directory for relative exception paths.
+///
+/// See [`Sandbox::set_base_dir`].
+pub(crate) fn canonical_base_dir(path: PathBuf) -> Result<PathBuf> {
+    let canonical_path = path.canonicalize().map_err(|err| Error::from_path(path, &err))?;
+    if !canonical_path.is_dir() {
+        return Err(Error::InvalidPath(canonical_path));
+    }
+    Ok(canonical_path)
+}
+
+/// Join relative exception paths to the base directory.
+pub(crate) fn resolve_relative_paths(exception: Exception, base_dir: Option<&Path>) -> Exception {
+    let Some(base_dir) = base_dir else { return exception };
+    let join = |path: PathBuf| base_dir.join(path);
+
+    match exception {
+        Exception::Read(path) => Exception::Read(join(path)),
+        Exception::ReadFile(path) => Exception::ReadFile(join(path)),
+        Exception::ReadExact(path) => Exception::ReadExact(join(path)),
+        Exception::ReadShallow(path) => Exception::ReadShallow(join(path)),
+        Exception::ReadDir(path) => Exception::ReadDir(join(path)),
+        Exception::WriteAndRead(path) => Exception::WriteAndRead(join(path)),
+        Exception::ExecuteAndRead(path) => Exception::ExecuteAndRead(join(path)),
+        Exception::AppendOnly(path) => Exception::AppendOnly(join(path)),
+        Exception::Deny(path) => Exception::Deny(join(path)),
+        Exception::UnixSocket(path) => Exception::UnixSocket(join(path)),
+        // Patterns are kept as-is if the base directory is not valid UTF-8.
+        Exception::ReadGlob(pattern) => {
+            match base_dir.join(&pattern).into_os_string().into_string() {
+                Ok(pattern) => Exception::ReadGlob(pattern),
+                Err(_) => Exception::ReadGlob(pattern),
+            }
+        },
+        exception => exception,
+    }
+}
+
+/// Ensure no symlink in a path resolves outside of the directory containing it.
+///
+/// Fails with [`Error::SymlinkEscape`] for the first escaping symlink. Broken
+/// symlinks are ignored, since they are rejected when the path is resolved.
+pub(crate) fn check_symlink_escape(path: &Path) -> Result<()> {
+    let absolute =
+        if path.is_absolute() { path.to_path_buf() } else { env::current_dir()?.join(path) };
+
+    let mut prefix = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => continue,
+            Component::ParentDir => {
+                prefix.pop();
+                continue;
+            },
+            component => prefix.push(component),
+        }
+
+        let is_symlink = prefix.symlink_metadata().is_ok_and(|metadata| metadata.is_symlink());
+        let parent = prefix.parent().and_then(|parent| parent.canonicalize().ok());
+        let (true, Some(parent), Ok(target)) = (is_symlink, parent, prefix.canonicalize()) else {
+            continue;
+        };
+
+        if !target.starts_with(parent) {
+            return Err(Error::SymlinkEscape(prefix));
+        }
+    }
+
+    Ok(())
+}
+
+/// Internet protocols available with host networking.
+#[derive(Copy, Clone)]
+struct NetworkProtocols {
+    tcp: bool,
+    udp: bool,
+    other: bool,
+}
+
+impl NetworkProtocols {
+    /// Check if all protocols are also allowed by `other`.
+    fn is_subset_of(&self, other: &Self) -> bool {
+        (!self.tcp || other.tcp) && (!self.udp || other.udp) && (!self.other || other.other)
+    }
+}
+
+/// Get the protocols allowed by host networking exceptions.
+///
+/// Returns `None` if host networking is not allowed.
+fn host_network_protocols(exceptions: &[Exception]) -> Option<NetworkProtocols> {
+    let mut protocols: Option<NetworkProtocols> = None;
+    let mut networking = false;
+    for exception in exceptions {
+        match exception {
+            Exception::Networking => networking = true,
+            Exception::NetworkProtocol { tcp, udp } => {
+                let protocols = protocols.get_or_insert(NetworkProtocols {
+                    tcp: false,
+                    udp: false,
+                    other: false,
+                });
+                protocols.tcp |= tcp;
+                protocols.udp |= udp;
+            },
+            _ => (),
+        }
+    }
+
+    match protocols {
+        Some(protocols) => Some(protocols),
+        None if networking => Some(NetworkProtocols { tcp: true, udp: true, other: true }),
+        None => None,
+    }
+}
+
+/// Ensure a subnet's prefix length fits its address family.
+pub(crate) fn validate_subnet(addr: IpAddr, prefix_len: u8) -> Result<()> {
+    let max_len = if addr.is_ipv4() { 32 } else { 128 };
+    if prefix_len > max_len {
+        return Err(Error::InvalidSubnet(format!("{addr}/{prefix_len}")));
+    }
+    Ok(())
+}
+
+/// Check if a subnet contains an IP address.
+///
+/// IPv4-mapped IPv6 addresses are treated like their IPv4 equivalent.
+pub(crate) fn subnet_contains(addr: IpAddr, prefix_len: u8, ip: IpAddr) -> bool {
+    let ip = match ip {
+        IpAddr::V6(ipv6) => ipv6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        ip => ip,
+    };
+
+    match (addr, ip) {
+        (IpAddr::V4(addr), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32u32.saturating_sub(prefix_len.into())).unwrap_or(0);
+            u32::from(addr) & mask == u32::from(ip) & mask
+        },
+        (IpAddr::V6(addr), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128u32.saturating_sub(prefix_len.into())).unwrap_or(0);
+            u128::from(addr) & mask == u128::from(ip) & mask
+        },
+        _ => false,
+    }
+}
+
+/// Get the immediate entries of a directory.
+///
+/// Fails with [`Error::InvalidPath`] if the path is not a directory.
+pub(crate) fn directory_entries(path: &Path) -> Result<Vec<PathBuf>> {
+    let metadata = path.metadata().map_err(|err| Error::from_path(path.to_path_buf(), &err))?;
+    if !metadata.is_dir() {
+        return Err(Error::InvalidPath(path.to_path_buf()));
+    }
+
+    let mut entries = Vec::new();
+    for entry in path.read_dir()? {
+        entries.push(entry?.path());
+    }
+    entries.sort_unstable();
+
+    Ok(entries)
+}
+
+/// Remove path exceptions which are already covered by their parent.
+///
+/// Since exceptions for more specific paths take precedence, a path is only
+/// redundant if its closest parent has the exact same permissions. The paths
+/// must be sorted with parents before their descendants.
+pub(crate) fn remove_redundant_paths<P: AsRef<Path>, T: PartialEq>(paths: &mut Vec<(P, T)>) {
+    let mut i = 0;
+    while i < paths.len() {
+        let (path, permissions) = &paths[i];
+        let parent = paths[..i].iter().rev().find(|(parent, _)| {
+            path.as_ref() != parent.as_ref() && path.as_ref().starts_with(parent.as_ref())
+        });
+
+        if parent.is_some_and(|(_, parent_permissions)| parent_permissions == permissions) {
+            paths.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Describe how environment variables will be restricted.
+pub(crate) fn env_preview(
+    exceptions: &[String],
+    prefixes: &[String],
+    patterns: &[String],
+    full_env: bool,
+    custom_env: Option<&HashMap<String, String>>,
+    set_env: &HashMap<String, String>,
+) -> String {
+    let mut preview = match custom_env {
+        Some(env_map) => {
+            let mut keys: Vec<_> = env_map.keys().collect();
+            keys.sort_unstable();
+            format!("replaced with {keys:?}")
+        },
+        None if full_env => "all variables allowed".into(),
+        None if prefixes.is_empty() && patterns.is_empty() => {
+            let mut exceptions: Vec<_> = exceptions.iter().collect();
+            exceptions.sort_unstable();
+            format!("only {exceptions:?} allowed")
+        },
+        None => {
+            let mut exceptions: Vec<_> = exceptions.iter().collect();
+            exceptions.sort_unstable();
+
+            let mut patterns: Vec<_> = prefixes
+                .iter()
+                .map(|prefix| format!("{prefix}*"))
+                .chain(patterns.iter().cloned())
+                .collect();
+            patterns.sort_unstable();
+            format!("only {exceptions:?} and {patterns:?} allowed")
+        },
+    };
+
+    if !set_env.is_empty() {
+        let mut keys: Vec<_> = set_env.keys().collect();
+        keys.sort_unstable();
+        preview.push_str(&format!(", setting {keys:?}"));
+    }
+
+    preview
+}
+
+/// Environment variables used by the dynamic loader to locate libraries.
+#[cfg(target_os = "linux")]
+const LOADER_ENV_VARS: &[&str] = &["LD_LIBRARY_PATH"];
+#[cfg(target_os = "macos")]
+const LOADER_ENV_VARS: &[&str] =
+    &["DYLD_LIBRARY_PATH", "DYLD_FALLBACK_LIBRARY_PATH", "DYLD_FRAMEWORK_PATH"];
+
+/// Get warnings for loader variables which are removed from the environment.
+///
+/// Variables are only reported if they are set in the current process and at
+/// least one executable path is allowed.
+pub(crate) fn loader_env_warnings(exceptions: &[Exception]) -> Vec<SandboxWarning> {
+    if !exceptions.iter().any(|exception| matches!(exception, Exception::ExecuteAndRead(_))) {
+        return Vec::new();
+    }
+
+    LOADER_ENV_VARS
+        .iter()
+        .filter(|key| env::var_os(key).is_some())
+        .filter(|key| {
+            let variable = Exception::Environment(key.to_string());
+            let set = |exception: &Exception| match exception {
+                Exception::CustomEnvironment(env) | Exception::SetEnvironment(env) => {
+                    env.contains_key(**key)
+                },
+                _ => false,
+            };
+
+            // Custom environments replace all inherited variables.
+            if exceptions
+                .iter()
+                .any(|exception| matches!(exception, Exception::CustomEnvironment(_)))
+            {
+                !exceptions.iter().any(set)
+            } else {
+                !exceptions.iter().any(|exception| exception.implies(&variable) || set(exception))
+            }
+        })
+        .map(|key| SandboxWarning::LoaderEnvironmentRemoved(key.to_string()))
+        .collect()
+}
+
+/// Set environment variables of the current process.
+pub(crate) fn set_env_variables(env_map: &HashMap<String, String>) {
+    for (key, value) in env_map {
+        env::set_var(key, value);
+    }
+}
+
+/// Ensure all environment variables can be set without panicking.
+pub(crate) fn validate_env_variables(env_map: &HashMap<String, String>) -> Result<()> {
+    for (key, value) in env_map {
+        if key.is_empty() || key.contains(['=', '\0']) || value.contains('\0') {
+            return Err(Error::InvalidEnvVar(key.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Check if an environment variable is allowed by its name, prefix or pattern.
+pub(crate) fn env_allowed(
+    key: &str,
+    exceptions: &[String],
+    prefixes: &[String],
+    patterns: &[String],
+) -> bool {
+    exceptions.iter().any(|exception| exception == key)
+        || prefixes.iter().any(|prefix| key.starts_with(prefix.as_str()))
+        || patterns.iter().any(|pattern| wildcard_match(pattern, key))
+}
+
+/// Check if `text` matches a pattern with `*` and `?` wildcards.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was matched against.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            },
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            },
+            // Let the last `*` consume one more character.
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Restrict access to environment variables.
+pub(crate) fn restrict_env_variables(
+    exceptions: &[String],
+    prefixes: &[String],
+    patterns: &[String],
+) {
+    restrict_env_variables_with_custom(exceptions, prefixes, patterns, None);
+}
+
+/// Restrict access to environment variables, optionally replacing with custom
+/// map.
+///
+/// If `custom_env` is provided, all existing environment variables are cleared
+/// and replaced with the variables from the map. Otherwise, variables not in
+/// the `exceptions` list, not starting with one of the `prefixes` and not
+/// matching one of the `patterns` are removed.
+pub(crate) fn restrict_env_variables_with_custom(
+    exceptions: &[String],
+    prefixes: &[String],
+    patterns: &[String],
+    custom_env: Option<&HashMap<String, String>>,
+) {
+    match custom_env {
+        Some(env_map) => {
+            event!(target: "birdcage", "replacing environment with {} variables", env_map.len());
+
+            // Clear all existing environment variables
+            for (key, _) in env::vars_os() {
+                env::remove_var(key);
+            }
+
+            // Set custom environment variables
+            for (key, value) in env_map {
+                env::set_var(key, value);
+            }
+        },
+        None => {
+            // Variables with invalid unicode names can never match an exception.
+            for (key, _) in env::vars_os() {
+                if !key.to_str().is_some_and(|key| env_allowed(key, exceptions, prefixes, patterns))
+                {
+                    event!(target: "birdcage", "removing environment variable {key:?}");
+                    env::remove_var(key);
+                }
+            }
+        },
     }
 }
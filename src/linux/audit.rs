@@ -0,0 +1,324 @@
+//! Audit mode.
+//!
+//! In audit mode, the sandboxee has full access to the host's filesystem,
+//! while file accesses which would have been denied are written to a log.
+//!
+//! File opens and executions are sent to a separate auditor process using
+//! seccomp user notifications. The auditor resolves the path in the
+//! sandboxee's context, checks it against the sandbox's path exceptions and
+//! then lets the kernel continue the syscall unchanged.
+//!
+//! Since the sandboxee could modify the syscall arguments after they were
+//! checked, the log is only accurate for cooperative sandboxees. Audit mode
+//! must never be used to enforce a policy.
+//!
+//! Violations sent to a channel are written to a pipe by the auditor, which
+//! is read by a forwarding thread in the sandbox's parent process.
+
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Error as IoError, Write};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::time::Instant;
+use std::{mem, thread};
+
+use crate::linux::namespaces::MountAttrFlags;
+use crate::linux::network::{self, Supervisor};
+use crate::linux::{PathExceptions, SandboxViolation};
+
+/// Maximum length of a path passed to the kernel.
+const PATH_MAX: usize = libc::PATH_MAX as usize;
+
+/// Page size used to split reads from the sandboxee's memory.
+const PAGE_SIZE: usize = 4096;
+
+/// Destination of reported violations.
+pub enum AuditLog {
+    /// Human-readable log file.
+    File(File),
+    /// Channel receiving each violation.
+    Channel(Sender<SandboxViolation>),
+}
+
+impl AuditLog {
+    /// Duplicate the log destination for another sandbox.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Self::File(file) => Ok(Self::File(file.try_clone()?)),
+            Self::Channel(sender) => Ok(Self::Channel(sender.clone())),
+        }
+    }
+}
+
+/// Exceptions checked by the auditor.
+pub struct AuditRules {
+    pub path_exceptions: PathExceptions,
+    pub log: File,
+    /// Write NUL-separated records for an [`AuditForwarder`].
+    pub structured: bool,
+}
+
+/// Forwarder of structured violations from the auditor to a channel.
+pub struct AuditForwarder {
+    pipe: File,
+    sender: Sender<SandboxViolation>,
+}
+
+impl AuditForwarder {
+    /// Create the auditor's log for a channel.
+    ///
+    /// Returns the write end of the log's pipe and the forwarder reading it.
+    pub fn new(sender: Sender<SandboxViolation>) -> io::Result<(File, Self)> {
+        let (rx, tx) = rustix::pipe::pipe_with(rustix::pipe::PipeFlags::CLOEXEC)?;
+        Ok((File::from(tx), Self { pipe: File::from(rx), sender }))
+    }
+
+    /// Forward violations on a background thread until the auditor exits.
+    ///
+    /// This must only be called after the sandbox's PID 1 was spawned.
+    pub fn spawn(self) -> io::Result<()> {
+        thread::Builder::new().name("birdcage-audit".into()).spawn(move || {
+            let mut fields = BufReader::new(self.pipe).split(b'\0');
+            while let (Some(Ok(syscall)), Some(Ok(access)), Some(Ok(path))) =
+                (fields.next(), fields.next(), fields.next())
+            {
+                let violation = SandboxViolation {
+                    syscall: String::from_utf8_lossy(&syscall).into_owned(),
+                    access: String::from_utf8_lossy(&access).into_owned(),
+                    path: Some(PathBuf::from(OsStr::from_bytes(&path))),
+                    timestamp: Instant::now(),
+                };
+
+                // Keep draining the pipe, to avoid blocking the auditor.
+                let _ = self.sender.send(violation);
+            }
+        })?;
+        Ok(())
+    }
+}
+
+/// Spawn the auditor process.
+///
+/// This must be called after the mount namespace is finalized, so paths are
+/// resolved like they would be by the sandboxee.
+pub fn spawn(rules: AuditRules) -> io::Result<Supervisor> {
+    Supervisor::fork("auditor", move |socket| run_auditor(rules, socket))
+}
+
+/// Auditor main loop.
+fn run_auditor(mut rules: AuditRules, socket: OwnedFd) -> io::Result<()> {
+    // Prevent sandboxees from accessing our memory.
+    rustix::process::set_dumpable_behavior(rustix::process::DumpableBehavior::NotDumpable)?;
+
+    // Ensure the auditor doesn't outlive the sandbox.
+    rustix::process::set_parent_process_death_signal(Some(rustix::process::Signal::Kill))?;
+
+    // Wait for the seccomp listener.
+    let listener = network::recv_fd(&socket)?;
+    drop(socket);
+
+    // Only report each violation once.
+    let mut reported = HashSet::new();
+
+    loop {
+        let notif = match network::recv_notification(&listener) {
+            Ok(notif) => notif,
+            // Sandboxee was interrupted or exited before we received the notification.
+            Err(err) if err.raw_os_error() == Some(libc::ENOENT) => continue,
+            Err(err) if err.raw_os_error() == Some(libc::EINTR) => continue,
+            Err(err) => return Err(err),
+        };
+
+        // Violations are reported before the syscall is performed.
+        if let Ok(Some(access)) = FileAccess::new(&notif) {
+            if network::notification_valid(&listener, notif.id) {
+                for kind in access.violations(&rules.path_exceptions) {
+                    let path = &access.path;
+                    let violation = if rules.structured {
+                        let mut record = format!("{}\0{kind}\0", access.syscall).into_bytes();
+                        record.extend_from_slice(path.as_os_str().as_bytes());
+                        record.push(b'\0');
+                        record
+                    } else {
+                        format!("{kind} {path:?}\n").into_bytes()
+                    };
+
+                    if reported.insert(violation.clone()) {
+                        rules.log.write_all(&violation)?;
+                    }
+                }
+            }
+        }
+
+        // Failure indicates the sandboxee is no longer waiting for a response.
+        let _ = continue_syscall(&listener, notif.id);
+    }
+}
+
+/// Intercepted file access.
+struct FileAccess {
+    syscall: &'static str,
+    path: PathBuf,
+    write: bool,
+    truncate: bool,
+    execute: bool,
+}
+
+impl FileAccess {
+    /// Read the accessed path from the sandboxee.
+    ///
+    /// Returns `None` if the syscall does not access the file's content.
+    fn new(notif: &libc::seccomp_notif) -> io::Result<Option<Self>> {
+        let args = notif.data.args;
+        let (syscall, dirfd, path_ptr, flags, execute) = match notif.data.nr as libc::c_long {
+            #[cfg(target_arch = "x86_64")]
+            libc::SYS_open => ("open", libc::AT_FDCWD, args[0], args[1] as libc::c_int, false),
+            #[cfg(target_arch = "x86_64")]
+            libc::SYS_creat => {
+                ("creat", libc::AT_FDCWD, args[0], libc::O_CREAT | libc::O_TRUNC, false)
+            },
+            libc::SYS_openat => {
+                ("openat", args[0] as libc::c_int, args[1], args[2] as libc::c_int, false)
+            },
+            libc::SYS_openat2 => {
+                let mut how = [0; mem::size_of::<u64>()];
+                read_memory(notif.pid, args[2] as usize, &mut how)?;
+                let flags = u64::from_ne_bytes(how) as libc::c_int;
+                ("openat2", args[0] as libc::c_int, args[1], flags, false)
+            },
+            libc::SYS_execve => ("execve", libc::AT_FDCWD, args[0], libc::O_RDONLY, true),
+            libc::SYS_execveat => {
+                ("execveat", args[0] as libc::c_int, args[1], libc::O_RDONLY, true)
+            },
+            _ => return Ok(None),
+        };
+
+        // Paths opened without access to their content are always allowed.
+        if flags & libc::O_PATH != 0 {
+            return Ok(None);
+        }
+
+        // Copy the path from the sandboxee's memory.
+        let mut path = vec![0; PATH_MAX];
+        let len = read_memory(notif.pid, path_ptr as usize, &mut path)?;
+        let path = match path[..len].iter().position(|byte| *byte == b'\0') {
+            Some(nul) => PathBuf::from(OsStr::from_bytes(&path[..nul])),
+            None => return Err(IoError::from_raw_os_error(libc::ENAMETOOLONG)),
+        };
+
+        // The sandbox always has its own accessible procfs.
+        let path = resolve_path(notif.pid, dirfd, path)?;
+        if path.starts_with("/proc") {
+            return Ok(None);
+        }
+
+        let write = flags & libc::O_ACCMODE != libc::O_RDONLY || flags & libc::O_CREAT != 0;
+        let truncate = flags & libc::O_TRUNC != 0;
+
+        Ok(Some(Self { syscall, path, write, truncate, execute }))
+    }
+
+    /// Get all kinds of access denied by the path exceptions.
+    fn violations(&self, path_exceptions: &PathExceptions) -> Vec<&'static str> {
+        let path = &self.path;
+        let flags = path_exceptions.permissions(path);
+        let mut violations = Vec::new();
+
+        if flags.is_none() || (path_exceptions.is_list_only(path) && !path.is_dir()) {
+            violations.push("read");
+        }
+
+        let read_only = flags.map_or(true, |flags| flags.contains(MountAttrFlags::RDONLY));
+        if self.write && read_only {
+            violations.push("write");
+        } else if self.truncate && path_exceptions.append_only.contains(path) {
+            violations.push("truncate");
+        }
+
+        let no_exec = flags.map_or(true, |flags| flags.contains(MountAttrFlags::NOEXEC));
+        if self.execute && no_exec {
+            violations.push("execute");
+        }
+
+        violations
+    }
+}
+
+/// Resolve a path relative to the sandboxee's directory file descriptor.
+///
+/// Symlinks are resolved for all its existing parents.
+fn resolve_path(pid: u32, dirfd: libc::c_int, path: PathBuf) -> io::Result<PathBuf> {
+    let path = if path.is_absolute() {
+        path
+    } else if dirfd == libc::AT_FDCWD {
+        fs::read_link(format!("/proc/{pid}/cwd"))?.join(path)
+    } else {
+        fs::read_link(format!("/proc/{pid}/fd/{dirfd}"))?.join(path)
+    };
+
+    if let Ok(path) = path.canonicalize() {
+        return Ok(path);
+    }
+
+    // Files which don't exist yet are resolved through their parent.
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => match parent.canonicalize() {
+            Ok(parent) => Ok(parent.join(name)),
+            Err(_) => Ok(path),
+        },
+        _ => Ok(path),
+    }
+}
+
+/// Read from the sandboxee's memory.
+///
+/// Returns the number of bytes read, which might be less than the buffer
+/// size if the end of the mapped memory was reached.
+fn read_memory(pid: u32, addr: usize, buf: &mut [u8]) -> io::Result<usize> {
+    // Split the read at page boundaries, to allow for partial reads.
+    let mut local = Vec::new();
+    let mut remote = Vec::new();
+    let mut offset = 0;
+    while offset < buf.len() {
+        let page_end = (addr + offset) / PAGE_SIZE * PAGE_SIZE + PAGE_SIZE;
+        let len = (page_end - addr - offset).min(buf.len() - offset);
+        let base = unsafe { buf.as_mut_ptr().add(offset) };
+        local.push(libc::iovec { iov_base: base as _, iov_len: len });
+        remote.push(libc::iovec { iov_base: (addr + offset) as _, iov_len: len });
+        offset += len;
+    }
+
+    let read = unsafe {
+        libc::process_vm_readv(
+            pid as _,
+            local.as_ptr(),
+            local.len() as _,
+            remote.as_ptr(),
+            remote.len() as _,
+            0,
+        )
+    };
+
+    match read {
+        -1 => Err(IoError::last_os_error()),
+        read => Ok(read as usize),
+    }
+}
+
+/// Let the kernel perform the sandboxee's syscall.
+fn continue_syscall(listener: &OwnedFd, id: u64) -> io::Result<()> {
+    let flags = libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32;
+    let mut response = libc::seccomp_notif_resp { id, val: 0, error: 0, flags };
+    let result = unsafe {
+        libc::ioctl(listener.as_raw_fd(), libc::SECCOMP_IOCTL_NOTIF_SEND as _, &mut response)
+    };
+
+    match result {
+        -1 => Err(IoError::last_os_error()),
+        _ => Ok(()),
+    }
+}
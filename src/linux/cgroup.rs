@@ -0,0 +1,147 @@
+//! Cgroup v2 resource limits.
+//!
+//! Unlike rlimits, cgroup limits apply to the sandbox as a whole, so they
+//! cannot be bypassed by spawning additional processes.
+//!
+//! Each sandbox gets a transient cgroup beneath the root cgroup, which the
+//! sandbox's PID 1 is moved into before it spawns the sandboxee.
+//!
+//! Controllers can only be enabled for child cgroups of a cgroup without
+//! processes, with the exception of the root cgroup. Since the calling
+//! process is part of its own cgroup, limits are only supported if the caller
+//! is in the root cgroup of the host, not just the root of a cgroup namespace.
+
+use std::io::{self, ErrorKind as IoErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{fs, process};
+
+use rustix::fs::Access;
+
+/// Root of the unified cgroup v2 hierarchy.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Counter to ensure cgroup names are unique within the current process.
+static CGROUP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Limits applied to the sandbox's cgroup.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CgroupLimits {
+    /// Memory limit in bytes (`memory.max`).
+    pub memory_max: Option<u64>,
+    /// CPU bandwidth as period and quota in microseconds (`cpu.max`).
+    pub cpu_max: Option<(u64, u64)>,
+}
+
+impl CgroupLimits {
+    /// Check if no cgroup limits are set.
+    pub fn is_empty(&self) -> bool {
+        self.memory_max.is_none() && self.cpu_max.is_none()
+    }
+
+    /// Check whether a cgroup with all required controllers can be created.
+    ///
+    /// This is `false` on systems without a unified cgroup v2 hierarchy, or if
+    /// the calling process is not in the writable root cgroup.
+    pub fn is_supported(&self) -> bool {
+        let root = match root_cgroup() {
+            Some(root) => root,
+            None => return false,
+        };
+
+        if rustix::fs::access(&root, Access::WRITE_OK).is_err() {
+            return false;
+        }
+
+        let controllers = match fs::read_to_string(root.join("cgroup.controllers")) {
+            Ok(controllers) => controllers,
+            Err(_) => return false,
+        };
+
+        self.controllers()
+            .iter()
+            .all(|controller| controllers.split_whitespace().any(|c| c == *controller))
+    }
+
+    /// Get all controllers required for these limits.
+    fn controllers(&self) -> Vec<&'static str> {
+        let mut controllers = Vec::new();
+        if self.memory_max.is_some() {
+            controllers.push("memory");
+        }
+        if self.cpu_max.is_some() {
+            controllers.push("cpu");
+        }
+        controllers
+    }
+}
+
+/// Transient cgroup, which is removed on drop.
+///
+/// Removal only succeeds once all processes in the cgroup have exited.
+pub struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    /// Create a new cgroup with the specified limits.
+    pub fn new(limits: &CgroupLimits) -> io::Result<Self> {
+        let root = root_cgroup()
+            .ok_or_else(|| io::Error::new(IoErrorKind::NotFound, "not in the root cgroup"))?;
+
+        // Delegate the required controllers to the sandbox's cgroup.
+        let controllers: Vec<_> = limits.controllers().iter().map(|c| format!("+{c}")).collect();
+        fs::write(root.join("cgroup.subtree_control"), controllers.join(" "))?;
+
+        let id = CGROUP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = root.join(format!("birdcage-{}-{id}", process::id()));
+        fs::create_dir(&path)?;
+        let cgroup = Self { path };
+
+        if let Some(bytes) = limits.memory_max {
+            fs::write(cgroup.path.join("memory.max"), bytes.to_string())?;
+
+            // Prevent the sandbox from exceeding its limit by swapping.
+            let swap_max = cgroup.path.join("memory.swap.max");
+            if swap_max.exists() {
+                fs::write(swap_max, "0")?;
+            }
+        }
+
+        if let Some((period_us, quota_us)) = limits.cpu_max {
+            fs::write(cgroup.path.join("cpu.max"), format!("{quota_us} {period_us}"))?;
+        }
+
+        Ok(cgroup)
+    }
+
+    /// Move a process into this cgroup.
+    pub fn add_process(&self, pid: i32) -> io::Result<()> {
+        fs::write(self.path.join("cgroup.procs"), pid.to_string())
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir(&self.path);
+    }
+}
+
+/// Get the path of the root cgroup, if the calling process is part of it.
+fn root_cgroup() -> Option<PathBuf> {
+    // The unified hierarchy is listed with ID 0 and no controllers.
+    let cgroups = fs::read_to_string("/proc/self/cgroup").ok()?;
+    let path = cgroups.lines().find_map(|line| line.strip_prefix("0::"))?;
+    if path != "/" {
+        return None;
+    }
+
+    // Inside a cgroup namespace, the namespace's root is a regular cgroup,
+    // which unlike the host's root cgroup has a type.
+    let root = Path::new(CGROUP_ROOT);
+    if root.join("cgroup.type").exists() {
+        return None;
+    }
+
+    Some(root.to_path_buf())
+}
@@ -0,0 +1,234 @@
+//! Linux Landlock filesystem restrictions.
+//!
+//! Landlock is available since Linux 5.13, access rights which are not
+//! supported by the running kernel are masked out before creating a ruleset.
+
+use std::ffi::CString;
+use std::io::Error as IoError;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::{io, mem, ptr};
+
+use bitflags::bitflags;
+
+/// Landlock ABI required for [`AccessFs::TRUNCATE`].
+pub const TRUNCATE_ABI: u32 = 3;
+
+/// Flag for `landlock_create_ruleset` to query the supported ABI version.
+const CREATE_RULESET_VERSION: u32 = 1 << 0;
+
+/// Rule type for `landlock_add_rule` restricting a file hierarchy.
+const RULE_PATH_BENEATH: libc::c_int = 1;
+
+/// Get the highest Landlock ABI version supported by the kernel.
+///
+/// Returns `0` if Landlock is unsupported or disabled.
+pub fn abi_version() -> u32 {
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            ptr::null::<RulesetAttr>(),
+            0,
+            CREATE_RULESET_VERSION,
+        )
+    };
+
+    result.try_into().unwrap_or(0)
+}
+
+/// Deny truncation of all files, except for files beneath `allowed`.
+///
+/// Paths are resolved in the current mount namespace, so this must be called
+/// after the sandbox's new root is in place.
+///
+/// This is a noop if truncation cannot be restricted with the Landlock `abi`.
+pub fn restrict_truncation<'a>(
+    abi: u32,
+    allowed: impl IntoIterator<Item = &'a Path>,
+) -> io::Result<()> {
+    restrict_beneath(abi, AccessFs::TRUNCATE, allowed)
+}
+
+/// Deny reading the content of all files, except for files beneath `allowed`.
+///
+/// Paths are resolved in the current mount namespace, so this must be called
+/// after the sandbox's new root is in place.
+pub fn restrict_file_reads<'a>(
+    abi: u32,
+    allowed: impl IntoIterator<Item = &'a Path>,
+) -> io::Result<()> {
+    restrict_beneath(abi, AccessFs::READ_FILE, allowed)
+}
+
+/// Deny opening files for writing, except for files beneath `allowed`.
+///
+/// Paths are resolved in the current mount namespace, so this must be called
+/// after the sandbox's new root is in place.
+pub fn restrict_file_writes<'a>(
+    abi: u32,
+    allowed: impl IntoIterator<Item = &'a Path>,
+) -> io::Result<()> {
+    restrict_beneath(abi, AccessFs::WRITE_FILE, allowed)
+}
+
+/// Deny `access` everywhere, except for paths beneath `allowed`.
+///
+/// This is a noop if `access` is not supported by the Landlock `abi`.
+fn restrict_beneath<'a>(
+    abi: u32,
+    access: AccessFs,
+    allowed: impl IntoIterator<Item = &'a Path>,
+) -> io::Result<()> {
+    let Some(mut ruleset) = Ruleset::new(abi, access)? else {
+        return Ok(());
+    };
+    event!(target: "birdcage::linux", "creating Landlock ruleset handling {access:?}");
+    for path in allowed {
+        event!(target: "birdcage::linux", "allowing {access:?} beneath {path:?}");
+        ruleset.add_path_rule(path, access)?;
+    }
+    ruleset.restrict_self()
+}
+
+/// Landlock ruleset.
+struct Ruleset {
+    fd: OwnedFd,
+    handled: AccessFs,
+}
+
+impl Ruleset {
+    /// Create a new ruleset denying all `handled` access by default.
+    ///
+    /// Returns `None` if none of the `handled` access rights are supported by
+    /// the Landlock `abi`.
+    fn new(abi: u32, handled: AccessFs) -> io::Result<Option<Self>> {
+        let handled = handled & AccessFs::supported(abi);
+        if handled.is_empty() {
+            return Ok(None);
+        }
+
+        let attr = RulesetAttr { handled_access_fs: handled.bits() };
+
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const _,
+                mem::size_of::<RulesetAttr>(),
+                0,
+            )
+        };
+
+        if fd < 0 {
+            return Err(IoError::last_os_error());
+        }
+
+        Ok(Some(Self { fd: unsafe { OwnedFd::from_raw_fd(fd as _) }, handled }))
+    }
+
+    /// Allow `access` for everything beneath `path`.
+    fn add_path_rule(&mut self, path: &Path, access: AccessFs) -> io::Result<()> {
+        let path_c = CString::new(path.as_os_str().as_bytes())?;
+        let parent_fd = unsafe { libc::open(path_c.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+        if parent_fd < 0 {
+            return Err(IoError::last_os_error());
+        }
+        let parent_fd = unsafe { OwnedFd::from_raw_fd(parent_fd) };
+
+        let access = access & self.handled;
+        let attr =
+            PathBeneathAttr { allowed_access: access.bits(), parent_fd: parent_fd.as_raw_fd() };
+
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                self.fd.as_raw_fd(),
+                RULE_PATH_BENEATH,
+                &attr as *const _,
+                0,
+            )
+        };
+
+        match result {
+            0 => Ok(()),
+            _ => Err(IoError::last_os_error()),
+        }
+    }
+
+    /// Enforce the ruleset on the current thread and all its future children.
+    fn restrict_self(self) -> io::Result<()> {
+        let result =
+            unsafe { libc::syscall(libc::SYS_landlock_restrict_self, self.fd.as_raw_fd(), 0) };
+
+        match result {
+            0 => Ok(()),
+            _ => Err(IoError::last_os_error()),
+        }
+    }
+}
+
+/// Parameter for the `landlock_create_ruleset` syscall.
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+/// Parameter for the `landlock_add_rule` syscall.
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+bitflags! {
+    /// Landlock filesystem access rights.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct AccessFs: u64 {
+        /// Execute a file.
+        const EXECUTE     = 1 << 0;
+        /// Open a file with write access.
+        const WRITE_FILE  = 1 << 1;
+        /// Open a file with read access.
+        const READ_FILE   = 1 << 2;
+        /// Open a directory or list its content.
+        const READ_DIR    = 1 << 3;
+        /// Remove an empty directory or rename one.
+        const REMOVE_DIR  = 1 << 4;
+        /// Unlink or rename a file.
+        const REMOVE_FILE = 1 << 5;
+        /// Create, rename or link a character device.
+        const MAKE_CHAR   = 1 << 6;
+        /// Create or rename a directory.
+        const MAKE_DIR    = 1 << 7;
+        /// Create, rename or link a regular file.
+        const MAKE_REG    = 1 << 8;
+        /// Create, rename or link a UNIX domain socket.
+        const MAKE_SOCK   = 1 << 9;
+        /// Create, rename or link a named pipe.
+        const MAKE_FIFO   = 1 << 10;
+        /// Create, rename or link a block device.
+        const MAKE_BLOCK  = 1 << 11;
+        /// Create, rename or link a symbolic link.
+        const MAKE_SYM    = 1 << 12;
+        /// Link or rename a file from or to a different directory.
+        const REFER       = 1 << 13;
+        /// Truncate a file with `truncate`, `ftruncate`, `creat` or `O_TRUNC`.
+        const TRUNCATE    = 1 << 14;
+        /// Invoke `ioctl` commands on an opened character or block device.
+        const IOCTL_DEV   = 1 << 15;
+    }
+}
+
+impl AccessFs {
+    /// Get all access rights supported by a Landlock ABI version.
+    pub fn supported(abi: u32) -> Self {
+        let abi_1 = Self::from_bits_truncate((Self::MAKE_SYM.bits() << 1) - 1);
+        match abi {
+            0 => Self::empty(),
+            1 => abi_1,
+            2 => abi_1 | Self::REFER,
+            3 | 4 => abi_1 | Self::REFER | Self::TRUNCATE,
+            _ => Self::all(),
+        }
+    }
+}
@@ -1,50 +1,343 @@
 //! Linux sandboxing.
 
-use std::collections::HashMap;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::ffi::CString;
+use std::fmt::Write;
+use std::fs::{File, OpenOptions};
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-use std::os::fd::OwnedFd;
+use std::os::fd::{AsRawFd, OwnedFd};
 use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, OpenOptionsExt, PermissionsExt};
+use std::os::unix::process::CommandExt;
 use std::path::{Component, Path, PathBuf};
-use std::{env, fs, io, ptr};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use std::{env, fs, io, mem, ptr};
 
 use rustix::pipe::pipe;
-use rustix::process::{Gid, Pid, Uid, WaitOptions};
+use rustix::process::{Gid, Pid, PidfdFlags, Uid, WaitOptions};
+use seccompiler::BpfProgram;
 
 use crate::error::{Error, Result};
+use crate::linux::audit::{AuditForwarder, AuditLog, AuditRules};
+use crate::linux::cgroup::CgroupLimits;
 use crate::linux::namespaces::{MountAttrFlags, Namespaces};
-use crate::linux::seccomp::SyscallFilter;
-use crate::{Child, Command, Exception, Sandbox};
+use crate::linux::network::{NetworkRules, Supervisor};
+use crate::linux::seccomp::{
+    AuditFilter, NetworkFilter, ProcessFilter, ProtocolFilter, SyscallFilter,
+};
+use crate::process::{FdMappings, TempDir, EXIT_SIGNAL_TIMEOUT};
+use crate::{
+    Capabilities, CapabilitySet, Child, Command, Exception, ResourceLimits, Sandbox,
+    SandboxWarning, SupportedExceptions,
+};
 
+mod audit;
+mod cgroup;
+mod landlock;
 mod namespaces;
+mod network;
 mod seccomp;
 
+pub(crate) use crate::linux::cgroup::Cgroup;
+
+/// Whether the current process was sandboxed with [`Sandbox::lock`].
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// PID of the sandboxee, used by PID 1 to forward signals.
+static SANDBOXEE_PID: AtomicI32 = AtomicI32::new(0);
+
+/// Signals received by PID 1 before the sandboxee was spawned.
+static PENDING_SIGNALS: AtomicU64 = AtomicU64::new(0);
+
+/// Maximum number of symlinks followed when resolving an exception path.
+///
+/// This matches the kernel's limit for path resolution.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Signals forwarded from the sandbox's PID 1 to the sandboxee.
+const FORWARDED_SIGNALS: [libc::c_int; 11] = [
+    libc::SIGHUP,
+    libc::SIGINT,
+    libc::SIGQUIT,
+    libc::SIGUSR1,
+    libc::SIGUSR2,
+    libc::SIGTERM,
+    libc::SIGCONT,
+    libc::SIGALRM,
+    libc::SIGWINCH,
+    libc::SIGTTIN,
+    libc::SIGTTOU,
+];
+
 /// Linux sandboxing.
 #[derive(Default)]
 pub struct LinuxSandbox {
-    env_exceptions: Vec<String>,
-    custom_env: Option<HashMap<String, String>>,
+    env_filter: EnvFilter,
     path_exceptions: PathExceptions,
+    network_rules: NetworkRules,
+    protocol_filter: Option<ProtocolFilter>,
+    syscall_filter: SyscallFilter,
+    resource_limits: ResourceLimits,
+    dropped_capabilities: CapabilitySet,
+    cgroup_limits: CgroupLimits,
+    exceptions: Vec<Exception>,
+    landlock_abi: u32,
+    uid_map: Vec<IdMap>,
+    gid_map: Vec<IdMap>,
+    audit_log: Option<AuditLog>,
+    temp_dirs: Vec<TempDir>,
     allow_networking: bool,
-    full_env: bool,
+    deny_subprocesses: bool,
+    drop_privileges: Option<(u32, u32)>,
+    network_namespace: NetworkNamespace,
+    proc_access: ProcAccess,
+    proc_self: bool,
+    uts_namespace: bool,
+    hostname: Option<String>,
+    base_dir: Option<PathBuf>,
+    strict_landlock: bool,
+    seccomp_best_effort: bool,
+}
+
+/// Network namespace of the sandbox.
+///
+/// See `LinuxSandbox::with_network_namespace`.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NetworkNamespace {
+    /// Share the host's network namespace if networking is allowed.
+    ///
+    /// Without any network exceptions, the sandbox still gets an isolated
+    /// network namespace.
+    #[default]
+    Host,
+    /// Always use a new network namespace without any network interfaces.
+    Isolated,
+    /// Always use a new network namespace with only the loopback interface.
+    LoopbackOnly,
+}
+
+/// Access to `/proc` inside the sandbox.
+///
+/// See `LinuxSandbox::proc_access`.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ProcAccess {
+    /// Expose all processes of the sandbox and system-wide files like
+    /// `/proc/cpuinfo`.
+    #[default]
+    Full,
+    /// Only expose the sandboxee's own processes.
+    ///
+    /// Process directories like `/proc/self/maps` remain accessible, while
+    /// system-wide files like `/proc/cpuinfo` and `/proc/meminfo` are hidden.
+    SelfOnly,
+    /// Replace `/proc` with an empty directory.
+    Hidden,
+}
+
+/// Whether sandbox restrictions are enforced.
+///
+/// See `LinuxSandbox::mode`.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SandboxMode {
+    /// Deny all accesses without a matching exception.
+    #[default]
+    Enforce,
+    /// Report accesses without a matching exception instead of denying them.
+    Audit,
+}
+
+/// File access reported in audit mode.
+///
+/// See `LinuxSandbox::with_audit_log`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SandboxViolation {
+    /// Name of the syscall, like `openat` or `execve`.
+    pub syscall: String,
+    /// Denied kind of access: `read`, `write`, `truncate` or `execute`.
+    pub access: String,
+    /// Absolute path of the accessed file.
+    pub path: Option<PathBuf>,
+    /// Time at which the violation was received by the calling process.
+    pub timestamp: Instant,
+}
+
+/// User or group ID mapping for the sandbox's user namespace.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct IdMap {
+    /// First ID inside the sandbox.
+    pub inside_id: u32,
+    /// First ID outside the sandbox.
+    pub outside_id: u32,
+    /// Number of consecutive IDs mapped.
+    pub count: u32,
+}
+
+/// System call number.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Syscall(libc::c_long);
+
+impl Syscall {
+    /// Load and manage eBPF programs (`bpf`).
+    pub const BPF: Self = Self(libc::SYS_bpf);
+    /// Submit io_uring operations (`io_uring_enter`).
+    pub const IO_URING_ENTER: Self = Self(libc::SYS_io_uring_enter);
+    /// Register io_uring resources (`io_uring_register`).
+    pub const IO_URING_REGISTER: Self = Self(libc::SYS_io_uring_register);
+    /// Create an io_uring instance (`io_uring_setup`).
+    pub const IO_URING_SETUP: Self = Self(libc::SYS_io_uring_setup);
+    /// Manage the kernel's key retention service (`keyctl`).
+    pub const KEYCTL: Self = Self(libc::SYS_keyctl);
+    /// Open performance monitoring events (`perf_event_open`).
+    pub const PERF_EVENT_OPEN: Self = Self(libc::SYS_perf_event_open);
+    /// Read another process's memory (`process_vm_readv`).
+    pub const PROCESS_VM_READV: Self = Self(libc::SYS_process_vm_readv);
+    /// Write another process's memory (`process_vm_writev`).
+    pub const PROCESS_VM_WRITEV: Self = Self(libc::SYS_process_vm_writev);
+    /// Trace and modify other processes (`ptrace`).
+    pub const PTRACE: Self = Self(libc::SYS_ptrace);
+    /// Handle page faults in user space (`userfaultfd`).
+    pub const USERFAULTFD: Self = Self(libc::SYS_userfaultfd);
+
+    /// Create a syscall from its number, like [`libc::SYS_getpid`].
+    pub const fn from_raw(nr: libc::c_long) -> Self {
+        Self(nr)
+    }
+
+    /// Get the syscall number.
+    pub const fn as_raw(self) -> libc::c_long {
+        self.0
+    }
+}
+
+/// Action taken when the sandboxee performs a syscall.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SeccompAction {
+    /// Allow the syscall.
+    Allow,
+    /// Fail the syscall with the specified errno.
+    ErrnoReturn(i32),
+    /// Kill the sandboxed process.
+    Kill,
 }
 
 impl Sandbox for LinuxSandbox {
     fn new() -> Self {
-        Self::default()
+        Self { landlock_abi: landlock::abi_version(), ..Self::default() }
     }
 
     fn add_exception(&mut self, exception: Exception) -> Result<&mut Self> {
+        let exception = crate::resolve_relative_paths(exception, self.base_dir.as_deref());
+        let recorded = exception.clone();
+
         match exception {
             Exception::Read(path) => self.path_exceptions.update(path, false, false)?,
+            Exception::ReadFile(path) => {
+                let metadata =
+                    path.metadata().map_err(|err| Error::from_path(path.clone(), &err))?;
+                if !metadata.is_file() {
+                    return Err(Error::InvalidPath(path));
+                }
+                self.path_exceptions.update(path, false, false)?;
+            },
+            Exception::ReadExact(path) => self.path_exceptions.update_exact(path)?,
+            Exception::ReadGlob(pattern) => {
+                for path in crate::glob::expand(&pattern)? {
+                    self.path_exceptions.update(path, false, false)?;
+                }
+            },
+            Exception::ReadShallow(path) => {
+                for entry in crate::directory_entries(&path)? {
+                    match self.path_exceptions.update_exact(entry) {
+                        // Broken symlinks, escaping symlinks and denied entries are skipped.
+                        Ok(())
+                        | Err(
+                            Error::PathNotFound(_)
+                            | Error::SymlinkEscape(_)
+                            | Error::ConflictingPath(_),
+                        ) => (),
+                        Err(err) => return Err(err),
+                    }
+                }
+                self.path_exceptions.update_exact(path)?;
+            },
+            Exception::ReadDir(path) => self.path_exceptions.update_list_only(path)?,
             Exception::WriteAndRead(path) => self.path_exceptions.update(path, true, false)?,
             Exception::ExecuteAndRead(path) => self.path_exceptions.update(path, false, true)?,
-            Exception::Environment(key) => self.env_exceptions.push(key),
-            Exception::FullEnvironment => self.full_env = true,
-            Exception::CustomEnvironment(env_map) => self.custom_env = Some(env_map),
+            Exception::AppendOnly(path) => self.path_exceptions.update_append_only(path)?,
+            Exception::Deny(path) => self.path_exceptions.deny(path)?,
+            Exception::Environment(key) => self.env_filter.exceptions.push(key),
+            Exception::EnvironmentPrefix(prefix) => self.env_filter.prefixes.push(prefix),
+            Exception::EnvironmentPattern(pattern) => self.env_filter.patterns.push(pattern),
+            Exception::FullEnvironment => self.env_filter.full_env = true,
+            Exception::ProcSelf => self.proc_self = true,
+            Exception::CustomEnvironment(env_map) => {
+                crate::validate_env_variables(&env_map)?;
+                self.env_filter.custom_env = Some(env_map);
+            },
+            Exception::SetEnvironment(env_map) => {
+                crate::validate_env_variables(&env_map)?;
+                self.env_filter.set_env.extend(env_map);
+            },
             Exception::Networking => self.allow_networking = true,
+            Exception::UnixSocket(path) => {
+                // Connecting to a socket requires write access on its inode.
+                // Other sockets are rejected by the network supervisor.
+                let metadata =
+                    path.metadata().map_err(|err| Error::from_path(path.clone(), &err))?;
+                if !metadata.file_type().is_socket() {
+                    return Err(Error::InvalidPath(path));
+                }
+                self.path_exceptions.update(path.clone(), true, false)?;
+                self.network_rules.allow_unix(path)?;
+            },
+            Exception::NetworkProtocol { tcp, udp } => {
+                let filter = self.protocol_filter.get_or_insert_with(ProtocolFilter::default);
+                filter.tcp |= tcp;
+                filter.udp |= udp;
+                self.allow_networking = true;
+            },
+            Exception::ResourceLimits(limits) => {
+                limits.validate()?;
+                self.resource_limits.merge(limits);
+            },
+            Exception::NetworkConnect(addr) => self.network_rules.allow_connect(addr),
+            Exception::NetworkBind(addr) => self.network_rules.allow_bind(addr),
+            Exception::NetworkOutbound => self.network_rules.allow_connect_all(),
+            Exception::NetworkInbound => self.network_rules.allow_bind_all(),
+            Exception::NetworkHost { host, port } => self.network_rules.allow_host(host, port)?,
+            Exception::NetworkSubnet { addr, prefix_len } => {
+                crate::validate_subnet(addr, prefix_len)?;
+                self.network_rules.allow_subnet(addr, prefix_len);
+            },
+            Exception::SharedMemory(name) => {
+                self.path_exceptions.update(crate::shm_path(&name)?, true, false)?;
+            },
+            Exception::SharedMemoryCreate(name) => {
+                let path = crate::shm_path(&name)?;
+                self.path_exceptions.add_shared_memory(path);
+            },
+            Exception::DropCapabilities(capabilities) => {
+                self.dropped_capabilities = self.dropped_capabilities.insert(capabilities);
+            },
+            Exception::DropAllCapabilities => self.dropped_capabilities = CapabilitySet::all(),
+            Exception::Device(device) => {
+                device.validate()?;
+                let path = device.path().to_path_buf();
+                match device.stdio_fd() {
+                    Some(fd) => {
+                        self.path_exceptions.add_symlink(path, format!("/proc/self/fd/{fd}"))
+                    },
+                    None => self.path_exceptions.update(path, device.is_writable(), false)?,
+                }
+            },
         }
 
+        crate::record_exception(&mut self.exceptions, recorded);
+
         Ok(self)
     }
 
@@ -55,12 +348,1013 @@ impl Sandbox for LinuxSandbox {
             "`Sandbox::spawn` must be called from a single-threaded process"
         );
 
+        self.validate()?;
+        self.check_current_dir(&sandboxee)?;
+        self.check_ids(&sandboxee)?;
+
         // Remove/replace environment variables.
-        if let Some(ref custom_env) = self.custom_env {
-            crate::restrict_env_variables_with_custom(&[], Some(custom_env));
-        } else if !self.full_env {
-            crate::restrict_env_variables(&self.env_exceptions);
+        if self.audit_log.is_none() {
+            self.env_filter.apply();
+        }
+
+        self.spawn_init(sandboxee, false)
+    }
+
+    fn spawn_isolated(self, sandboxee: Command) -> Result<Child> {
+        // Ensure calling process is not multi-threaded.
+        assert!(
+            thread_count().unwrap_or(0) == 1,
+            "`Sandbox::spawn_isolated` must be called from a single-threaded process"
+        );
+
+        self.validate()?;
+        self.check_current_dir(&sandboxee)?;
+        self.check_ids(&sandboxee)?;
+
+        // Environment variables are restricted by PID 1 instead.
+        self.spawn_init(sandboxee, true)
+    }
+
+    fn lock(mut self) -> Result<()> {
+        // Ensure calling process is not multi-threaded.
+        assert!(
+            thread_count().unwrap_or(0) == 1,
+            "`Sandbox::lock` must be called from a single-threaded process"
+        );
+
+        self.validate()?;
+
+        // Skip syscall filtering in best-effort mode.
+        if !seccomp::is_available() {
+            self.syscall_filter.disable();
+        }
+
+        // Granular network exceptions require a separate supervisor process.
+        if !self.allow_networking && !self.network_rules.is_empty() {
+            return Err(Error::ActivationFailed(
+                "granular network exceptions are not supported by `Sandbox::lock`".into(),
+            ));
+        }
+
+        // Auditing also requires a separate supervisor process.
+        if self.audit_log.is_some() {
+            return Err(Error::ActivationFailed(
+                "audit mode is not supported by `Sandbox::lock`".into(),
+            ));
+        }
+
+        // Restricting procfs requires mounting a new procfs for a new PID namespace.
+        if self.effective_proc_access() == ProcAccess::SelfOnly {
+            return Err(Error::ActivationFailed(
+                "`ProcAccess::SelfOnly` is not supported by `Sandbox::lock`".into(),
+            ));
+        }
+
+        // Cgroups are removed once the sandbox exits, which requires a child process.
+        if !self.cgroup_limits.is_empty() {
+            return Err(Error::ActivationFailed(
+                "cgroup limits are not supported by `Sandbox::lock`".into(),
+            ));
+        }
+
+        // Only mark the process as locked once no unsupported settings were rejected.
+        if LOCKED.swap(true, Ordering::SeqCst) {
+            return Err(Error::AlreadyLocked);
+        }
+
+        self.path_exceptions.create_shared_memory()?;
+
+        // Remove/replace environment variables.
+        self.env_filter.apply();
+
+        // Switch to the unprivileged user before entering the user namespace.
+        if let Some((uid, gid)) = self.drop_privileges {
+            set_ids(uid, gid)?;
+        }
+
+        // Temporary directories must outlive the sandbox.
+        mem::forget(mem::take(&mut self.temp_dirs));
+
+        // Get UID/GID of the sandboxed process.
+        let ids = self.sandboxee_ids();
+
+        // Enter new namespaces with a root user mapping.
+        let mut extra_namespaces = Namespaces::MOUNT | Namespaces::IPC;
+        if !self.allow_networking || self.network_namespace != NetworkNamespace::Host {
+            extra_namespaces |= Namespaces::NETWORK;
+        }
+        if self.uts_namespace {
+            extra_namespaces |= Namespaces::UTS;
+        }
+        namespaces::create_user_namespace(0, 0, extra_namespaces)?;
+
+        if let Some(hostname) = &self.hostname {
+            namespaces::set_hostname(hostname)?;
+        }
+
+        if self.network_namespace == NetworkNamespace::LoopbackOnly {
+            namespaces::enable_loopback()?;
+        }
+
+        // Since a PID namespace cannot be entered by the current process, the existing
+        // procfs is kept.
+        let proc_mount = match self.effective_proc_access() {
+            ProcAccess::Hidden => ProcMount::Hidden,
+            _ => ProcMount::Existing,
+        };
+        let protocol_filter = self.protocol_filter;
+        lockdown(
+            self.path_exceptions,
+            None,
+            protocol_filter,
+            self.landlock_abi,
+            self.syscall_filter,
+            ids,
+            proc_mount,
+        )?;
+
+        // Restrict resource usage.
+        self.resource_limits.apply()?;
+        self.dropped_capabilities.drop()?;
+
+        // Prevent spawning subprocesses.
+        if self.deny_subprocesses {
+            let process_filter = ProcessFilter::program(false)?;
+            seccompiler::apply_filter(&process_filter)?;
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<Vec<SandboxWarning>> {
+        self.ensure_supported()?;
+
+        let mut warnings = self.path_exceptions.warnings();
+
+        if self.landlock_abi == 0 {
+            warnings.push(SandboxWarning::LandlockUnavailable);
+        }
+
+        let env_filter = &self.env_filter;
+        if env_filter.custom_env.is_some()
+            && (env_filter.full_env
+                || !env_filter.exceptions.is_empty()
+                || !env_filter.prefixes.is_empty()
+                || !env_filter.patterns.is_empty())
+        {
+            warnings.push(SandboxWarning::EnvironmentIgnored);
+        }
+
+        if self.allow_networking && !self.network_rules.is_empty() {
+            warnings.push(SandboxWarning::NetworkExceptionsIgnored);
+        } else if self.network_rules.is_redundant() {
+            warnings.push(SandboxWarning::NetworkExceptionsRedundant);
+        }
+
+        if !self.cgroup_limits.is_empty() && !self.cgroup_limits.is_supported() {
+            warnings.push(SandboxWarning::CgroupUnavailable);
+        }
+
+        if !seccomp::is_available() {
+            warnings.push(SandboxWarning::SeccompUnavailable);
+        }
+
+        warnings.append(&mut crate::loader_env_warnings(&self.exceptions));
+
+        Ok(warnings)
+    }
+
+    fn profile_preview(&self) -> String {
+        let exceptions = &self.path_exceptions;
+        let mut preview = String::new();
+
+        // Writing to a string cannot fail, so errors are ignored.
+        if self.audit_log.is_some() {
+            let _ = writeln!(preview, "audit: violations are logged instead of denied");
+        }
+
+        for (path, flags) in exceptions.sorted_bind_mounts() {
+            let read = 'r';
+            let write = if flags.contains(MountAttrFlags::RDONLY) { '-' } else { 'w' };
+            let execute = if flags.contains(MountAttrFlags::NOEXEC) { '-' } else { 'x' };
+            let append = if exceptions.append_only.contains(path) { " (append-only)" } else { "" };
+            let list = if exceptions.list_only.contains(path) { " (list-only)" } else { "" };
+            let _ = writeln!(preview, "mount {read}{write}{execute} {path:?}{append}{list}");
+        }
+
+        let mut exact: Vec<_> = exceptions.exact.iter().collect();
+        exact.sort_unstable();
+        for path in exact {
+            let _ = writeln!(preview, "directory {path:?} (entries hidden)");
+        }
+
+        for (source, target, flags) in &exceptions.remapped {
+            let write = if flags.contains(MountAttrFlags::RDONLY) { '-' } else { 'w' };
+            let _ = writeln!(preview, "mount r{write}x {source:?} at {target:?}");
+        }
+
+        for path in &exceptions.shared_memory {
+            let _ = writeln!(preview, "mount rw- {path:?} (created on spawn)");
+        }
+
+        for (symlink, target) in &exceptions.symlinks {
+            let _ = writeln!(preview, "symlink {symlink:?} -> {target:?}");
+        }
+
+        let mut denied: Vec<_> = exceptions.denied.iter().collect();
+        denied.sort_unstable();
+        for path in denied {
+            let _ = writeln!(preview, "deny {path:?}");
+        }
+
+        if let Some(mut truncate_paths) = exceptions.truncate_paths() {
+            truncate_paths.sort_unstable();
+            let _ = writeln!(preview, "landlock: truncation only allowed for {truncate_paths:?}");
+        }
+
+        if let Some(mut read_paths) = exceptions.read_paths() {
+            read_paths.sort_unstable();
+            let _ = writeln!(preview, "landlock: file reads only allowed for {read_paths:?}");
+        }
+
+        if let Some(mut write_paths) = exceptions.write_paths() {
+            write_paths.sort_unstable();
+            let _ = writeln!(preview, "landlock: file writes only allowed for {write_paths:?}");
+        }
+
+        if self.network_namespace == NetworkNamespace::Isolated {
+            let _ = writeln!(preview, "network: isolated");
+        } else if self.network_namespace == NetworkNamespace::LoopbackOnly {
+            let _ = writeln!(preview, "network: loopback only");
+        } else if let Some(protocol_filter) = &self.protocol_filter {
+            let _ = writeln!(preview, "network: host ({})", protocol_filter.summary());
+        } else if self.allow_networking {
+            let _ = writeln!(preview, "network: host");
+        } else if self.network_rules.is_empty() {
+            let _ = writeln!(preview, "network: isolated");
+        } else {
+            let _ = writeln!(preview, "network: {}", self.network_rules.summary());
+        }
+
+        if self.uts_namespace {
+            let _ = match &self.hostname {
+                Some(hostname) => writeln!(preview, "hostname: {hostname:?}"),
+                None => writeln!(preview, "hostname: isolated"),
+            };
+        }
+
+        let env_filter = &self.env_filter;
+        let env = crate::env_preview(
+            &env_filter.exceptions,
+            &env_filter.prefixes,
+            &env_filter.patterns,
+            env_filter.full_env,
+            env_filter.custom_env.as_ref(),
+            &env_filter.set_env,
+        );
+        let _ = writeln!(preview, "environment: {env}");
+
+        if seccomp::is_available() {
+            let _ = writeln!(preview, "seccomp: {}", self.syscall_filter.summary());
+        } else {
+            let _ = writeln!(preview, "seccomp: unavailable");
+        }
+
+        if self.deny_subprocesses {
+            let _ = writeln!(preview, "processes: subprocesses denied");
+        }
+
+        match self.effective_proc_access() {
+            ProcAccess::Full => (),
+            ProcAccess::SelfOnly => {
+                let _ = writeln!(preview, "procfs: only own processes");
+            },
+            ProcAccess::Hidden => {
+                let _ = writeln!(preview, "procfs: hidden");
+            },
+        }
+
+        preview
+    }
+
+    fn exceptions(&self) -> &[Exception] {
+        &self.exceptions
+    }
+
+    fn clone_config(&self) -> Self {
+        // Fall back to enforcing the policy if the audit log cannot be shared.
+        let audit_log = self.audit_log.as_ref().and_then(|log| log.try_clone().ok());
+        let mut syscall_filter = self.syscall_filter.clone();
+        if audit_log.is_none() {
+            syscall_filter.set_audit(false);
+        }
+
+        Self {
+            env_filter: self.env_filter.clone(),
+            path_exceptions: self.path_exceptions.clone(),
+            network_rules: self.network_rules.clone(),
+            protocol_filter: self.protocol_filter,
+            syscall_filter,
+            resource_limits: self.resource_limits,
+            dropped_capabilities: self.dropped_capabilities,
+            cgroup_limits: self.cgroup_limits,
+            exceptions: self.exceptions.clone(),
+            landlock_abi: self.landlock_abi,
+            uid_map: self.uid_map.clone(),
+            gid_map: self.gid_map.clone(),
+            audit_log,
+            // Temporary directories stay owned by the original sandbox.
+            temp_dirs: Vec::new(),
+            allow_networking: self.allow_networking,
+            deny_subprocesses: self.deny_subprocesses,
+            drop_privileges: self.drop_privileges,
+            network_namespace: self.network_namespace,
+            proc_access: self.proc_access,
+            proc_self: self.proc_self,
+            uts_namespace: self.uts_namespace,
+            hostname: self.hostname.clone(),
+            base_dir: self.base_dir.clone(),
+            strict_landlock: self.strict_landlock,
+            seccomp_best_effort: self.seccomp_best_effort,
+        }
+    }
+
+    fn allow_subprocesses(&mut self, allow: bool) -> &mut Self {
+        self.deny_subprocesses = !allow;
+        self
+    }
+
+    fn restrict_symlink_escape(&mut self, restrict: bool) -> &mut Self {
+        self.path_exceptions.restrict_symlink_escape = restrict;
+        self
+    }
+
+    fn set_base_dir(&mut self, path: PathBuf) -> Result<&mut Self> {
+        self.base_dir = Some(crate::canonical_base_dir(path)?);
+        Ok(self)
+    }
+
+    fn supported_exceptions() -> SupportedExceptions {
+        let landlock_abi = landlock::abi_version();
+        SupportedExceptions {
+            unix_sockets: true,
+            network_direction_split: true,
+            remote_network_addresses: true,
+            network_protocols: true,
+            append_only: landlock_abi >= landlock::TRUNCATE_ABI,
+            list_only_directories: landlock_abi > 0,
+            seccomp_filtering: true,
+            user_namespaces: namespaces::user_namespaces_restricted().is_none(),
+            resource_limits: true,
+            environment_patterns: true,
+        }
+    }
+}
+
+impl LinuxSandbox {
+    /// Probe the kernel for the enforceable sandbox features.
+    ///
+    /// All sandboxes require a user namespace, while network filtering and
+    /// subprocess restrictions additionally rely on seccomp.
+    pub(crate) fn capabilities() -> Capabilities {
+        let namespaces = namespaces::user_namespaces_restricted().is_none();
+        let seccomp = namespaces && seccomp::is_available();
+        Capabilities {
+            path_exceptions: namespaces,
+            network_filtering: seccomp,
+            env_filtering: namespaces,
+            subprocess_control: seccomp,
+        }
+    }
+
+    /// Get the highest Landlock ABI version supported by the kernel.
+    ///
+    /// Returns `0` if Landlock is unsupported or disabled.
+    pub fn landlock_abi_version(&self) -> u32 {
+        self.landlock_abi
+    }
+
+    /// Get the highest Landlock ABI version supported by the kernel.
+    ///
+    /// Returns `None` if Landlock is unsupported or disabled.
+    ///
+    /// Filesystem exceptions are always enforced using mount namespaces, with
+    /// Landlock only being used for restrictions which cannot be expressed
+    /// through mount flags. If the kernel doesn't support Landlock, the
+    /// sandbox still works, but:
+    ///
+    /// - [`Sandbox::validate`] reports [`SandboxWarning::LandlockUnavailable`]
+    /// - [`Exception::ReadDir`] fails with [`Error::ActivationFailed`]
+    /// - [`Exception::AppendOnly`] fails with [`Error::ActivationFailed`] below
+    ///   ABI 3
+    ///
+    /// Use [`LinuxSandbox::strict_landlock`] to refuse sandboxing without
+    /// Landlock.
+    pub fn landlock_abi(&self) -> Option<u32> {
+        (self.landlock_abi > 0).then_some(self.landlock_abi)
+    }
+
+    /// Require Landlock for sandbox activation.
+    ///
+    /// When enabled, [`Sandbox::validate`], [`Sandbox::spawn`] and
+    /// [`Sandbox::lock`] fail with [`Error::ActivationFailed`] if the kernel
+    /// doesn't support Landlock, instead of only reporting
+    /// [`SandboxWarning::LandlockUnavailable`].
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.strict_landlock(true);
+    /// ```
+    pub fn strict_landlock(&mut self, strict: bool) -> &mut Self {
+        self.strict_landlock = strict;
+        self
+    }
+
+    /// Continue without syscall filtering if seccomp is unavailable.
+    ///
+    /// Seccomp is often unavailable inside containers, in which case
+    /// [`Sandbox::validate`], [`Sandbox::spawn`] and [`Sandbox::lock`] fail
+    /// with [`Error::SeccompUnavailable`]. In best-effort mode, the sandbox is
+    /// instead activated using only namespaces and Landlock, and
+    /// [`Sandbox::validate`] reports [`SandboxWarning::SeccompUnavailable`].
+    ///
+    /// Filesystem and environment exceptions are still fully enforced, but
+    /// without seccomp the sandboxee can:
+    ///
+    /// - use every syscall, including `ptrace`, `bpf`, `keyctl` and `io_uring`,
+    ///   exposing a much larger kernel attack surface
+    /// - create nested namespaces
+    ///
+    /// Settings which can only be enforced with seccomp still fail with
+    /// [`Error::SeccompUnavailable`]: granular network exceptions,
+    /// [`Exception::NetworkProtocol`], audit mode, custom syscall actions and
+    /// denying subprocesses with [`Sandbox::allow_subprocesses`].
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.seccomp_best_effort(true);
+    /// ```
+    pub fn seccomp_best_effort(&mut self, best_effort: bool) -> &mut Self {
+        self.seccomp_best_effort = best_effort;
+        self
+    }
+
+    /// Set the user and group ID mappings of the sandbox's user namespace.
+    ///
+    /// By default, the sandboxed process keeps the UID and GID of the calling
+    /// process. Without privileges, only the effective UID and GID of the
+    /// calling process can be mapped to a single ID, so mapping them to `0`
+    /// makes the sandboxee appear as root without granting it any additional
+    /// access to the host.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, IdMap, Sandbox};
+    ///
+    /// let uid = unsafe { libc::geteuid() };
+    /// let gid = unsafe { libc::getegid() };
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.with_user_namespace(vec![IdMap { inside_id: 0, outside_id: uid, count: 1 }], vec![
+    ///     IdMap { inside_id: 0, outside_id: gid, count: 1 },
+    /// ]);
+    /// ```
+    pub fn with_user_namespace(&mut self, uid_map: Vec<IdMap>, gid_map: Vec<IdMap>) -> &mut Self {
+        self.uid_map = uid_map;
+        self.gid_map = gid_map;
+        self
+    }
+
+    /// Allow a syscall which is denied by default.
+    ///
+    /// Syscalls which could be used to create namespaces remain restricted.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox, Syscall};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.allow_syscall(Syscall::from_raw(libc::SYS_syslog));
+    /// ```
+    pub fn allow_syscall(&mut self, syscall: Syscall) -> &mut Self {
+        self.set_syscall_action(syscall, SeccompAction::Allow)
+    }
+
+    /// Fail a syscall with a custom errno.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox, Syscall};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.deny_syscall_with(Syscall::from_raw(libc::SYS_getppid), libc::ENOSYS);
+    /// ```
+    pub fn deny_syscall_with(&mut self, syscall: Syscall, errno: i32) -> &mut Self {
+        self.set_syscall_action(syscall, SeccompAction::ErrnoReturn(errno))
+    }
+
+    /// Set the action taken when the sandboxee performs a syscall.
+    ///
+    /// Denying actions take precedence over the default filter, while
+    /// [`SeccompAction::Allow`] only applies to syscalls which are not
+    /// restricted otherwise. If the same syscall is configured multiple times,
+    /// the last action takes precedence.
+    pub fn set_syscall_action(&mut self, syscall: Syscall, action: SeccompAction) -> &mut Self {
+        self.syscall_filter.set(syscall, action);
+        self
+    }
+
+    /// Add a custom seccomp rule on top of the default filter.
+    ///
+    /// This behaves like [`LinuxSandbox::set_syscall_action`], but fails with
+    /// [`Error::ActivationFailed`] if the rule conflicts with the sandbox's
+    /// own operation. Since the filter also applies to the sandbox's PID 1,
+    /// syscalls it requires to launch and reap the sandboxee (like `execve`
+    /// or `wait4`) cannot be denied, while syscalls which could be used to
+    /// leave the sandbox's namespaces (like `unshare` or `setns`) cannot be
+    /// allowed.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox, SeccompAction, Syscall};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_syscall_rule(Syscall::PTRACE, SeccompAction::Kill).unwrap();
+    ///
+    /// let wait4 = Syscall::from_raw(libc::SYS_wait4);
+    /// assert!(sandbox.add_syscall_rule(wait4, SeccompAction::Kill).is_err());
+    /// ```
+    pub fn add_syscall_rule(
+        &mut self,
+        syscall: Syscall,
+        action: SeccompAction,
+    ) -> Result<&mut Self> {
+        SyscallFilter::check_rule(syscall, action)?;
+        Ok(self.set_syscall_action(syscall, action))
+    }
+
+    /// Report accesses denied by the sandbox instead of enforcing them.
+    ///
+    /// In audit mode, the sandboxee has full access to the host's filesystem,
+    /// network and environment variables. Files which are opened or executed
+    /// without a matching exception are written to `log`, with one line like
+    /// `write "/etc/hosts"` per violation.
+    ///
+    /// Syscalls denied by the seccomp filter are allowed and reported to the
+    /// kernel's audit log instead.
+    ///
+    /// Since sandboxees can bypass the audit, this must only be used to
+    /// develop policies for trusted programs. Audit mode is not supported by
+    /// [`Sandbox::lock`].
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let log = File::create("/tmp/birdcage-audit.log").unwrap();
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.set_audit(log);
+    /// ```
+    pub fn set_audit(&mut self, log: File) -> &mut Self {
+        self.syscall_filter.set_audit(true);
+        self.audit_log = Some(AuditLog::File(log));
+        self
+    }
+
+    /// Report accesses denied by the sandbox to a channel.
+    ///
+    /// This enables audit mode like [`LinuxSandbox::set_audit`], but sends a
+    /// [`SandboxViolation`] to `sender` for every violation instead of writing
+    /// it to a log file. Violations are forwarded by a background thread, which
+    /// exits once all processes of the sandbox have exited.
+    ///
+    /// ```no_run
+    /// use std::sync::mpsc;
+    ///
+    /// use birdcage::process::Command;
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.with_audit_log(sender);
+    ///
+    /// sandbox.spawn(Command::new("/usr/bin/true")).unwrap().wait().unwrap();
+    /// for violation in receiver.iter() {
+    ///     println!("{} {:?}", violation.access, violation.path);
+    /// }
+    /// ```
+    pub fn with_audit_log(&mut self, sender: Sender<SandboxViolation>) -> &mut Self {
+        self.syscall_filter.set_audit(true);
+        self.audit_log = Some(AuditLog::Channel(sender));
+        self
+    }
+
+    /// Get the sandbox's enforcement mode.
+    ///
+    /// The sandbox is in [`SandboxMode::Audit`] after calling either
+    /// [`LinuxSandbox::set_audit`] or [`LinuxSandbox::with_audit_log`].
+    pub fn mode(&self) -> SandboxMode {
+        match self.audit_log {
+            Some(_) => SandboxMode::Audit,
+            None => SandboxMode::Enforce,
         }
+    }
+
+    /// Create a temporary directory the sandboxee can write to.
+    ///
+    /// The returned directory has an [`Exception::WriteAndRead`] exception and
+    /// is removed with all its content once the sandboxee was waited on, or
+    /// its [`Child`] is dropped. With [`Sandbox::lock`], the directory is never
+    /// removed.
+    ///
+    /// ```rust
+    /// use birdcage::process::Command;
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// let tempdir = sandbox.add_temp_dir().unwrap();
+    ///
+    /// let mut cmd = Command::new("touch");
+    /// cmd.arg(tempdir.join("file"));
+    /// ```
+    pub fn add_temp_dir(&mut self) -> Result<PathBuf> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().to_path_buf();
+        self.temp_dirs.push(temp_dir);
+
+        self.add_exception(Exception::WriteAndRead(path.clone()))?;
+
+        Ok(path)
+    }
+
+    /// Limit the sandbox's memory usage using cgroup v2.
+    ///
+    /// Unlike [`ResourceLimits::max_memory_bytes`], this limits the resident
+    /// memory of all processes in the sandbox combined. Once exceeded, the
+    /// kernel's OOM killer terminates the sandboxee with `SIGKILL`.
+    ///
+    /// Due to cgroup v2 delegation rules, the calling process must be in the
+    /// host's writable root cgroup. Otherwise the limit is ignored and
+    /// [`SandboxWarning::CgroupUnavailable`] is reported. Cgroup limits are
+    /// not supported by [`Sandbox::lock`].
+    pub fn set_cgroup_memory_limit(&mut self, bytes: u64) -> &mut Self {
+        self.cgroup_limits.memory_max = Some(bytes);
+        self
+    }
+
+    /// Limit the sandbox's CPU bandwidth using cgroup v2.
+    ///
+    /// All processes in the sandbox combined can use at most `quota_us`
+    /// microseconds of CPU time in each `period_us` microseconds.
+    ///
+    /// Due to cgroup v2 delegation rules, the calling process must be in the
+    /// host's writable root cgroup. Otherwise the limit is ignored and
+    /// [`SandboxWarning::CgroupUnavailable`] is reported. Cgroup limits are
+    /// not supported by [`Sandbox::lock`].
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// // Allow using half of one CPU core.
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.set_cgroup_cpu_quota(100_000, 50_000);
+    /// ```
+    pub fn set_cgroup_cpu_quota(&mut self, period_us: u64, quota_us: u64) -> &mut Self {
+        self.cgroup_limits.cpu_max = Some((period_us, quota_us));
+        self
+    }
+
+    /// Run the sandboxee as an unprivileged user of the host.
+    ///
+    /// By default, the sandboxee has the same host UID and GID as the calling
+    /// process, even if it appears as a different user inside the sandbox.
+    /// When the calling process is root, this maps the sandbox to `uid` and
+    /// `gid` on the host instead and removes all supplementary groups, so
+    /// file permissions are checked against the unprivileged user.
+    ///
+    /// Inside the sandbox, the sandboxee also uses `uid` and `gid`, unless
+    /// they are changed with [`LinuxSandbox::with_user_namespace`].
+    ///
+    /// Privileges are dropped before any namespaces, Landlock rules or
+    /// seccomp filters are set up. Independent of this setting,
+    /// `PR_SET_NO_NEW_PRIVS` is always set before the first seccomp filter is
+    /// installed and all ambient capabilities are cleared, so the sandboxee
+    /// can never gain privileges through setuid binaries or file
+    /// capabilities.
+    ///
+    /// This requires the calling process to be root.
+    ///
+    /// ```no_run
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.drop_privileges(65534, 65534);
+    /// ```
+    pub fn drop_privileges(&mut self, uid: u32, gid: u32) -> &mut Self {
+        self.drop_privileges = Some((uid, gid));
+        self
+    }
+
+    /// Select the network namespace of the sandbox.
+    ///
+    /// By default, the sandbox shares the host's network namespace whenever
+    /// networking is allowed by an exception like [`Exception::Networking`].
+    ///
+    /// With [`NetworkNamespace::Isolated`] or
+    /// [`NetworkNamespace::LoopbackOnly`], the sandbox always gets its own
+    /// network namespace, so network exceptions only grant access to the
+    /// network interfaces inside it. This is incompatible with granular
+    /// network exceptions like [`Exception::NetworkConnect`].
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, NetworkNamespace, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::Networking).unwrap();
+    /// sandbox.with_network_namespace(NetworkNamespace::LoopbackOnly);
+    /// ```
+    pub fn with_network_namespace(&mut self, ns_type: NetworkNamespace) -> &mut Self {
+        self.network_namespace = ns_type;
+        self
+    }
+
+    /// Isolate the sandbox's hostname using a new UTS namespace.
+    ///
+    /// If `hostname` is `None`, the sandbox starts out with the host's
+    /// hostname. Either way, changes to the hostname inside the sandbox no
+    /// longer affect the host.
+    ///
+    /// Other processes are always isolated from the sandboxee, since every
+    /// sandbox spawns its own PID namespace.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.with_uts_namespace(Some("sandbox".into()));
+    /// ```
+    pub fn with_uts_namespace(&mut self, hostname: Option<String>) -> &mut Self {
+        self.uts_namespace = true;
+        self.hostname = hostname;
+        self
+    }
+
+    /// Restrict access to `/proc` inside the sandbox.
+    ///
+    /// By default, the sandboxee can access the processes of its own PID
+    /// namespace and all system-wide files in `/proc`. Many language runtimes
+    /// rely on `/proc`, for example to read `/proc/self/maps` or
+    /// `/proc/self/exe` for stack traces, or `/proc/cpuinfo` to determine
+    /// the number of available threads. So [`ProcAccess::Hidden`] in
+    /// particular can break otherwise working programs.
+    ///
+    /// [`ProcAccess::SelfOnly`] mounts a new procfs with the `hidepid` and
+    /// `subset=pid` options, which only exposes processes the sandboxee could
+    /// trace and hides all system-wide files. The sandbox's init process is
+    /// not dumpable, so it is hidden even from a sandboxee running as root
+    /// inside the sandbox. Since
+    /// this requires a new PID namespace, it is not supported by
+    /// [`Sandbox::lock`].
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, ProcAccess, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.proc_access(ProcAccess::SelfOnly);
+    /// ```
+    pub fn proc_access(&mut self, access: ProcAccess) -> &mut Self {
+        self.proc_access = access;
+        self
+    }
+
+    /// Mount `source` at a different path inside the sandbox.
+    ///
+    /// The sandbox always runs in a private mount namespace, so the mount is
+    /// only visible to the sandboxee. Unlike path exceptions, `source` itself
+    /// remains inaccessible unless it is allowed by another exception, which
+    /// allows constructing a minimal filesystem layout for the sandboxee.
+    ///
+    /// The `target` path must be absolute and is created inside the sandbox
+    /// if necessary. Files beneath the target can be executed, while writes
+    /// are only allowed if `read_only` is `false`. Targets which do not exist
+    /// beneath another exception's path are skipped, since they cannot be
+    /// created without modifying the host's filesystem.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.bind_mount("/usr/bin".into(), "/opt/tools".into(), true).unwrap();
+    /// ```
+    pub fn bind_mount(
+        &mut self,
+        source: PathBuf,
+        target: PathBuf,
+        read_only: bool,
+    ) -> Result<&mut Self> {
+        if !target.is_absolute() || target.components().any(|c| c == Component::ParentDir) {
+            return Err(Error::InvalidPath(target));
+        }
+
+        let source = source.canonicalize().map_err(|err| Error::from_path(source, &err))?;
+        let flags = if read_only { MountAttrFlags::RDONLY } else { MountAttrFlags::empty() };
+        self.path_exceptions.remapped.push((source, target, flags));
+
+        Ok(self)
+    }
+
+    /// Get the procfs access, accounting for [`Exception::ProcSelf`].
+    fn effective_proc_access(&self) -> ProcAccess {
+        match self.proc_access {
+            ProcAccess::Hidden if self.proc_self => ProcAccess::SelfOnly,
+            access => access,
+        }
+    }
+
+    /// Create the cgroup for a new sandbox.
+    ///
+    /// Returns `None` if no cgroup limits are set, or cgroup v2 is unavailable.
+    fn create_cgroup(&self) -> Result<Option<Cgroup>> {
+        if self.cgroup_limits.is_empty() {
+            return Ok(None);
+        }
+
+        if !self.cgroup_limits.is_supported() {
+            log::warn!("cgroup v2 is unavailable, skipping birdcage cgroup limits");
+            return Ok(None);
+        }
+
+        // Enabling controllers can still be rejected by the cgroup hierarchy.
+        match Cgroup::new(&self.cgroup_limits) {
+            Ok(cgroup) => Ok(Some(cgroup)),
+            Err(err) => {
+                log::warn!("failed to create cgroup, skipping birdcage cgroup limits: {err}");
+                Ok(None)
+            },
+        }
+    }
+
+    /// Get the UID and GID of the sandboxed process.
+    fn sandboxee_ids(&self) -> (u32, u32) {
+        let (euid, egid) = match self.drop_privileges {
+            Some(ids) => ids,
+            None => (rustix::process::geteuid().as_raw(), rustix::process::getegid().as_raw()),
+        };
+        let uid = match self.uid_map.first() {
+            Some(map) => map.inside_id,
+            None => euid,
+        };
+        let gid = match self.gid_map.first() {
+            Some(map) => map.inside_id,
+            None => egid,
+        };
+        (uid, gid)
+    }
+
+    /// Ensure all exceptions can be enforced by the kernel.
+    fn ensure_supported(&self) -> Result<()> {
+        // Ensure append-only exceptions can be enforced.
+        if !self.path_exceptions.append_only.is_empty()
+            && self.landlock_abi < landlock::TRUNCATE_ABI
+        {
+            return Err(Error::ActivationFailed(
+                "append-only exceptions require Landlock ABI 3 (Linux 6.2)".into(),
+            ));
+        }
+
+        // Ensure Landlock is available in strict mode.
+        if self.strict_landlock && self.landlock_abi == 0 {
+            return Err(Error::ActivationFailed("Landlock is unavailable".into()));
+        }
+
+        // Ensure list-only exceptions can be enforced.
+        if !self.path_exceptions.list_only.is_empty() && self.landlock_abi == 0 {
+            return Err(Error::ActivationFailed(
+                "directory listing exceptions require Landlock (Linux 5.13)".into(),
+            ));
+        }
+
+        // Ensure custom errno values can be returned by seccomp.
+        self.syscall_filter.validate()?;
+
+        // Ensure seccomp is available, unless only namespaces and Landlock are
+        // required.
+        if !seccomp::is_available() {
+            let requires_seccomp = (!self.allow_networking && !self.network_rules.is_empty())
+                || self.protocol_filter.is_some()
+                || self.audit_log.is_some()
+                || self.deny_subprocesses
+                || self.syscall_filter.has_custom_actions();
+            if !self.seccomp_best_effort || requires_seccomp {
+                return Err(Error::SeccompUnavailable);
+            }
+        }
+
+        // Ensure the sandbox's user namespace can be created.
+        if let Some(restriction) = namespaces::user_namespaces_restricted() {
+            return Err(Error::ActivationFailed(restriction.into()));
+        }
+
+        // Granular network exceptions are enforced from the host's network namespace.
+        let granular_network = !self.allow_networking && !self.network_rules.is_empty();
+        if granular_network && self.network_namespace != NetworkNamespace::Host {
+            return Err(Error::ActivationFailed(
+                "granular network exceptions require the host network namespace".into(),
+            ));
+        }
+
+        // Unprivileged user namespaces can only map the caller's own IDs, while
+        // root can only map the IDs it drops privileges to.
+        let euid = rustix::process::geteuid().as_raw();
+        let egid = rustix::process::getegid().as_raw();
+        let (euid, egid) = match self.drop_privileges {
+            Some(_) if euid != 0 => {
+                return Err(Error::ActivationFailed("dropping privileges requires root".into()));
+            },
+            Some(ids) => ids,
+            None => (euid, egid),
+        };
+        for (maps, outside_id) in [(&self.uid_map, euid), (&self.gid_map, egid)] {
+            match maps.as_slice() {
+                [] => (),
+                [map] if map.count == 1 && map.outside_id == outside_id => (),
+                _ => {
+                    return Err(Error::ActivationFailed(
+                        "user namespace can only map the effective UID and GID of the current \
+                         process"
+                            .into(),
+                    ))
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensure the sandboxee's user and group IDs are mapped.
+    fn check_ids(&self, sandboxee: &Command) -> Result<()> {
+        let (uid, gid) = self.sandboxee_ids();
+        let ids = [
+            ("UID", sandboxee.uid, &self.uid_map, uid),
+            ("GID", sandboxee.gid, &self.gid_map, gid),
+        ];
+        for (kind, id, maps, default_id) in ids {
+            let Some(id) = id else { continue };
+
+            let mapped = match maps.as_slice() {
+                [] => id == default_id,
+                maps => maps.iter().any(|map| {
+                    (map.inside_id..map.inside_id.saturating_add(map.count)).contains(&id)
+                }),
+            };
+            if !mapped {
+                return Err(Error::ActivationFailed(format!(
+                    "{kind} {id} is not mapped in the sandbox's user namespace"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Warn if the sandboxee's working directory is not accessible.
+    fn check_current_dir(&self, sandboxee: &Command) -> Result<()> {
+        let Some(current_dir) = sandboxee.get_current_dir() else { return Ok(()) };
+
+        // In audit mode, the entire filesystem is accessible.
+        if self.audit_log.is_none() && !self.path_exceptions.is_accessible(current_dir) {
+            return Err(Error::CurrentDirNotAccessible(current_dir.into()));
+        }
+
+        Ok(())
+    }
+
+    /// Spawn the sandbox's PID 1.
+    ///
+    /// If `isolated` is `true`, environment variables are restricted by PID 1,
+    /// rather than the current process.
+    fn spawn_init(mut self, sandboxee: Command, isolated: bool) -> Result<Child> {
+        // Keep temporary directories alive until the sandboxee exits.
+        let temp_dirs = mem::take(&mut self.temp_dirs);
+        let kill_on_drop = sandboxee.kill_on_drop;
+        let drop_privileges = self.drop_privileges;
+
+        self.path_exceptions.create_shared_memory()?;
+
+        // Ensure all file descriptors passed to the sandboxee exist.
+        for parent_fd in sandboxee.fd_mappings.values() {
+            if unsafe { libc::fcntl(*parent_fd, libc::F_GETFD) } == -1 {
+                return Err(IoError::last_os_error().into());
+            }
+        }
+
+        // Skip syscall filtering in best-effort mode.
+        if !seccomp::is_available() {
+            self.syscall_filter.disable();
+        }
+
+        // Create cgroup before PID 1 exists, to ensure it can be entered.
+        let cgroup = self.create_cgroup()?;
 
         // Create pipes to hook up init's stdio.
         let stdin_pipe = sandboxee.stdin.make_pipe(true)?;
@@ -69,19 +1363,66 @@ impl Sandbox for LinuxSandbox {
         let exit_signal_pipe = pipe().map_err(IoError::from)?;
 
         // Spawn isolated sandbox PID 1.
-        let allow_networking = self.allow_networking;
-        let init_arg = ProcessInitArg::new(
+        //
+        // With granular network exceptions, the network namespace is created later on,
+        // to allow the network supervisor to stay in the host's namespace.
+        let allow_networking = self.network_namespace == NetworkNamespace::Host
+            && (self.allow_networking
+                || !self.network_rules.is_empty()
+                || self.audit_log.is_some());
+        let mut init_arg = ProcessInitArg::new(
             self,
             sandboxee,
+            isolated,
             exit_signal_pipe,
             stdin_pipe,
             stdout_pipe,
             stderr_pipe,
-        );
-        let init_arg = spawn_sandbox_init(init_arg, allow_networking)?;
+        )?;
+
+        // Block PID 1 until it was moved into the cgroup and its IDs are mapped,
+        // so no process escapes the cgroup or runs with the calling user's privileges.
+        let setup_sync_tx = if cgroup.is_some() || drop_privileges.is_some() {
+            let (setup_sync_rx, setup_sync_tx) = pipe().map_err(IoError::from)?;
+            init_arg.setup_sync_rx = Some(setup_sync_rx);
+            Some(setup_sync_tx)
+        } else {
+            None
+        };
+
+        let mut init_arg = spawn_sandbox_init(init_arg, allow_networking)?;
+
+        // Threads can only be spawned once PID 1 exists.
+        if let Some(audit_forwarder) = init_arg.audit_forwarder.take() {
+            audit_forwarder.spawn()?;
+        }
+
+        if let Some(setup_sync_tx) = setup_sync_tx {
+            let pid = init_arg.pid;
+            let result = cgroup
+                .as_ref()
+                .map_or(Ok(()), |cgroup| cgroup.add_process(pid))
+                .and_then(|_| match drop_privileges {
+                    Some((uid, gid)) => namespaces::map_process_ids(pid, uid, gid),
+                    None => Ok(()),
+                })
+                .and_then(|_| {
+                    rustix::io::write(setup_sync_tx, &[0]).map_err(IoError::from)?;
+                    Ok(())
+                });
+
+            // Kill PID 1 if it cannot be restricted.
+            if let Err(err) = result {
+                unsafe {
+                    libc::kill(init_arg.pid, libc::SIGKILL);
+                    libc::waitpid(init_arg.pid, ptr::null_mut(), 0);
+                }
+                return Err(err.into());
+            }
+        }
 
         // Deconstruct init args, dropping unused FDs.
-        let (pid, stdin_tx, stdout_rx, stderr_rx, exit_signal_rx) = {
+        let (pid, stdin_tx, stdout_rx, stderr_rx, exit_signal_rx, sandboxee_pidfd_rx) = {
             let ProcessInitArg {
                 // Extract used fields.
                 pid,
@@ -89,10 +1430,28 @@ impl Sandbox for LinuxSandbox {
                 stdout_rx,
                 stderr_rx,
                 exit_signal_rx,
+                sandboxee_pidfd_rx,
 
                 // Deconstruct all remaining fields to manually drop them.
                 path_exceptions: _x0,
+                supervision: _x8,
+                env_filter: _x9,
+                resource_limits: _x10,
+                dropped_capabilities: _x23,
+                protocol_filter: _x11,
+                landlock_abi: _x12,
+                sandboxee_ids: _x13,
+                syscall_filter: _x14,
                 exit_signal_tx: _x1,
+                sandboxee_pidfd_tx: _x24,
+                setup_sync_rx: _x15,
+                drop_privileges: _x17,
+                loopback: _x18,
+                uts_namespace: _x21,
+                hostname: _x22,
+                proc_mount: _x19,
+                audit_forwarder: _x20,
+                process_filter: _x16,
                 parent_euid: _x2,
                 parent_egid: _x3,
                 stdout_tx: _x4,
@@ -100,15 +1459,49 @@ impl Sandbox for LinuxSandbox {
                 sandboxee: _x6,
                 stdin_rx: _x7,
             } = init_arg;
-            (pid, stdin_tx, stdout_rx, stderr_rx, exit_signal_rx)
+            (pid, stdin_tx, stdout_rx, stderr_rx, exit_signal_rx, sandboxee_pidfd_rx)
         };
 
-        let child = Child::new(pid, exit_signal_rx, stdin_tx, stdout_rx, stderr_rx)?;
+        // Wait for PID 1 to pass back the sandboxee's PID file descriptor.
+        //
+        // The socket is closed without a message if the sandboxee could not be spawned.
+        let sandboxee_pidfd = network::recv_fd(&sandboxee_pidfd_rx).ok();
+
+        let mut child = Child::new(pid, exit_signal_rx, stdin_tx, stdout_rx, stderr_rx)?;
+        if let Some(sandboxee_pidfd) = sandboxee_pidfd {
+            child.set_sandboxee(sandboxee_pidfd)?;
+        }
+        child.temp_dirs = temp_dirs;
+        child.cgroup = cgroup;
+        child.kill_on_drop = kill_on_drop;
 
         Ok(child)
     }
 }
 
+/// Environment variable restrictions.
+#[derive(Default, Clone)]
+struct EnvFilter {
+    exceptions: Vec<String>,
+    prefixes: Vec<String>,
+    patterns: Vec<String>,
+    custom_env: Option<HashMap<String, String>>,
+    set_env: HashMap<String, String>,
+    full_env: bool,
+}
+
+impl EnvFilter {
+    /// Remove/replace environment variables of the current process.
+    fn apply(&self) {
+        if let Some(ref custom_env) = self.custom_env {
+            crate::restrict_env_variables_with_custom(&[], &[], &[], Some(custom_env));
+        } else if !self.full_env {
+            crate::restrict_env_variables(&self.exceptions, &self.prefixes, &self.patterns);
+        }
+        crate::set_env_variables(&self.set_env);
+    }
+}
+
 /// Create sandbox child process.
 ///
 /// This function uses `clone` to setup the sandbox's init process with user
@@ -140,13 +1533,33 @@ fn spawn_sandbox_init(init_arg: ProcessInitArg, allow_networking: bool) -> Resul
         if !allow_networking {
             flags |= libc::CLONE_NEWNET;
         }
+        if init_arg.uts_namespace {
+            flags |= libc::CLONE_NEWUTS;
+        }
+
+        // Block forwarded signals until PID 1 installed its handlers, since PID 1
+        // discards unhandled signals.
+        let forwarded_signals = forwarded_sigset();
+        let mut signal_mask = mem::zeroed();
+        libc::pthread_sigmask(libc::SIG_BLOCK, &forwarded_signals, &mut signal_mask);
 
         // Spawn sandbox init process.
         let init_arg_raw = Box::into_raw(Box::new(init_arg));
         let init_pid =
             libc::clone(sandbox_init, stack_top, flags | libc::SIGCHLD, init_arg_raw as _);
+        libc::pthread_sigmask(libc::SIG_SETMASK, &signal_mask, ptr::null_mut());
         if init_pid == -1 {
-            Err(IoError::last_os_error().into())
+            let err = IoError::last_os_error();
+            drop(Box::from_raw(init_arg_raw));
+            libc::munmap(child_stack, stack_size);
+
+            // Namespace creation can be denied by security modules like AppArmor.
+            match err.raw_os_error() {
+                Some(libc::EPERM | libc::ENOSPC | libc::EUSERS) => Err(Error::ActivationFailed(
+                    format!("failed to create sandbox namespaces: {err}"),
+                )),
+                _ => Err(err.into()),
+            }
         } else {
             let mut init_arg = Box::from_raw(init_arg_raw);
             init_arg.pid = init_pid;
@@ -175,11 +1588,27 @@ extern "C" fn sandbox_init(arg: *mut libc::c_void) -> libc::c_int {
 ///
 /// Wrapper to simplify error handling.
 fn sandbox_init_inner(mut init_arg: ProcessInitArg) -> io::Result<libc::c_int> {
+    // Forward signals, since PID 1 ignores all signals without handlers.
+    forward_signals()?;
+
     // Close all unused FDs.
     init_arg.stdin_tx.take();
     init_arg.stdout_rx.take();
     init_arg.stderr_rx.take();
+    init_arg.audit_forwarder.take();
     drop(init_arg.exit_signal_rx);
+    drop(init_arg.sandboxee_pidfd_rx);
+
+    // Wait for the parent to move us into the sandbox's cgroup and map our IDs.
+    if let Some(setup_sync) = init_arg.setup_sync_rx.take() {
+        let mut buf = [0];
+        if rustix::io::read(setup_sync, &mut buf)? == 0 {
+            return Err(IoError::new(IoErrorKind::Other, "sandbox setup failed"));
+        }
+    }
+
+    // Duplicate FDs passed to the sandboxee, before they can be replaced by stdio.
+    let fd_mappings = FdMappings::new(&init_arg.sandboxee)?;
 
     // Hook up stdio to parent process.
     if let Some(stdin_pipe) = &mut init_arg.stdin_rx {
@@ -192,78 +1621,364 @@ fn sandbox_init_inner(mut init_arg: ProcessInitArg) -> io::Result<libc::c_int> {
         rustix::stdio::dup2_stderr(stderr_pipe)?;
     }
 
-    // Map root UID and GID.
-    namespaces::map_ids(init_arg.parent_euid.as_raw(), init_arg.parent_egid.as_raw(), 0, 0)?;
+    // Remove/replace environment variables.
+    if let Some(env_filter) = &init_arg.env_filter {
+        env_filter.apply();
+    }
 
-    // Isolate filesystem using a mount namespace.
-    namespaces::setup_mount_namespace(init_arg.path_exceptions)?;
+    // Map root UID and GID, unless the parent mapped root to an unprivileged user.
+    if init_arg.drop_privileges {
+        // Switch from the unmapped host root to the mapped namespace root.
+        set_ids(0, 0)?;
+    } else {
+        namespaces::map_ids(init_arg.parent_euid.as_raw(), init_arg.parent_egid.as_raw(), 0, 0)?;
+    }
 
-    // Create new procfs directory.
-    let new_proc_c = CString::new("/proc")?;
-    namespaces::mount_proc(&new_proc_c)?;
+    // Bring up the loopback interface of the new network namespace.
+    if init_arg.loopback {
+        namespaces::enable_loopback()?;
+    }
 
-    // Drop root user mapping.
-    namespaces::create_user_namespace(
-        init_arg.parent_euid.as_raw(),
-        init_arg.parent_egid.as_raw(),
-        Namespaces::empty(),
-    )?;
+    // Set the hostname of the new UTS namespace.
+    if let Some(hostname) = &init_arg.hostname {
+        namespaces::set_hostname(hostname)?;
+    }
 
-    // Setup system call filters.
-    SyscallFilter::apply().map_err(|err| IoError::new(IoErrorKind::Other, err))?;
+    // Restrict filesystem and syscall access.
+    lockdown(
+        init_arg.path_exceptions,
+        init_arg.supervision,
+        init_arg.protocol_filter,
+        init_arg.landlock_abi,
+        init_arg.syscall_filter,
+        init_arg.sandboxee_ids,
+        init_arg.proc_mount,
+    )?;
 
-    // Block suid/sgid.
+    // Prevent the sandboxee from inspecting PID 1 through procfs or ptrace.
     //
-    // This is also blocked by our bind mount's MS_NOSUID flag, so we're just
-    // doubling-down here.
-    rustix::thread::set_no_new_privs(true)?;
+    // This must happen after all ID mappings were written, since it also
+    // changes the owner of our own procfs files. The sandboxee becomes
+    // dumpable again once it is executed.
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+        return Err(IoError::last_os_error());
+    }
 
     // Spawn sandboxed process.
+    let timeout = init_arg.sandboxee.timeout;
+    let core_dump_limit = init_arg.sandboxee.core_dump_limit;
+    let pre_exec_hooks = mem::take(&mut init_arg.sandboxee.pre_exec_hooks);
     let mut std_command = std::process::Command::from(init_arg.sandboxee);
     std_command.stdin(std::process::Stdio::inherit());
     std_command.stdout(std::process::Stdio::inherit());
     std_command.stderr(std::process::Stdio::inherit());
+
+    // Restrict sandboxee's resource usage, subprocesses and capabilities.
+    let resource_limits = init_arg.resource_limits;
+    let process_filter = init_arg.process_filter;
+    let dropped_capabilities = init_arg.dropped_capabilities;
+    unsafe {
+        std_command.pre_exec(move || {
+            fd_mappings.apply()?;
+            resource_limits.apply()?;
+            if let Some(core_dump_limit) = core_dump_limit {
+                set_core_dump_limit(core_dump_limit)?;
+            }
+            if let Some(process_filter) = &process_filter {
+                seccompiler::apply_filter(process_filter)
+                    .map_err(|err| IoError::new(IoErrorKind::Other, err))?;
+            }
+            dropped_capabilities.drop()?;
+            Ok(())
+        });
+
+        // Run user hooks once all restrictions are in place.
+        for hook in pre_exec_hooks {
+            std_command.pre_exec(hook);
+        }
+    };
+    event!(target: "birdcage::linux", "executing {:?}", std_command.get_program());
     let child = std_command.spawn()?;
 
+    // Forward signals received before the sandboxee was spawned.
+    SANDBOXEE_PID.store(child.id() as i32, Ordering::SeqCst);
+    let pending_signals = PENDING_SIGNALS.swap(0, Ordering::SeqCst);
+    for signal in FORWARDED_SIGNALS {
+        if pending_signals & (1 << signal) != 0 {
+            unsafe { libc::kill(child.id() as i32, signal) };
+        }
+    }
+
+    // Allow the parent to signal the sandboxee directly.
+    let sandboxee_pid = Pid::from_raw(child.id() as i32).unwrap();
+    let pidfd = rustix::process::pidfd_open(sandboxee_pid, PidfdFlags::empty())?;
+    network::send_fd(&init_arg.sandboxee_pidfd_tx, pidfd.as_raw_fd())?;
+    drop(init_arg.sandboxee_pidfd_tx);
+    drop(pidfd);
+
+    // Start the sandboxee's deadline.
+    let timer_pid = timeout.map(spawn_timer).transpose()?;
+    let mut timed_out = false;
+
     // Reap zombie children.
     let child_pid = Pid::from_raw(child.id() as i32);
     loop {
         // Wait for any child to exit.
         match rustix::process::wait(WaitOptions::empty())? {
             Some((pid, status)) if Some(pid) == child_pid => match status.terminating_signal() {
-                Some(signal) => {
+                Some(mut signal) => {
+                    if timed_out && signal == libc::SIGKILL as u32 {
+                        signal |= EXIT_SIGNAL_TIMEOUT;
+                    }
+
                     // Send exit signal to parent.
                     rustix::io::write(init_arg.exit_signal_tx, &signal.to_le_bytes())?;
                     return Ok(1);
                 },
                 None => return Ok(status.exit_status().unwrap_or(1) as i32),
             },
+            // Kill the sandboxee once its deadline has expired.
+            Some((pid, _)) if Some(pid) == timer_pid => {
+                timed_out = true;
+                unsafe { libc::kill(child.id() as i32, libc::SIGKILL) };
+            },
             Some(_) => (),
             None => unreachable!("none without nohang"),
         }
     }
 }
 
+/// Spawn a process which exits once `timeout` has elapsed.
+fn spawn_timer(timeout: Duration) -> io::Result<Pid> {
+    match unsafe { libc::fork() } {
+        -1 => Err(IoError::last_os_error()),
+        0 => {
+            std::thread::sleep(timeout);
+            unsafe { libc::_exit(0) };
+        },
+        pid => Ok(Pid::from_raw(pid).unwrap()),
+    }
+}
+
+/// Install handlers forwarding signals to the sandboxee.
+///
+/// Signals received before the sandboxee is spawned are recorded in
+/// [`PENDING_SIGNALS`]. Handlers are reset by `execve`, so they do not affect
+/// the sandboxee itself.
+fn forward_signals() -> io::Result<()> {
+    extern "C" fn handler(signal: libc::c_int) {
+        let pid = SANDBOXEE_PID.load(Ordering::SeqCst);
+        if pid > 0 {
+            unsafe { libc::kill(pid, signal) };
+        } else {
+            PENDING_SIGNALS.fetch_or(1 << signal, Ordering::SeqCst);
+        }
+    }
+
+    unsafe {
+        for signal in FORWARDED_SIGNALS {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(signal, &action, ptr::null_mut()) == -1 {
+                return Err(IoError::last_os_error());
+            }
+        }
+
+        // Deliver signals blocked since the sandbox was spawned.
+        let forwarded_signals = forwarded_sigset();
+        libc::pthread_sigmask(libc::SIG_UNBLOCK, &forwarded_signals, ptr::null_mut());
+    }
+
+    Ok(())
+}
+
+/// Get the set of signals forwarded to the sandboxee.
+fn forwarded_sigset() -> libc::sigset_t {
+    unsafe {
+        let mut set = mem::zeroed();
+        libc::sigemptyset(&mut set);
+        for signal in FORWARDED_SIGNALS {
+            libc::sigaddset(&mut set, signal);
+        }
+        set
+    }
+}
+
+/// Apply all sandbox restrictions to the current process.
+///
+/// This must be called as root in a new user and mount namespace. The root
+/// mapping is replaced by the sandboxee's UID and GID before applying seccomp
+/// filters.
+///
+/// The `proc_mount` determines if the existing procfs is kept, replaced with a
+/// new procfs for the current PID namespace, or hidden entirely.
+///
+/// If `supervision` is present, the current process must still be in the
+/// host's network namespace. For network rules, a new network namespace will
+/// be created, with `connect` and `bind` calls being forwarded to a supervisor
+/// process. In audit mode, file accesses are reported to an auditor process.
+///
+/// If `protocol_filter` is present, only the selected internet protocols can
+/// be used.
+///
+/// Custom syscall actions from `syscall_filter` are applied on top of the
+/// default seccomp filter.
+fn lockdown(
+    path_exceptions: PathExceptions,
+    supervision: Option<Supervision>,
+    protocol_filter: Option<ProtocolFilter>,
+    landlock_abi: u32,
+    syscall_filter: SyscallFilter,
+    (uid, gid): (u32, u32),
+    proc_mount: ProcMount,
+) -> io::Result<()> {
+    // Get paths which are allowed to be truncated, read or written.
+    let truncate_paths = path_exceptions.truncate_paths();
+    let read_paths = path_exceptions.read_paths();
+    let write_paths = path_exceptions.write_paths();
+
+    // Isolate filesystem using a mount namespace.
+    let (hosts, network) = match &supervision {
+        Some(Supervision::Network(rules)) => (rules.hosts_file(), true),
+        _ => (None, false),
+    };
+    namespaces::setup_mount_namespace(path_exceptions, hosts)?;
+
+    // Create new procfs directory.
+    if let ProcMount::New | ProcMount::SelfOnly = proc_mount {
+        let new_proc_c = CString::new("/proc")?;
+        namespaces::mount_proc(&new_proc_c, proc_mount == ProcMount::SelfOnly)?;
+    }
+
+    // Spawn supervisor while still in the host's network namespace.
+    let supervisor = match supervision {
+        Some(Supervision::Network(rules)) => Some(Supervisor::spawn(rules)?),
+        Some(Supervision::Audit(rules)) => Some(audit::spawn(rules)?),
+        None => None,
+    };
+
+    // Drop root user mapping.
+    //
+    // Since procfs is required to map IDs, it can only be hidden afterwards in a
+    // separate mount namespace.
+    let mut namespaces = if network { Namespaces::NETWORK } else { Namespaces::empty() };
+    if proc_mount == ProcMount::Hidden {
+        namespaces |= Namespaces::MOUNT;
+    }
+    namespaces::create_user_namespace(uid, gid, namespaces)?;
+
+    if proc_mount == ProcMount::Hidden {
+        namespaces::hide_proc()?;
+    }
+
+    // Prevent gaining privileges through setuid binaries or file capabilities.
+    //
+    // This must happen before Landlock and seccomp are applied, since both
+    // require it without `CAP_SYS_ADMIN`. Setuid binaries are also blocked by
+    // our bind mount's MS_NOSUID flag, so we're just doubling-down here.
+    rustix::thread::set_no_new_privs(true)?;
+    clear_ambient_capabilities()?;
+
+    // Prevent truncation of append-only files.
+    if let Some(truncate_paths) = truncate_paths {
+        let truncate_paths = truncate_paths.iter().map(|path| path.as_path());
+        landlock::restrict_truncation(landlock_abi, truncate_paths)?;
+    }
+
+    // Prevent reading files beneath list-only directories.
+    if let Some(mut read_paths) = read_paths {
+        // Files mounted by the sandbox itself are always readable.
+        let internal = ["/proc", "/etc/hosts"].map(PathBuf::from);
+        read_paths.extend(internal.into_iter().filter(|path| path.exists()));
+
+        let read_paths = read_paths.iter().map(|path| path.as_path());
+        landlock::restrict_file_reads(landlock_abi, read_paths)?;
+    }
+
+    // Prevent writing to read-only devices.
+    if let Some(mut write_paths) = write_paths {
+        write_paths.push(PathBuf::from("/proc"));
+
+        let write_paths = write_paths.iter().map(|path| path.as_path());
+        landlock::restrict_file_writes(landlock_abi, write_paths)?;
+    }
+
+    // Forward network syscalls or file accesses to the supervisor.
+    if let Some(supervisor) = supervisor {
+        let listener = if network { NetworkFilter::apply()? } else { AuditFilter::apply()? };
+        supervisor.supervise(listener)?;
+    }
+
+    // Restrict internet protocols.
+    if let Some(protocol_filter) = protocol_filter {
+        protocol_filter.apply().map_err(|err| IoError::new(IoErrorKind::Other, err))?;
+    }
+
+    // Setup system call filters.
+    syscall_filter.apply().map_err(|err| IoError::new(IoErrorKind::Other, err))?;
+
+    Ok(())
+}
+
+/// Procfs setup of the sandbox's mount namespace.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ProcMount {
+    /// Keep the existing procfs.
+    Existing,
+    /// Mount a new procfs for the current PID namespace.
+    New,
+    /// Mount a new procfs which only exposes traceable processes.
+    SelfOnly,
+    /// Replace procfs with an empty directory.
+    Hidden,
+}
+
+/// Process handling seccomp user notifications of the sandboxee.
+enum Supervision {
+    Network(NetworkRules),
+    Audit(AuditRules),
+}
+
 /// Init process argument passed to `clone`.
 struct ProcessInitArg {
     path_exceptions: PathExceptions,
+    supervision: Option<Supervision>,
+    env_filter: Option<EnvFilter>,
+    protocol_filter: Option<ProtocolFilter>,
+    syscall_filter: SyscallFilter,
+    resource_limits: ResourceLimits,
+    dropped_capabilities: CapabilitySet,
+    process_filter: Option<BpfProgram>,
+    drop_privileges: bool,
+    loopback: bool,
+    uts_namespace: bool,
+    hostname: Option<String>,
+    proc_mount: ProcMount,
+    landlock_abi: u32,
 
     sandboxee: Command,
 
     parent_euid: Uid,
     parent_egid: Gid,
+    sandboxee_ids: (u32, u32),
 
     // FDs used by the child process.
     stdin_rx: Option<OwnedFd>,
     stdout_tx: Option<OwnedFd>,
     stderr_tx: Option<OwnedFd>,
     exit_signal_tx: OwnedFd,
+    sandboxee_pidfd_tx: OwnedFd,
+    setup_sync_rx: Option<OwnedFd>,
 
     // FDs passed to the child for closing them.
     stdin_tx: Option<OwnedFd>,
     stdout_rx: Option<OwnedFd>,
     stderr_rx: Option<OwnedFd>,
     exit_signal_rx: OwnedFd,
+    sandboxee_pidfd_rx: OwnedFd,
+    audit_forwarder: Option<AuditForwarder>,
 
     pid: i32,
 }
@@ -272,38 +1987,117 @@ impl ProcessInitArg {
     fn new(
         sandbox: LinuxSandbox,
         sandboxee: Command,
+        isolated: bool,
         exit_signal: (OwnedFd, OwnedFd),
         stdin: (Option<OwnedFd>, Option<OwnedFd>),
         stdout: (Option<OwnedFd>, Option<OwnedFd>),
         stderr: (Option<OwnedFd>, Option<OwnedFd>),
-    ) -> Self {
+    ) -> Result<Self> {
         // Get EUID/EGID outside of the namespaces.
         let parent_euid = rustix::process::geteuid();
         let parent_egid = rustix::process::getegid();
+        let sandboxee_ids = sandbox.sandboxee_ids();
 
-        Self {
+        // Socket used by PID 1 to pass back the sandboxee's PID file descriptor.
+        let sandboxee_pidfd = network::socket_pair()?;
+
+        let proc_mount = match sandbox.effective_proc_access() {
+            ProcAccess::Full => ProcMount::New,
+            ProcAccess::SelfOnly => ProcMount::SelfOnly,
+            ProcAccess::Hidden => ProcMount::Hidden,
+        };
+
+        // Network rules are ignored if networking is allowed.
+        let network_rules = if sandbox.allow_networking || sandbox.network_rules.is_empty() {
+            None
+        } else {
+            Some(sandbox.network_rules)
+        };
+        let mut supervision = network_rules.map(Supervision::Network);
+
+        // Environment variables are only passed to PID 1 if they haven't been
+        // restricted already.
+        let mut env_filter = isolated.then_some(sandbox.env_filter);
+
+        // Subprocesses can only be denied once the sandboxee was spawned.
+        let process_filter = if sandbox.deny_subprocesses {
+            Some(ProcessFilter::program(sandbox.audit_log.is_some())?)
+        } else {
+            None
+        };
+
+        // In audit mode, the host's filesystem, network and environment are accessible.
+        let mut path_exceptions = sandbox.path_exceptions;
+        let mut protocol_filter = sandbox.protocol_filter;
+        let mut audit_forwarder = None;
+        if let Some(log) = sandbox.audit_log {
+            let mut unrestricted = PathExceptions::default();
+            unrestricted.update("/".into(), true, true)?;
+            let path_exceptions = mem::replace(&mut path_exceptions, unrestricted);
+
+            let (log, structured) = match log {
+                AuditLog::File(log) => (log, false),
+                AuditLog::Channel(sender) => {
+                    let (log, forwarder) = AuditForwarder::new(sender)?;
+                    audit_forwarder = Some(forwarder);
+                    (log, true)
+                },
+            };
+
+            let rules = AuditRules { path_exceptions, log, structured };
+            supervision = Some(Supervision::Audit(rules));
+            protocol_filter = None;
+            env_filter = None;
+        }
+
+        Ok(Self {
+            supervision,
+            env_filter,
+            resource_limits: sandbox.resource_limits,
+            dropped_capabilities: sandbox.dropped_capabilities,
+            process_filter,
+            drop_privileges: sandbox.drop_privileges.is_some(),
+            loopback: sandbox.network_namespace == NetworkNamespace::LoopbackOnly,
+            uts_namespace: sandbox.uts_namespace,
+            hostname: sandbox.hostname,
+            proc_mount,
+            protocol_filter,
+            syscall_filter: sandbox.syscall_filter,
+            landlock_abi: sandbox.landlock_abi,
             parent_euid,
             parent_egid,
+            sandboxee_ids,
             sandboxee,
-            path_exceptions: sandbox.path_exceptions,
+            path_exceptions,
             stdin_rx: stdin.0,
             stdout_tx: stdout.1,
             stderr_tx: stderr.1,
             exit_signal_tx: exit_signal.1,
+            sandboxee_pidfd_tx: sandboxee_pidfd.1,
+            setup_sync_rx: None,
             stdin_tx: stdin.1,
             stdout_rx: stdout.0,
             stderr_rx: stderr.0,
             exit_signal_rx: exit_signal.0,
+            sandboxee_pidfd_rx: sandboxee_pidfd.0,
+            audit_forwarder,
             pid: -1,
-        }
+        })
     }
 }
 
 /// Path permissions required for the sandbox.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub(crate) struct PathExceptions {
     bind_mounts: HashMap<PathBuf, MountAttrFlags>,
     symlinks: Vec<(PathBuf, PathBuf)>,
+    append_only: HashSet<PathBuf>,
+    list_only: HashSet<PathBuf>,
+    exact: HashSet<PathBuf>,
+    denied: HashSet<PathBuf>,
+    remapped: Vec<(PathBuf, PathBuf, MountAttrFlags)>,
+    shared_memory: Vec<PathBuf>,
+    restrict_symlink_escape: bool,
 }
 
 impl PathExceptions {
@@ -315,6 +2109,163 @@ impl PathExceptions {
     /// If the bind mount already exists, it will *ADD* the additional
     /// permissions.
     fn update(&mut self, path: PathBuf, write: bool, execute: bool) -> Result<()> {
+        let canonical_path = self.canonicalize(path)?;
+
+        // Update bind mount's permission flags.
+
+        let flags = self
+            .bind_mounts
+            .entry(canonical_path.clone())
+            .or_insert(MountAttrFlags::RDONLY | MountAttrFlags::NOEXEC);
+
+        if write {
+            flags.remove(MountAttrFlags::RDONLY);
+            self.append_only.remove(&canonical_path);
+        }
+
+        if execute {
+            flags.remove(MountAttrFlags::NOEXEC);
+        }
+
+        self.list_only.remove(&canonical_path);
+
+        Ok(())
+    }
+
+    /// Add or modify a path's append-only exception.
+    ///
+    /// If the path already has write access, this is a noop.
+    fn update_append_only(&mut self, path: PathBuf) -> Result<()> {
+        let canonical_path = self.canonicalize(path)?;
+
+        let flags = self
+            .bind_mounts
+            .entry(canonical_path.clone())
+            .or_insert(MountAttrFlags::RDONLY | MountAttrFlags::NOEXEC);
+
+        // Ignore exception if full write access was already granted.
+        if flags.contains(MountAttrFlags::RDONLY) {
+            flags.remove(MountAttrFlags::RDONLY);
+            self.append_only.insert(canonical_path.clone());
+        }
+
+        self.list_only.remove(&canonical_path);
+
+        Ok(())
+    }
+
+    /// Add a directory's list-only exception.
+    ///
+    /// If the path already has read access, this is a noop.
+    fn update_list_only(&mut self, path: PathBuf) -> Result<()> {
+        let canonical_path = self.canonicalize(path)?;
+        if !canonical_path.is_dir() {
+            return Err(Error::InvalidPath(canonical_path));
+        }
+
+        if let Entry::Vacant(entry) = self.bind_mounts.entry(canonical_path.clone()) {
+            entry.insert(MountAttrFlags::RDONLY | MountAttrFlags::NOEXEC);
+            self.list_only.insert(canonical_path);
+        }
+
+        Ok(())
+    }
+
+    /// Add an exception for a path, without exposing anything beneath it.
+    ///
+    /// Files are mounted like regular read exceptions, while directories are
+    /// only created as empty directories inside the sandbox.
+    fn update_exact(&mut self, path: PathBuf) -> Result<()> {
+        let metadata = path.metadata().map_err(|err| Error::from_path(path.clone(), &err))?;
+        if !metadata.is_dir() {
+            return self.update(path, false, false);
+        }
+
+        let canonical_path = self.canonicalize(path)?;
+        self.exact.insert(canonical_path);
+
+        Ok(())
+    }
+
+    /// Add a shared memory object, which is created once the sandbox is
+    /// applied.
+    fn add_shared_memory(&mut self, path: PathBuf) {
+        if !self.shared_memory.contains(&path) {
+            self.shared_memory.push(path);
+        }
+    }
+
+    /// Create all shared memory objects and grant write access to them.
+    ///
+    /// Bind mounts require an existing file, so objects which do not exist
+    /// yet are created empty.
+    fn create_shared_memory(&mut self) -> Result<()> {
+        for path in mem::take(&mut self.shared_memory) {
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .mode(0o600)
+                .open(&path)
+                .map_err(|err| Error::from_path(path.clone(), &err))?;
+            self.update(path, true, false)?;
+        }
+        Ok(())
+    }
+
+    /// Add a symlink, without granting access to its target.
+    fn add_symlink(&mut self, symlink: PathBuf, target: impl Into<PathBuf>) {
+        self.symlinks.push((symlink, target.into()));
+    }
+
+    /// Deny all access to a path.
+    fn deny(&mut self, path: PathBuf) -> Result<()> {
+        let canonical_path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(err) => return Err(Error::from_path(path, &err)),
+        };
+
+        if self.bind_mounts.contains_key(&canonical_path) || self.exact.contains(&canonical_path) {
+            return Err(Error::ConflictingPath(canonical_path));
+        }
+
+        self.denied.insert(canonical_path);
+
+        Ok(())
+    }
+
+    /// Get all bind mounts, sorted by shortest length.
+    ///
+    /// This ensures parent directories are mounted before their children.
+    /// Mounts with the same permissions as their parent are omitted.
+    fn sorted_bind_mounts(&self) -> Vec<(&PathBuf, MountAttrFlags)> {
+        let mut bind_mounts: Vec<_> = self
+            .bind_mounts
+            .iter()
+            .map(|(path, flags)| {
+                (path, (*flags, self.append_only.contains(path), self.list_only.contains(path)))
+            })
+            .collect();
+        bind_mounts.sort_unstable_by(|(a_path, a_flags), (b_path, b_flags)| {
+            match a_path.components().count().cmp(&b_path.components().count()) {
+                CmpOrdering::Equal => (a_path, a_flags.0).cmp(&(b_path, b_flags.0)),
+                ord => ord,
+            }
+        });
+
+        crate::remove_redundant_paths(&mut bind_mounts);
+
+        bind_mounts.into_iter().map(|(path, (flags, ..))| (path, flags)).collect()
+    }
+
+    /// Get the canonical path for an exception.
+    ///
+    /// This will also record the original path for the creation of symlinks.
+    fn canonicalize(&mut self, path: PathBuf) -> Result<PathBuf> {
+        if self.restrict_symlink_escape {
+            crate::check_symlink_escape(&path)?;
+        }
+
         // Use canonical path for indexing.
         //
         // This ensures that a symlink and its target are treated like the same path for
@@ -323,34 +2274,184 @@ impl PathExceptions {
         // If the home path cannot be accessed, we ignore the exception.
         let canonical_path = match path.canonicalize() {
             Ok(path) => path,
-            Err(_) => return Err(Error::InvalidPath(path)),
+            Err(err) => return Err(Error::from_path(path, &err)),
         };
 
-        // Store original symlink path to create it if necessary.
-        if path_has_symlinks(&path) {
-            // Normalize symlink's path.
-            let absolute = absolute(&path)?;
-            let normalized = normalize_path(&absolute);
+        // Reject allowing access to explicitly denied paths.
+        if self.denied.contains(&canonical_path) {
+            return Err(Error::ConflictingPath(canonical_path));
+        }
 
-            self.symlinks.push((normalized, canonical_path.clone()));
+        // Store every symlink in the chain to create them if necessary.
+        let absolute = absolute(&path)?;
+        let mut symlink = normalize_path(&absolute);
+        for _ in 0..MAX_SYMLINK_HOPS {
+            let next = match resolve_symlink(&symlink) {
+                Some(next) => next,
+                None => break,
+            };
+            self.symlinks.push((symlink, canonical_path.clone()));
+            symlink = next;
         }
 
-        // Update bind mount's permission flags.
+        Ok(canonical_path)
+    }
 
-        let flags = self
+    /// Check if a path is visible inside the sandbox.
+    fn is_accessible(&self, path: &Path) -> bool {
+        let Ok(path) = path.canonicalize() else { return false };
+        self.permissions(&path).is_some()
+    }
+
+    /// Get the mount flags of a canonical path.
+    ///
+    /// Returns `None` if the path is not accessible.
+    fn permissions(&self, path: &Path) -> Option<MountAttrFlags> {
+        if self.denied.iter().any(|denied| path.starts_with(denied)) {
+            return None;
+        }
+
+        self.bind_mounts
+            .iter()
+            .filter(|(mount, _)| path.starts_with(mount))
+            .max_by_key(|(mount, _)| mount.components().count())
+            .map(|(_, flags)| *flags)
+            .or_else(|| {
+                self.exact.contains(path).then_some(MountAttrFlags::RDONLY | MountAttrFlags::NOEXEC)
+            })
+    }
+
+    /// Get warnings for exceptions which might not behave as expected.
+    fn warnings(&self) -> Vec<SandboxWarning> {
+        let mut warnings = Vec::new();
+
+        let bind_mounts = self.sorted_bind_mounts();
+        for (i, (path, flags)) in bind_mounts.iter().enumerate() {
+            // Check if access is reduced compared to the closest parent mount.
+            let parent = bind_mounts[..i].iter().rev().find(|(parent, _)| path.starts_with(parent));
+            if let Some((parent, parent_flags)) = parent {
+                let writable = |path: &PathBuf, flags: &MountAttrFlags| {
+                    !flags.contains(MountAttrFlags::RDONLY) && !self.append_only.contains(path)
+                };
+                let executable = |flags: &MountAttrFlags| !flags.contains(MountAttrFlags::NOEXEC);
+
+                if (writable(parent, parent_flags) && !writable(path, flags))
+                    || (executable(parent_flags) && !executable(flags))
+                {
+                    warnings.push(SandboxWarning::RestrictedSubpath {
+                        path: path.to_path_buf(),
+                        parent: parent.to_path_buf(),
+                    });
+                }
+            }
+
+            // Check that executable files can actually be executed.
+            if !flags.contains(MountAttrFlags::NOEXEC) {
+                let mode = path.metadata().map(|metadata| metadata.permissions().mode());
+                if matches!(mode, Ok(mode) if path.is_file() && mode & 0o111 == 0) {
+                    warnings.push(SandboxWarning::NotExecutable(path.to_path_buf()));
+                }
+            }
+        }
+
+        // Check that exception paths were not removed in the meantime.
+        let mut paths: Vec<_> = self.bind_mounts.keys().chain(&self.exact).collect();
+        paths.sort_unstable();
+        for path in paths {
+            if path.symlink_metadata().is_err() {
+                warnings.push(SandboxWarning::PathDoesNotExist(path.clone()));
+            }
+        }
+
+        // Check that denied paths would be accessible otherwise.
+        let mut denied: Vec<_> = self.denied.iter().collect();
+        denied.sort_unstable();
+        for path in denied {
+            if !bind_mounts.iter().any(|(mount, _)| path.starts_with(mount)) {
+                warnings.push(SandboxWarning::DenyIgnored(path.clone()));
+            }
+        }
+
+        warnings
+    }
+
+    /// Check if files beneath a path can only be listed, but not read.
+    fn is_list_only(&self, path: &Path) -> bool {
+        self.bind_mounts
+            .keys()
+            .filter(|mount| path.starts_with(mount))
+            .max_by_key(|mount| mount.components().count())
+            .is_some_and(|mount| self.list_only.contains(mount))
+    }
+
+    /// Get all paths whose files may be read.
+    ///
+    /// Returns `None` if there are no list-only paths, since no read
+    /// restrictions are necessary in that case.
+    fn read_paths(&self) -> Option<Vec<PathBuf>> {
+        if self.list_only.is_empty() {
+            return None;
+        }
+
+        let paths = self
             .bind_mounts
-            .entry(canonical_path)
-            .or_insert(MountAttrFlags::RDONLY | MountAttrFlags::NOEXEC);
+            .keys()
+            .filter(|path| !self.list_only.contains(*path))
+            .chain(self.remapped.iter().map(|(_, target, _)| target))
+            .cloned()
+            .collect();
 
-        if write {
-            flags.remove(MountAttrFlags::RDONLY);
+        Some(paths)
+    }
+
+    /// Get all paths whose files may be opened for writing.
+    ///
+    /// Returns `None` if no read-only devices are exposed, since read-only
+    /// bind mounts already prevent writes to all other files.
+    fn write_paths(&self) -> Option<Vec<PathBuf>> {
+        let read_only_device = self.bind_mounts.iter().any(|(path, flags)| {
+            let file_type = path.metadata().map(|metadata| metadata.file_type());
+            flags.contains(MountAttrFlags::RDONLY)
+                && file_type.is_ok_and(|file_type| {
+                    file_type.is_char_device() || file_type.is_block_device()
+                })
+        });
+        if !read_only_device {
+            return None;
         }
 
-        if execute {
-            flags.remove(MountAttrFlags::NOEXEC);
+        let paths = self
+            .bind_mounts
+            .iter()
+            .chain(self.remapped.iter().map(|(_, target, flags)| (target, flags)))
+            .filter(|(_, flags)| !flags.contains(MountAttrFlags::RDONLY))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        Some(paths)
+    }
+
+    /// Get all writable paths which may be truncated.
+    ///
+    /// Returns `None` if there are no append-only paths, since no truncation
+    /// restrictions are necessary in that case.
+    fn truncate_paths(&self) -> Option<Vec<PathBuf>> {
+        if self.append_only.is_empty() {
+            return None;
         }
 
-        Ok(())
+        let paths = self
+            .bind_mounts
+            .iter()
+            .filter(|(path, flags)| {
+                !flags.contains(MountAttrFlags::RDONLY) && !self.append_only.contains(*path)
+            })
+            .chain(self.remapped.iter().map(|(_, target, flags)| (target, flags)))
+            .filter(|(_, flags)| !flags.contains(MountAttrFlags::RDONLY))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        Some(paths)
     }
 }
 
@@ -421,9 +2522,79 @@ fn normalize_path(path: &Path) -> PathBuf {
     normalized
 }
 
-/// Check if a path contains any symlinks.
-fn path_has_symlinks(path: &Path) -> bool {
-    path.ancestors().any(|path| path.read_link().is_ok())
+/// Resolve the first symlink in an absolute, normalized path.
+///
+/// Returns `None` if the path does not contain any symlinks.
+fn resolve_symlink(path: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    let mut components = path.components();
+    while let Some(component) = components.next() {
+        resolved.push(component);
+
+        if let Ok(target) = resolved.read_link() {
+            resolved.pop();
+            resolved.push(target);
+            resolved.extend(components);
+            return Some(normalize_path(&resolved));
+        }
+    }
+    None
+}
+
+/// Switch the current process to a different user.
+///
+/// This removes all supplementary groups and sets the real, effective and
+/// saved UID and GID.
+fn set_ids(uid: u32, gid: u32) -> io::Result<()> {
+    unsafe {
+        if libc::setgroups(0, ptr::null()) != 0
+            || libc::setresgid(gid, gid, gid) != 0
+            || libc::setresuid(uid, uid, uid) != 0
+        {
+            return Err(IoError::last_os_error());
+        }
+    }
+
+    // Changing credentials makes procfs files owned by root, preventing us from
+    // writing our own user namespace mappings.
+    rustix::process::set_dumpable_behavior(rustix::process::DumpableBehavior::Dumpable)?;
+
+    Ok(())
+}
+
+/// Remove all capabilities from the ambient set.
+///
+/// Ambient capabilities are preserved across `execve`, even for unprivileged
+/// users.
+fn clear_ambient_capabilities() -> io::Result<()> {
+    let result =
+        unsafe { libc::prctl(libc::PR_CAP_AMBIENT, libc::PR_CAP_AMBIENT_CLEAR_ALL, 0, 0, 0) };
+    match result {
+        0 => Ok(()),
+        _ => Err(IoError::last_os_error()),
+    }
+}
+
+/// Limit the size of core dumps written by the current process.
+///
+/// A limit of zero also lowers the hard limit, preventing core dumps from being
+/// re-enabled. Without a limit, the soft limit is raised to the hard limit.
+fn set_core_dump_limit(limit: Option<u64>) -> io::Result<()> {
+    let mut rlimit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut rlimit) } != 0 {
+        return Err(IoError::last_os_error());
+    }
+
+    match limit {
+        Some(0) => rlimit = libc::rlimit { rlim_cur: 0, rlim_max: 0 },
+        Some(limit) => rlimit.rlim_cur = limit.min(rlimit.rlim_max),
+        None => rlimit.rlim_cur = rlimit.rlim_max,
+    }
+
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &rlimit) } != 0 {
+        return Err(IoError::last_os_error());
+    }
+    Ok(())
 }
 
 /// Get the number of threads used by the current process.
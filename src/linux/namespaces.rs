@@ -1,9 +1,9 @@
 //! Linux namespaces.
 
-use std::cmp::Ordering;
 use std::ffi::{CStr, CString};
 use std::fs::{self, File};
 use std::io::Error as IoError;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
 use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs as unixfs;
 use std::path::{Component, Path, PathBuf};
@@ -16,11 +16,19 @@ use crate::linux::PathExceptions;
 /// Path for mount namespace's new root.
 const NEW_ROOT: &str = "/tmp/birdcage-root";
 
+/// Path for staging the mount namespace's hosts file.
+const HOSTS_STAGING: &str = "/tmp/birdcage-hosts";
+
 /// Isolate filesystem access in an existing mount namespace.
 ///
 /// This will deny access to any path which isn't part of `bind_mounts`. Allowed
 /// paths are mounted according to their bind mount flags.
-pub(crate) fn setup_mount_namespace(exceptions: PathExceptions) -> io::Result<()> {
+///
+/// If `hosts` is present, it will replace the content of `/etc/hosts`.
+pub(crate) fn setup_mount_namespace(
+    exceptions: PathExceptions,
+    hosts: Option<String>,
+) -> io::Result<()> {
     // Get target paths for new and old root.
     let new_root = PathBuf::from(NEW_ROOT);
 
@@ -36,17 +44,8 @@ pub(crate) fn setup_mount_namespace(exceptions: PathExceptions) -> io::Result<()
     // aren't created outside the sandbox.
     mount_tmpfs(&new_root_c)?;
 
-    // Sort bind mounts by shortest length, to create parents before their children.
-    let mut bind_mounts: Vec<_> = exceptions.bind_mounts.into_iter().collect();
-    bind_mounts.sort_unstable_by(|(a_path, a_flags), (b_path, b_flags)| {
-        match a_path.components().count().cmp(&b_path.components().count()) {
-            Ordering::Equal => (a_path, a_flags).cmp(&(b_path, b_flags)),
-            ord => ord,
-        }
-    });
-
     // Bind mount all allowed directories.
-    for (path, flags) in bind_mounts {
+    for (path, flags) in exceptions.sorted_bind_mounts() {
         let src_c = CString::new(path.as_os_str().as_bytes()).unwrap();
 
         // Get bind mount destination.
@@ -55,11 +54,13 @@ pub(crate) fn setup_mount_namespace(exceptions: PathExceptions) -> io::Result<()
         let dst_c = CString::new(dst.as_os_str().as_bytes()).unwrap();
 
         // Create mount target.
-        if let Err(err) = copy_tree(&path, &new_root) {
+        if let Err(err) = copy_tree(path, &new_root) {
             log::error!("skipping birdcage exception {path:?}: {err}");
             continue;
         }
 
+        event!(target: "birdcage::linux", "bind mounting {path:?} with {flags:?}");
+
         // Bind path with full permissions.
         bind_mount(&src_c, &dst_c)?;
 
@@ -67,9 +68,49 @@ pub(crate) fn setup_mount_namespace(exceptions: PathExceptions) -> io::Result<()
         update_mount_flags(&dst_c, flags | MountAttrFlags::NOSUID)?;
     }
 
+    // Mount sources at their sandbox-only targets.
+    for (src, target, flags) in &exceptions.remapped {
+        let unrooted_path = target.strip_prefix("/").unwrap();
+        let dst = new_root.join(unrooted_path);
+
+        // Create mount target, unless it would be created on a bind mount.
+        if let Err(err) = create_mount_target(&exceptions, src, target, &dst) {
+            log::error!("skipping birdcage bind mount {target:?}: {err}");
+            continue;
+        }
+
+        event!(target: "birdcage::linux", "bind mounting {src:?} at {target:?} with {flags:?}");
+
+        let src_c = CString::new(src.as_os_str().as_bytes()).unwrap();
+        let dst_c = CString::new(dst.as_os_str().as_bytes()).unwrap();
+        bind_mount(&src_c, &dst_c)?;
+        update_mount_flags(&dst_c, *flags | MountAttrFlags::NOSUID)?;
+    }
+
+    // Create directories without exposing their entries.
+    for path in &exceptions.exact {
+        event!(target: "birdcage::linux", "creating {path:?} without its entries");
+        if let Err(err) = copy_tree(path, &new_root) {
+            log::error!("skipping birdcage exception {path:?}: {err}");
+        }
+    }
+
     // Ensure original symlink paths are available.
     create_symlinks(&new_root, exceptions.symlinks)?;
 
+    // Pin hostnames to the addresses allowed by network exceptions.
+    if let Some(hosts) = hosts {
+        mount_hosts(&new_root, &hosts)?;
+    }
+
+    // Hide denied paths exposed by any of the bind mounts.
+    for path in &exceptions.denied {
+        event!(target: "birdcage::linux", "hiding denied path {path:?}");
+        let unrooted_path = path.strip_prefix("/").unwrap();
+        let dst = new_root.join(unrooted_path);
+        mask_path(&dst)?;
+    }
+
     // Bind mount old procfs.
     let old_proc_c = CString::new("/proc").unwrap();
     let new_proc = new_root.join("proc");
@@ -90,6 +131,108 @@ pub(crate) fn setup_mount_namespace(exceptions: PathExceptions) -> io::Result<()
     Ok(())
 }
 
+/// Hide a path inside the new root.
+///
+/// Directories are replaced with an empty read-only tmpfs, while files are
+/// replaced with an inaccessible `/dev/null`. Paths which do not exist are
+/// ignored.
+fn mask_path(dst: &Path) -> io::Result<()> {
+    let metadata = match dst.symlink_metadata() {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(()),
+    };
+
+    let dst_c = CString::new(dst.as_os_str().as_bytes()).unwrap();
+    let flags = MountAttrFlags::RDONLY
+        | MountAttrFlags::NOSUID
+        | MountAttrFlags::NODEV
+        | MountAttrFlags::NOEXEC;
+
+    if metadata.is_dir() {
+        mount_tmpfs(&dst_c)?;
+    } else {
+        let null_c = CString::new("/dev/null").unwrap();
+        bind_mount(&null_c, &dst_c)?;
+    }
+
+    update_mount_flags(&dst_c, flags)
+}
+
+/// Replace `/proc` with an empty directory.
+///
+/// This must be called in a mount namespace owned by the current user
+/// namespace.
+pub fn hide_proc() -> io::Result<()> {
+    mask_path(Path::new("/proc"))
+}
+
+/// Create the target for mounting `src` at `target` inside the new root.
+///
+/// Fails if the target does not exist and would have to be created beneath
+/// another exception's bind mount, since that would modify the host.
+fn create_mount_target(
+    exceptions: &PathExceptions,
+    src: &Path,
+    target: &Path,
+    dst: &Path,
+) -> io::Result<()> {
+    if dst.symlink_metadata().is_ok() {
+        return Ok(());
+    }
+
+    if exceptions.bind_mounts.keys().any(|path| target.starts_with(path)) {
+        return Err(IoError::new(io::ErrorKind::NotFound, "target does not exist"));
+    }
+
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if src.is_dir() {
+        fs::create_dir(dst)
+    } else {
+        File::create(dst).map(drop)
+    }
+}
+
+/// Replace `/etc/hosts` inside the new root.
+///
+/// The content is written to a separate tmpfs, to avoid modifying any files
+/// exposed by bind mounts.
+fn mount_hosts(new_root: &Path, hosts: &str) -> io::Result<()> {
+    let dst = new_root.join("etc/hosts");
+
+    // Create mount target.
+    if !dst.exists() {
+        let created = fs::create_dir_all(new_root.join("etc")).and_then(|_| File::create(&dst));
+        if let Err(err) = created {
+            log::error!("skipping birdcage hosts file: {err}");
+            return Ok(());
+        }
+    }
+
+    // Write hosts file to a temporary tmpfs.
+    let staging = PathBuf::from(HOSTS_STAGING);
+    fs::create_dir_all(&staging)?;
+    let staging_c = CString::new(staging.as_os_str().as_bytes()).unwrap();
+    mount_tmpfs(&staging_c)?;
+    let src = staging.join("hosts");
+    fs::write(&src, hosts)?;
+
+    // Bind the hosts file on top of the original one.
+    let src_c = CString::new(src.as_os_str().as_bytes()).unwrap();
+    let dst_c = CString::new(dst.as_os_str().as_bytes()).unwrap();
+    bind_mount(&src_c, &dst_c)?;
+
+    let flags = MountAttrFlags::RDONLY
+        | MountAttrFlags::NOSUID
+        | MountAttrFlags::NODEV
+        | MountAttrFlags::NOEXEC;
+    update_mount_flags(&dst_c, flags)?;
+
+    // Remove the temporary tmpfs, the bind mount keeps the file alive.
+    umount(&staging_c)
+}
+
 /// Create missing symlinks.
 ///
 /// If the parent directory of a symlink is mapped, we do not need to map the
@@ -178,11 +321,20 @@ fn mount_tmpfs(dst: &CStr) -> io::Result<()> {
 }
 
 /// Mount a new procfs.
-pub fn mount_proc(dst: &CStr) -> io::Result<()> {
+///
+/// If `self_only` is `true`, only processes which can be traced by the reader
+/// are visible and all non-process files are hidden. This must be called
+/// before any IDs except root are mapped in the current user namespace.
+pub fn mount_proc(dst: &CStr, self_only: bool) -> io::Result<()> {
     let flags = MountFlags::NOSUID | MountFlags::NODEV | MountFlags::NOEXEC;
     let fstype = CString::new("proc").unwrap();
+    // Processes in the `gid` group can see all other processes, so an ID that is
+    // not mapped in the sandbox's user namespace is used instead of the
+    // default root group.
+    let options = CString::new("hidepid=invisible,subset=pid,gid=65534").unwrap();
+    let data = if self_only { options.as_ptr() } else { ptr::null() };
     let res = unsafe {
-        libc::mount(fstype.as_ptr(), dst.as_ptr(), fstype.as_ptr(), flags.bits(), ptr::null())
+        libc::mount(fstype.as_ptr(), dst.as_ptr(), fstype.as_ptr(), flags.bits(), data.cast())
     };
 
     if res == 0 {
@@ -276,6 +428,29 @@ fn umount(target: &CStr) -> io::Result<()> {
     }
 }
 
+/// Check if user namespaces are disabled by the kernel's configuration.
+///
+/// Returns a description of the restriction, if user namespaces cannot be
+/// created by the current process.
+pub fn user_namespaces_restricted() -> Option<&'static str> {
+    let sysctl = |path| fs::read_to_string(path).ok().map(|value| value.trim() == "0");
+
+    if sysctl("/proc/sys/user/max_user_namespaces") == Some(true) {
+        return Some("user namespaces are disabled by /proc/sys/user/max_user_namespaces");
+    }
+
+    // Root can always create user namespaces on kernels with this Debian patch.
+    let root = unsafe { libc::geteuid() } == 0;
+    if !root && sysctl("/proc/sys/kernel/unprivileged_userns_clone") == Some(true) {
+        return Some(
+            "unprivileged user namespaces are disabled by \
+             /proc/sys/kernel/unprivileged_userns_clone",
+        );
+    }
+
+    None
+}
+
 /// Create a new user namespace.
 ///
 /// The parent and child UIDs and GIDs define the user and group mappings
@@ -316,6 +491,51 @@ pub fn map_ids(
     Ok(())
 }
 
+/// Map root of another process's user namespace to an unprivileged user.
+///
+/// Since arbitrary IDs can only be mapped from the parent namespace, this
+/// must be called by a privileged process for its child.
+pub fn map_process_ids(pid: libc::pid_t, uid: u32, gid: u32) -> io::Result<()> {
+    fs::write(format!("/proc/{pid}/uid_map"), format!("0 {uid} 1\n"))?;
+    fs::write(format!("/proc/{pid}/gid_map"), format!("0 {gid} 1\n"))?;
+    Ok(())
+}
+
+/// Bring up the loopback interface of the current network namespace.
+pub fn enable_loopback() -> io::Result<()> {
+    let socket = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if socket == -1 {
+        return Err(IoError::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+
+    let mut ifreq: libc::ifreq = unsafe { mem::zeroed() };
+    for (dst, src) in ifreq.ifr_name.iter_mut().zip(b"lo") {
+        *dst = *src as libc::c_char;
+    }
+
+    unsafe {
+        if libc::ioctl(socket.as_raw_fd(), libc::SIOCGIFFLAGS, &mut ifreq) == -1 {
+            return Err(IoError::last_os_error());
+        }
+        ifreq.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+        if libc::ioctl(socket.as_raw_fd(), libc::SIOCSIFFLAGS, &ifreq) == -1 {
+            return Err(IoError::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Set the hostname of the current UTS namespace.
+pub fn set_hostname(hostname: &str) -> io::Result<()> {
+    let result = unsafe { libc::sethostname(hostname.as_ptr().cast(), hostname.len()) };
+    if result == -1 {
+        return Err(IoError::last_os_error());
+    }
+    Ok(())
+}
+
 /// Enter a namespace.
 fn unshare(namespaces: Namespaces) -> io::Result<()> {
     let result = unsafe { libc::unshare(namespaces.bits()) };
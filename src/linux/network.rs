@@ -0,0 +1,811 @@
+//! Granular network access.
+//!
+//! Sandboxed processes with granular network exceptions are placed in an
+//! isolated network namespace, while a separate supervisor process stays in the
+//! host's network namespace.
+//!
+//! Calls to `connect` and `bind` are forwarded to the supervisor using seccomp
+//! user notifications. The supervisor validates its own copy of the socket
+//! address and then performs the operation on behalf of the sandboxee. For
+//! allowed TCP addresses, this is done by creating a new socket in the host's
+//! network namespace and replacing the sandboxee's socket with it.
+//!
+//! Since the sandboxee's arguments are never used after validation, this avoids
+//! any time-of-check to time-of-use issues.
+//!
+//! When UNIX socket exceptions are present, connecting to UNIX socket paths is
+//! only allowed for the sockets in the rules. Since the allowed sockets are
+//! bind mounted, their paths cannot be replaced by the sandboxee.
+
+use std::ffi::OsStr;
+use std::io::{Error as IoError, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fs, io, mem, ptr, thread};
+
+/// Flag for `pidfd_open` to create a PID file descriptor for a thread.
+const PIDFD_THREAD: libc::c_uint = libc::O_EXCL as libc::c_uint;
+
+/// Socket options copied to replacement sockets.
+const COPIED_SOCKET_OPTIONS: &[(libc::c_int, libc::c_int)] = &[
+    (libc::SOL_SOCKET, libc::SO_REUSEADDR),
+    (libc::SOL_SOCKET, libc::SO_REUSEPORT),
+    (libc::SOL_SOCKET, libc::SO_KEEPALIVE),
+    (libc::SOL_SOCKET, libc::SO_OOBINLINE),
+    (libc::IPPROTO_TCP, libc::TCP_NODELAY),
+    (libc::IPPROTO_IPV6, libc::IPV6_V6ONLY),
+];
+
+/// Network addresses accessible from inside the sandbox.
+#[derive(Default, Clone)]
+pub struct NetworkRules {
+    hosts: Vec<(String, Vec<IpAddr>)>,
+    connect: Vec<SocketAddr>,
+    subnets: Vec<(IpAddr, u8)>,
+    bind: Vec<SocketAddr>,
+    unix: Vec<PathBuf>,
+    connect_all: bool,
+    bind_all: bool,
+}
+
+impl NetworkRules {
+    /// Allow outgoing TCP connections to an address.
+    pub fn allow_connect(&mut self, addr: SocketAddr) {
+        self.connect.push(addr);
+    }
+
+    /// Allow outgoing TCP connections to all addresses of a subnet.
+    pub fn allow_subnet(&mut self, addr: IpAddr, prefix_len: u8) {
+        self.subnets.push((addr, prefix_len));
+    }
+
+    /// Allow binding TCP sockets to an address.
+    pub fn allow_bind(&mut self, addr: SocketAddr) {
+        self.bind.push(addr);
+    }
+
+    /// Allow outgoing TCP connections to any address.
+    pub fn allow_connect_all(&mut self) {
+        self.connect_all = true;
+    }
+
+    /// Allow binding TCP sockets to any address.
+    pub fn allow_bind_all(&mut self) {
+        self.bind_all = true;
+    }
+
+    /// Allow connecting to a UNIX socket.
+    ///
+    /// Once any UNIX socket is allowed, connections to all other UNIX socket
+    /// paths are refused.
+    pub fn allow_unix(&mut self, path: PathBuf) -> io::Result<()> {
+        let path = fs::canonicalize(path)?;
+
+        // Ensure the path fits into `sockaddr_un`.
+        if path.as_os_str().len() >= mem::size_of::<[libc::c_char; 108]>() {
+            return Err(IoError::from_raw_os_error(libc::ENAMETOOLONG));
+        }
+
+        self.unix.push(path);
+
+        Ok(())
+    }
+
+    /// Allow outgoing TCP connections to all addresses of a host.
+    ///
+    /// The hostname is resolved immediately, connections are only allowed to
+    /// the addresses it resolved to.
+    pub fn allow_host(&mut self, host: String, port: Option<u16>) -> io::Result<()> {
+        let addrs: Vec<_> = (host.as_str(), port.unwrap_or(0)).to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(IoError::new(ErrorKind::NotFound, format!("no addresses for {host:?}")));
+        }
+
+        let mut ips: Vec<_> = addrs.iter().map(|addr| addr.ip()).collect();
+        ips.sort_unstable();
+        ips.dedup();
+
+        self.connect.extend(addrs);
+
+        // IP literals do not need to be pinned in the hosts file.
+        if host.parse::<IpAddr>().is_err() {
+            self.hosts.push((host, ips));
+        }
+
+        Ok(())
+    }
+
+    /// Get the hosts file pinning all hostnames to their allowed addresses.
+    ///
+    /// Returns `None` if no hostname exceptions are present.
+    pub fn hosts_file(&self) -> Option<String> {
+        if self.hosts.is_empty() {
+            return None;
+        }
+
+        let mut hosts = String::from("127.0.0.1 localhost\n::1 localhost\n");
+        for (host, ips) in &self.hosts {
+            for ip in ips {
+                hosts.push_str(&format!("{ip} {host}\n"));
+            }
+        }
+
+        Some(hosts)
+    }
+
+    /// Check if no granular network exceptions are present.
+    pub fn is_empty(&self) -> bool {
+        self.connect.is_empty()
+            && self.subnets.is_empty()
+            && self.bind.is_empty()
+            && self.unix.is_empty()
+            && !self.connect_all
+            && !self.bind_all
+    }
+
+    /// Check if address exceptions are covered by rules allowing all addresses.
+    pub fn is_redundant(&self) -> bool {
+        (self.connect_all && (!self.connect.is_empty() || !self.subnets.is_empty()))
+            || (self.bind_all && !self.bind.is_empty())
+    }
+
+    /// Describe all network rules.
+    pub fn summary(&self) -> String {
+        let addrs = |all, addrs: &[SocketAddr]| match all {
+            true => "any".into(),
+            false => format!("{addrs:?}"),
+        };
+        let connect = addrs(self.connect_all, &self.connect);
+        let bind = addrs(self.bind_all, &self.bind);
+        let mut summary = format!("connect to {connect}, bind to {bind}");
+        if !self.subnets.is_empty() && !self.connect_all {
+            let subnets: Vec<_> =
+                self.subnets.iter().map(|(addr, len)| format!("{addr}/{len}")).collect();
+            summary.push_str(&format!(", connect to subnets {subnets:?}"));
+        }
+        if !self.unix.is_empty() {
+            summary.push_str(&format!(", unix sockets {:?}", self.unix));
+        }
+        summary
+    }
+
+    /// Get the allowed UNIX socket matching a path.
+    ///
+    /// Returns `None` if UNIX socket connections are not restricted.
+    fn unix_socket(&self, path: &Path) -> Option<io::Result<&Path>> {
+        if self.unix.is_empty() {
+            return None;
+        }
+
+        let canonical_path = fs::canonicalize(path).ok();
+        let rule = self.unix.iter().find(|rule| Some(rule.as_path()) == canonical_path.as_deref());
+        Some(rule.map(|rule| rule.as_path()).ok_or(IoError::from_raw_os_error(libc::ECONNREFUSED)))
+    }
+
+    /// Check if an address matches any of the rules.
+    fn allows(rules: &[SocketAddr], addr: &SocketAddr) -> bool {
+        let ip = canonical_ip(addr.ip());
+        rules.iter().any(|rule| {
+            canonical_ip(rule.ip()) == ip && (rule.port() == 0 || rule.port() == addr.port())
+        })
+    }
+}
+
+/// Handle for passing the seccomp listener to the supervisor.
+pub struct Supervisor {
+    socket: OwnedFd,
+}
+
+impl Supervisor {
+    /// Spawn the network supervisor process.
+    ///
+    /// This must be called while the current process is still in the host's
+    /// network namespace, but after its mount namespace is finalized.
+    pub fn spawn(rules: NetworkRules) -> io::Result<Self> {
+        Self::fork("network supervisor", move |socket| run_supervisor(rules, socket))
+    }
+
+    /// Fork a supervisor process.
+    ///
+    /// The `run` function is called in the child with the socket used to
+    /// receive the seccomp listener.
+    pub fn fork<F>(name: &str, run: F) -> io::Result<Self>
+    where
+        F: FnOnce(OwnedFd) -> io::Result<()>,
+    {
+        let (parent_socket, child_socket) = socket_pair()?;
+
+        match unsafe { libc::fork() } {
+            -1 => Err(IoError::last_os_error()),
+            0 => {
+                drop(parent_socket);
+                let exit_code = match run(child_socket) {
+                    Ok(()) => 0,
+                    Err(err) => {
+                        eprintln!("{name} failure: {err}");
+                        1
+                    },
+                };
+                unsafe { libc::_exit(exit_code) };
+            },
+            _ => Ok(Self { socket: parent_socket }),
+        }
+    }
+
+    /// Start supervising a seccomp user notification listener.
+    pub fn supervise(self, listener: OwnedFd) -> io::Result<()> {
+        send_fd(&self.socket, listener.as_raw_fd())
+    }
+}
+
+/// Network supervisor main loop.
+fn run_supervisor(rules: NetworkRules, socket: OwnedFd) -> io::Result<()> {
+    // Prevent sandboxees from accessing our memory.
+    rustix::process::set_dumpable_behavior(rustix::process::DumpableBehavior::NotDumpable)?;
+
+    // Ensure the supervisor doesn't outlive the sandbox.
+    rustix::process::set_parent_process_death_signal(Some(rustix::process::Signal::Kill))?;
+
+    // Wait for the seccomp listener.
+    let listener = Arc::new(recv_fd(&socket)?);
+    drop(socket);
+    let rules = Arc::new(rules);
+
+    loop {
+        let notif = match recv_notification(&listener) {
+            Ok(notif) => notif,
+            // Sandboxee was interrupted or exited before we received the notification.
+            Err(err) if err.raw_os_error() == Some(libc::ENOENT) => continue,
+            Err(err) if err.raw_os_error() == Some(libc::EINTR) => continue,
+            Err(err) => return Err(err),
+        };
+
+        let response = match Request::new(&listener, &notif) {
+            Ok(Some(request)) => request.handle(&listener, &rules),
+            Ok(None) => continue,
+            Err(err) => Response::Error(err.raw_os_error().unwrap_or(libc::EPERM)),
+        };
+
+        // Failure indicates the sandboxee is no longer waiting for a response.
+        let _ = response.send(&listener, notif.id);
+    }
+}
+
+/// Intercepted `connect` or `bind` call.
+struct Request {
+    kind: RequestKind,
+    id: u64,
+    fd: RawFd,
+    cloexec: bool,
+    socket: OwnedFd,
+    addr: Vec<u8>,
+    cwd: String,
+}
+
+impl Request {
+    /// Read all syscall arguments from the sandboxee.
+    ///
+    /// Returns `None` if the notification is no longer valid.
+    fn new(listener: &OwnedFd, notif: &libc::seccomp_notif) -> io::Result<Option<Self>> {
+        let kind = match notif.data.nr as libc::c_long {
+            libc::SYS_connect => RequestKind::Connect,
+            libc::SYS_bind => RequestKind::Bind,
+            _ => return Err(IoError::from_raw_os_error(libc::ENOSYS)),
+        };
+        let fd = notif.data.args[0] as RawFd;
+        let addr_ptr = notif.data.args[1] as usize;
+        let addr_len = notif.data.args[2] as usize;
+
+        if addr_len > mem::size_of::<libc::sockaddr_storage>() {
+            return Err(IoError::from_raw_os_error(libc::EINVAL));
+        }
+
+        // Copy socket address from the sandboxee's memory.
+        let mut addr = vec![0; addr_len];
+        let local = libc::iovec { iov_base: addr.as_mut_ptr() as _, iov_len: addr_len };
+        let remote = libc::iovec { iov_base: addr_ptr as _, iov_len: addr_len };
+        let read = unsafe { libc::process_vm_readv(notif.pid as _, &local, 1, &remote, 1, 0) };
+        if read != addr_len as isize {
+            return Err(IoError::from_raw_os_error(libc::EFAULT));
+        }
+
+        // Duplicate the sandboxee's socket.
+        let pidfd = pidfd_open(notif.pid)?;
+        let socket = pidfd_getfd(&pidfd, fd)?;
+        let cloexec = fd_cloexec(notif.pid, fd).unwrap_or(true);
+        let cwd = format!("/proc/{}/cwd", notif.pid);
+
+        // Ensure the PID still belongs to the sandboxee.
+        if !notification_valid(listener, notif.id) {
+            return Ok(None);
+        }
+
+        Ok(Some(Self { kind, id: notif.id, fd, cloexec, socket, addr, cwd }))
+    }
+
+    /// Process the request.
+    fn handle(self, listener: &Arc<OwnedFd>, rules: &NetworkRules) -> Response {
+        if socket_option(&self.socket, libc::SOL_SOCKET, libc::SO_DOMAIN).is_err() {
+            return Response::Error(libc::ENOTSOCK);
+        }
+
+        // Perform non-internet operations on the sandboxee's socket.
+        let inet_addr = match parse_inet_addr(&self.addr) {
+            Ok(Some(addr)) => addr,
+            Ok(None) => return self.forward(listener, rules),
+            Err(err) => return Response::from(err),
+        };
+
+        let allowed = match self.kind {
+            RequestKind::Connect => {
+                rules.connect_all
+                    || NetworkRules::allows(&rules.connect, &inet_addr)
+                    || rules.subnets.iter().any(|(addr, prefix_len)| {
+                        crate::subnet_contains(*addr, *prefix_len, inet_addr.ip())
+                    })
+            },
+            RequestKind::Bind => rules.bind_all || NetworkRules::allows(&rules.bind, &inet_addr),
+        };
+
+        // Only TCP sockets are supported, since other protocols could send to
+        // arbitrary addresses after the replacement.
+        let is_tcp = socket_option(&self.socket, libc::SOL_SOCKET, libc::SO_PROTOCOL)
+            .is_ok_and(|protocol| protocol == libc::IPPROTO_TCP);
+
+        match (self.kind, allowed && is_tcp) {
+            (RequestKind::Connect, true) => self.replace(listener),
+            (RequestKind::Bind, true) => self.replace(listener),
+            (RequestKind::Connect, false) => Response::Error(libc::ECONNREFUSED),
+            (RequestKind::Bind, false) => Response::Error(libc::EACCES),
+        }
+    }
+
+    /// Perform the request on a new TCP socket in the host's network namespace.
+    fn replace(self, listener: &Arc<OwnedFd>) -> Response {
+        let domain = match socket_option(&self.socket, libc::SOL_SOCKET, libc::SO_DOMAIN) {
+            Ok(domain) => domain,
+            Err(err) => return Response::from(err),
+        };
+
+        let host_socket =
+            unsafe { libc::socket(domain, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0) };
+        if host_socket == -1 {
+            return Response::from(IoError::last_os_error());
+        }
+        let host_socket = unsafe { OwnedFd::from_raw_fd(host_socket) };
+
+        // Mirror relevant socket configuration.
+        for (level, name) in COPIED_SOCKET_OPTIONS {
+            if let Ok(value) = socket_option(&self.socket, *level, *name) {
+                let _ = set_socket_option(&host_socket, *level, *name, value);
+            }
+        }
+        let status_flags = unsafe { libc::fcntl(self.socket.as_raw_fd(), libc::F_GETFL) };
+        let nonblocking = status_flags != -1 && status_flags & libc::O_NONBLOCK != 0;
+        if nonblocking {
+            unsafe { libc::fcntl(host_socket.as_raw_fd(), libc::F_SETFL, libc::O_NONBLOCK) };
+        }
+
+        let result = match self.kind {
+            RequestKind::Connect if !nonblocking => {
+                // Avoid blocking other requests while waiting for the connection.
+                let listener = listener.clone();
+                thread::spawn(move || {
+                    let response = match self.syscall(&host_socket, &self.addr) {
+                        Ok(()) => self.inject(&listener, host_socket, 0),
+                        Err(err) => Response::from(err),
+                    };
+                    let _ = response.send(&listener, self.id);
+                });
+                return Response::Deferred;
+            },
+            _ => self.syscall(&host_socket, &self.addr),
+        };
+
+        match result {
+            Ok(()) => self.inject(listener, host_socket, 0),
+            Err(err) if err.raw_os_error() == Some(libc::EINPROGRESS) => {
+                self.inject(listener, host_socket, libc::EINPROGRESS)
+            },
+            Err(err) => Response::from(err),
+        }
+    }
+
+    /// Perform the request on the sandboxee's socket.
+    fn forward(self, listener: &Arc<OwnedFd>, rules: &NetworkRules) -> Response {
+        let mut addr = match self.resolve_unix_path() {
+            Ok(addr) => addr,
+            Err(err) => return Response::from(err),
+        };
+
+        // Connect to the allowed socket path, rather than the sandboxee's path.
+        if let (RequestKind::Connect, Some(path)) = (self.kind, unix_path(&addr)) {
+            match rules.unix_socket(Path::new(OsStr::from_bytes(path))) {
+                Some(Ok(rule)) => addr = unix_addr(rule),
+                Some(Err(err)) => return Response::from(err),
+                None => (),
+            }
+        }
+
+        let status_flags = unsafe { libc::fcntl(self.socket.as_raw_fd(), libc::F_GETFL) };
+        let nonblocking = status_flags != -1 && status_flags & libc::O_NONBLOCK != 0;
+
+        if matches!(self.kind, RequestKind::Connect) && !nonblocking {
+            // Avoid blocking other requests while waiting for the connection.
+            let listener = listener.clone();
+            thread::spawn(move || {
+                let response = Response::from(self.syscall(&self.socket, &addr));
+                let _ = response.send(&listener, self.id);
+            });
+            return Response::Deferred;
+        }
+
+        Response::from(self.syscall(&self.socket, &addr))
+    }
+
+    /// Execute the `connect` or `bind` syscall.
+    fn syscall(&self, socket: &OwnedFd, addr: &[u8]) -> io::Result<()> {
+        let addr_ptr = addr.as_ptr() as *const libc::sockaddr;
+        let addr_len = addr.len() as libc::socklen_t;
+        let result = unsafe {
+            match self.kind {
+                RequestKind::Connect => libc::connect(socket.as_raw_fd(), addr_ptr, addr_len),
+                RequestKind::Bind => libc::bind(socket.as_raw_fd(), addr_ptr, addr_len),
+            }
+        };
+
+        match result {
+            0 => Ok(()),
+            _ => Err(IoError::last_os_error()),
+        }
+    }
+
+    /// Replace the sandboxee's socket and complete the request.
+    fn inject(&self, listener: &OwnedFd, socket: OwnedFd, errno: libc::c_int) -> Response {
+        let addfd = libc::seccomp_notif_addfd {
+            id: self.id,
+            flags: libc::SECCOMP_ADDFD_FLAG_SETFD as u32,
+            srcfd: socket.as_raw_fd() as u32,
+            newfd: self.fd as u32,
+            newfd_flags: if self.cloexec { libc::O_CLOEXEC as u32 } else { 0 },
+        };
+
+        let result = unsafe {
+            libc::ioctl(listener.as_raw_fd(), libc::SECCOMP_IOCTL_NOTIF_ADDFD as _, &addfd)
+        };
+
+        match result {
+            -1 => Response::from(IoError::last_os_error()),
+            _ if errno != 0 => Response::Error(errno),
+            _ => Response::Success,
+        }
+    }
+
+    /// Make relative UNIX socket paths relative to the sandboxee's working
+    /// directory.
+    fn resolve_unix_path(&self) -> io::Result<Vec<u8>> {
+        let path_offset = mem::size_of::<libc::sa_family_t>();
+        let is_unix = sockaddr_family(&self.addr) == Some(libc::AF_UNIX as libc::sa_family_t);
+
+        // Ignore abstract, unnamed and absolute sockets.
+        match self.addr.get(path_offset) {
+            Some(b'/' | b'\0') | None => return Ok(self.addr.clone()),
+            Some(_) if !is_unix => return Ok(self.addr.clone()),
+            Some(_) => (),
+        }
+
+        let path = &self.addr[path_offset..];
+        let path = path.split(|byte| *byte == b'\0').next().unwrap_or(path);
+        let mut resolved = self.addr[..path_offset].to_vec();
+        resolved.extend_from_slice(self.cwd.as_bytes());
+        resolved.push(b'/');
+        resolved.extend_from_slice(path);
+        resolved.push(b'\0');
+
+        let max_len = path_offset + mem::size_of::<[libc::c_char; 108]>();
+        if resolved.len() > max_len {
+            return Err(IoError::from_raw_os_error(libc::ENAMETOOLONG));
+        }
+
+        Ok(resolved)
+    }
+}
+
+/// Intercepted syscall type.
+#[derive(Copy, Clone)]
+enum RequestKind {
+    Connect,
+    Bind,
+}
+
+/// Seccomp notification response.
+enum Response {
+    /// Syscall successful.
+    Success,
+    /// Syscall failed with an errno.
+    Error(libc::c_int),
+    /// Response will be sent asynchronously.
+    Deferred,
+}
+
+impl Response {
+    /// Send the response to the sandboxee.
+    fn send(&self, listener: &OwnedFd, id: u64) -> io::Result<()> {
+        let error = match self {
+            Self::Success => 0,
+            Self::Error(errno) => -errno,
+            Self::Deferred => return Ok(()),
+        };
+
+        let mut response = libc::seccomp_notif_resp { id, val: 0, error, flags: 0 };
+        let result = unsafe {
+            libc::ioctl(listener.as_raw_fd(), libc::SECCOMP_IOCTL_NOTIF_SEND as _, &mut response)
+        };
+
+        match result {
+            -1 => Err(IoError::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<io::Result<()>> for Response {
+    fn from(result: io::Result<()>) -> Self {
+        match result {
+            Ok(()) => Self::Success,
+            Err(err) => Self::from(err),
+        }
+    }
+}
+
+impl From<IoError> for Response {
+    fn from(err: IoError) -> Self {
+        Self::Error(err.raw_os_error().unwrap_or(libc::EPERM))
+    }
+}
+
+/// Receive the next seccomp user notification.
+pub fn recv_notification(listener: &OwnedFd) -> io::Result<libc::seccomp_notif> {
+    let mut notif: libc::seccomp_notif = unsafe { mem::zeroed() };
+    let result = unsafe {
+        libc::ioctl(listener.as_raw_fd(), libc::SECCOMP_IOCTL_NOTIF_RECV as _, &mut notif)
+    };
+
+    match result {
+        -1 => Err(IoError::last_os_error()),
+        _ => Ok(notif),
+    }
+}
+
+/// Check if the sandboxee is still waiting for a notification response.
+pub fn notification_valid(listener: &OwnedFd, id: u64) -> bool {
+    let result =
+        unsafe { libc::ioctl(listener.as_raw_fd(), libc::SECCOMP_IOCTL_NOTIF_ID_VALID as _, &id) };
+    result == 0
+}
+
+/// Parse an IPv4 or IPv6 socket address.
+///
+/// Returns `None` for other address families.
+fn parse_inet_addr(addr: &[u8]) -> io::Result<Option<SocketAddr>> {
+    let family = match sockaddr_family(addr) {
+        Some(family) => family as libc::c_int,
+        None => return Ok(None),
+    };
+
+    match family {
+        libc::AF_INET if addr.len() >= mem::size_of::<libc::sockaddr_in>() => {
+            let addr: libc::sockaddr_in = unsafe { ptr::read_unaligned(addr.as_ptr() as _) };
+            let ip = Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr));
+            let port = u16::from_be(addr.sin_port);
+            Ok(Some(SocketAddr::V4(SocketAddrV4::new(ip, port))))
+        },
+        libc::AF_INET6 if addr.len() >= mem::size_of::<libc::sockaddr_in6>() => {
+            let addr: libc::sockaddr_in6 = unsafe { ptr::read_unaligned(addr.as_ptr() as _) };
+            let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+            let port = u16::from_be(addr.sin6_port);
+            let flowinfo = addr.sin6_flowinfo;
+            Ok(Some(SocketAddr::V6(SocketAddrV6::new(ip, port, flowinfo, addr.sin6_scope_id))))
+        },
+        // Reject truncated internet addresses, rather than forwarding them.
+        libc::AF_INET | libc::AF_INET6 => Err(IoError::from_raw_os_error(libc::EINVAL)),
+        _ => Ok(None),
+    }
+}
+
+/// Get the path of a pathname UNIX socket address.
+fn unix_path(addr: &[u8]) -> Option<&[u8]> {
+    if sockaddr_family(addr) != Some(libc::AF_UNIX as libc::sa_family_t) {
+        return None;
+    }
+
+    // Abstract and unnamed sockets have no path.
+    let path = addr.get(mem::size_of::<libc::sa_family_t>()..)?;
+    let path = path.split(|byte| *byte == b'\0').next()?;
+    (!path.is_empty()).then_some(path)
+}
+
+/// Create a UNIX socket address for a path.
+fn unix_addr(path: &Path) -> Vec<u8> {
+    let mut addr = (libc::AF_UNIX as libc::sa_family_t).to_ne_bytes().to_vec();
+    addr.extend_from_slice(path.as_os_str().as_bytes());
+    addr.push(b'\0');
+    addr
+}
+
+/// Get the address family of a raw socket address.
+fn sockaddr_family(addr: &[u8]) -> Option<libc::sa_family_t> {
+    let family = addr.get(..mem::size_of::<libc::sa_family_t>())?;
+    Some(libc::sa_family_t::from_ne_bytes(family.try_into().ok()?))
+}
+
+/// Convert IPv4-mapped IPv6 addresses to IPv4.
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ipv6) => match ipv6.to_ipv4_mapped() {
+            Some(ipv4) => IpAddr::V4(ipv4),
+            None => ip,
+        },
+        ip => ip,
+    }
+}
+
+/// Get an integer socket option.
+fn socket_option(
+    socket: &OwnedFd,
+    level: libc::c_int,
+    name: libc::c_int,
+) -> io::Result<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(socket.as_raw_fd(), level, name, &mut value as *mut _ as _, &mut len)
+    };
+
+    match result {
+        0 => Ok(value),
+        _ => Err(IoError::last_os_error()),
+    }
+}
+
+/// Set an integer socket option.
+fn set_socket_option(
+    socket: &OwnedFd,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: libc::c_int,
+) -> io::Result<()> {
+    let len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let result =
+        unsafe { libc::setsockopt(socket.as_raw_fd(), level, name, &value as *const _ as _, len) };
+
+    match result {
+        0 => Ok(()),
+        _ => Err(IoError::last_os_error()),
+    }
+}
+
+/// Check if a sandboxee's file descriptor has the close-on-exec flag set.
+fn fd_cloexec(tid: u32, fd: RawFd) -> io::Result<bool> {
+    let fdinfo = fs::read_to_string(format!("/proc/{tid}/fdinfo/{fd}"))?;
+    let flags = fdinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|flags| libc::c_int::from_str_radix(flags.trim(), 8).ok())
+        .ok_or_else(|| IoError::from_raw_os_error(libc::EINVAL))?;
+    Ok(flags & libc::O_CLOEXEC != 0)
+}
+
+/// Get a PID file descriptor for a sandboxee's thread.
+fn pidfd_open(tid: u32) -> io::Result<OwnedFd> {
+    let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, tid, PIDFD_THREAD) };
+    if pidfd >= 0 {
+        return Ok(unsafe { OwnedFd::from_raw_fd(pidfd as RawFd) });
+    }
+
+    // Fall back to the thread group leader on kernels without `PIDFD_THREAD`.
+    let status = fs::read_to_string(format!("/proc/{tid}/status"))?;
+    let tgid = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Tgid:"))
+        .and_then(|tgid| tgid.trim().parse::<libc::pid_t>().ok())
+        .ok_or_else(|| IoError::from_raw_os_error(libc::ESRCH))?;
+
+    let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, tgid, 0) };
+    if pidfd >= 0 {
+        Ok(unsafe { OwnedFd::from_raw_fd(pidfd as RawFd) })
+    } else {
+        Err(IoError::last_os_error())
+    }
+}
+
+/// Duplicate a file descriptor from another process.
+fn pidfd_getfd(pidfd: &OwnedFd, fd: RawFd) -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_getfd, pidfd.as_raw_fd(), fd, 0) };
+    if fd >= 0 {
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+    } else {
+        Err(IoError::last_os_error())
+    }
+}
+
+/// Create a pair of connected UNIX sockets for passing file descriptors.
+pub fn socket_pair() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut sockets = [0; 2];
+    let result = unsafe {
+        libc::socketpair(
+            libc::AF_UNIX,
+            libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC,
+            0,
+            sockets.as_mut_ptr(),
+        )
+    };
+    if result != 0 {
+        return Err(IoError::last_os_error());
+    }
+
+    unsafe { Ok((OwnedFd::from_raw_fd(sockets[0]), OwnedFd::from_raw_fd(sockets[1]))) }
+}
+
+/// Send a file descriptor over a UNIX socket.
+pub fn send_fd(socket: &OwnedFd, fd: RawFd) -> io::Result<()> {
+    let mut payload = [0u8; 1];
+    let mut iov = libc::iovec { iov_base: payload.as_mut_ptr() as _, iov_len: payload.len() };
+
+    let mut control = [0u8; 64];
+    let control_len = unsafe { libc::CMSG_SPACE(mem::size_of::<RawFd>() as u32) } as usize;
+
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as _;
+    msg.msg_controllen = control_len as _;
+
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(mem::size_of::<RawFd>() as u32) as _;
+        ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut RawFd, fd);
+    }
+
+    let result = unsafe { libc::sendmsg(socket.as_raw_fd(), &msg, 0) };
+    match result {
+        -1 => Err(IoError::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Receive a file descriptor over a UNIX socket.
+pub fn recv_fd(socket: &OwnedFd) -> io::Result<OwnedFd> {
+    let mut payload = [0u8; 1];
+    let mut iov = libc::iovec { iov_base: payload.as_mut_ptr() as _, iov_len: payload.len() };
+
+    let mut control = [0u8; 64];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as _;
+    msg.msg_controllen = control.len() as _;
+
+    let result = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC) };
+    if result == -1 {
+        return Err(IoError::last_os_error());
+    }
+
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        if cmsg.is_null()
+            || (*cmsg).cmsg_level != libc::SOL_SOCKET
+            || (*cmsg).cmsg_type != libc::SCM_RIGHTS
+        {
+            return Err(IoError::from_raw_os_error(libc::EBADMSG));
+        }
+
+        let fd = ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const RawFd);
+        Ok(OwnedFd::from_raw_fd(fd))
+    }
+}
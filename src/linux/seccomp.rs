@@ -1,19 +1,29 @@
 //! Seccomp system call filtering.
 
 use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::os::fd::{FromRawFd, OwnedFd, RawFd};
+use std::sync::OnceLock;
 
 use seccompiler::{
     BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter,
     SeccompRule, TargetArch,
 };
 
-use crate::Result;
+use crate::error::{Error, Result};
+use crate::linux::{SeccompAction as SyscallAction, Syscall};
 
 #[cfg(target_arch = "x86_64")]
 const ARCH: TargetArch = TargetArch::x86_64;
 #[cfg(target_arch = "aarch64")]
 const ARCH: TargetArch = TargetArch::aarch64;
 
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc00000b7;
+
 /// Bitmask for the clone syscall seccomp filter.
 ///
 /// A 1 in the bitmask means system calls with this flag set will be denied.
@@ -28,16 +38,85 @@ const ARCH: TargetArch = TargetArch::aarch64;
 ///  - CLONE_NEWNET    = 0x40000000
 const CLONE_NAMESPACE_FILTER: u32 = 0b01111110000000100000000000000000;
 
+/// Largest errno which can be returned by a seccomp filter.
+const MAX_ERRNO: i32 = 4095;
+
 /// Seccomp system call filter.
 ///
 /// This filter is aimed at restricting system calls which shouldn't be
 /// executable by an untrusted client.
-#[derive(Default)]
-pub struct SyscallFilter;
+#[derive(Default, Clone)]
+pub struct SyscallFilter {
+    actions: BTreeMap<Syscall, SyscallAction>,
+    audit: bool,
+    disabled: bool,
+}
 
 impl SyscallFilter {
+    /// Override the action for a syscall.
+    pub fn set(&mut self, syscall: Syscall, action: SyscallAction) {
+        self.actions.insert(syscall, action);
+    }
+
+    /// Ensure a custom rule doesn't conflict with the sandbox's own rules.
+    pub fn check_rule(syscall: Syscall, action: SyscallAction) -> Result<()> {
+        let nr = syscall.as_raw();
+        match action {
+            SyscallAction::Allow if NAMESPACE_SYSCALLS.contains(&nr) => {
+                Err(Error::ActivationFailed(format!(
+                    "syscall {nr} cannot be allowed, since it could be used to leave the \
+                     sandbox's namespaces"
+                )))
+            },
+            SyscallAction::ErrnoReturn(_) | SyscallAction::Kill
+                if REQUIRED_SYSCALLS.contains(&nr) =>
+            {
+                Err(Error::ActivationFailed(format!(
+                    "syscall {nr} cannot be denied, since it is required by the sandbox"
+                )))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Log denied syscalls instead of blocking them.
+    pub fn set_audit(&mut self, audit: bool) {
+        self.audit = audit;
+    }
+
+    /// Skip the filter entirely, since seccomp is unavailable.
+    pub fn disable(&mut self) {
+        self.disabled = true;
+    }
+
+    /// Check if any syscall actions were overridden.
+    pub fn has_custom_actions(&self) -> bool {
+        !self.actions.is_empty()
+    }
+
+    /// Ensure all actions can be enforced.
+    pub fn validate(&self) -> Result<()> {
+        for action in self.actions.values() {
+            if let SyscallAction::ErrnoReturn(errno) = action {
+                if !(0..=MAX_ERRNO).contains(errno) {
+                    return Err(Error::ActivationFailed(format!(
+                        "syscall errno must be between 0 and {MAX_ERRNO}, got {errno}"
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Apply the seccomp filter.
-    pub fn apply() -> Result<()> {
+    pub fn apply(&self) -> Result<()> {
+        if self.disabled {
+            return Ok(());
+        }
+
+        event!(target: "birdcage::linux", "installing seccomp filter: {}", self.summary());
+
         let mut rules = BTreeMap::new();
 
         // Add exceptions for allowed syscalls.
@@ -55,11 +134,20 @@ impl SyscallFilter {
         let clone_rule = SeccompRule::new(vec![allow_clone])?;
         rules.insert(libc::SYS_clone, vec![clone_rule]);
 
+        // Add custom exceptions, without overriding the `clone` restrictions.
+        for (syscall, action) in &self.actions {
+            if *action == SyscallAction::Allow {
+                rules.entry(syscall.as_raw()).or_default();
+            }
+        }
+
         // Apply seccomp filter.
+        let denied =
+            if self.audit { SeccompAction::Log } else { SeccompAction::Errno(libc::EACCES as u32) };
         let filter = SeccompFilter::new(
             rules,
             // Action performed if no rule matches.
-            SeccompAction::Errno(libc::EACCES as u32),
+            denied,
             // Action performed if any rule matches.
             SeccompAction::Allow,
             ARCH,
@@ -81,10 +169,422 @@ impl SyscallFilter {
         let program: BpfProgram = filter.try_into()?;
         seccompiler::apply_filter(&program)?;
 
+        // Group denied syscalls by their errno, with `None` killing the process.
+        let mut denied: BTreeMap<Option<u32>, BTreeMap<_, _>> = BTreeMap::new();
+        for (syscall, action) in &self.actions {
+            let errno = match action {
+                SyscallAction::Allow => continue,
+                SyscallAction::ErrnoReturn(errno) => Some(*errno as u32),
+                SyscallAction::Kill => None,
+            };
+            denied.entry(errno).or_default().insert(syscall.as_raw(), Vec::new());
+        }
+
+        // Apply custom denials, taking precedence over the allowed syscalls.
+        for (errno, rules) in denied {
+            let action = match errno {
+                _ if self.audit => SeccompAction::Log,
+                Some(errno) => SeccompAction::Errno(errno),
+                None => SeccompAction::KillProcess,
+            };
+            let filter = SeccompFilter::new(
+                rules,
+                // Action performed if no rule matches.
+                SeccompAction::Allow,
+                // Action performed if any rule matches.
+                action,
+                ARCH,
+            )?;
+            let program: BpfProgram = filter.try_into()?;
+            seccompiler::apply_filter(&program)?;
+        }
+
         Ok(())
     }
+
+    /// Describe the applied seccomp filter.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} syscalls allowed, namespace creation denied, clone3 returns ENOSYS, all other \
+             syscalls return EACCES",
+            SYSCALL_WHITELIST.len() + 1,
+        );
+
+        if self.audit {
+            summary.push_str(", denials are logged instead of enforced");
+        }
+
+        for (syscall, action) in &self.actions {
+            let nr = syscall.as_raw();
+            let _ = match action {
+                SyscallAction::Allow => write!(summary, ", syscall {nr} allowed"),
+                SyscallAction::ErrnoReturn(errno) => {
+                    write!(summary, ", syscall {nr} returns errno {errno}")
+                },
+                SyscallAction::Kill => write!(summary, ", syscall {nr} kills the process"),
+            };
+        }
+
+        summary
+    }
 }
 
+/// Seccomp filter preventing the creation of new processes.
+///
+/// Threads can still be created, since `clone` is only denied without
+/// `CLONE_THREAD`. The `clone3` syscall is already unavailable due to the
+/// [`SyscallFilter`].
+pub struct ProcessFilter;
+
+impl ProcessFilter {
+    /// Compile the seccomp filter.
+    ///
+    /// Since [`seccompiler::apply_filter`] is async-signal-safe, the program
+    /// can be applied after `fork`.
+    pub fn program(audit: bool) -> Result<BpfProgram> {
+        let no_thread = SeccompCondition::new(
+            0,
+            SeccompCmpArgLen::Qword,
+            SeccompCmpOp::MaskedEq(libc::CLONE_THREAD as u64),
+            0,
+        )?;
+
+        let mut rules = BTreeMap::new();
+        rules.insert(libc::SYS_clone, vec![SeccompRule::new(vec![no_thread])?]);
+        #[cfg(target_arch = "x86_64")]
+        {
+            rules.insert(libc::SYS_fork, Vec::new());
+            rules.insert(libc::SYS_vfork, Vec::new());
+        }
+
+        let denied =
+            if audit { SeccompAction::Log } else { SeccompAction::Errno(libc::EACCES as u32) };
+        let filter = SeccompFilter::new(
+            rules,
+            // Action performed if no rule matches.
+            SeccompAction::Allow,
+            // Action performed if any rule matches.
+            denied,
+            ARCH,
+        )?;
+
+        Ok(filter.try_into()?)
+    }
+}
+
+/// Mask for the socket type, excluding `SOCK_NONBLOCK` and `SOCK_CLOEXEC`.
+const SOCK_TYPE_MASK: u64 = 0xf;
+
+/// Seccomp filter restricting internet protocols.
+///
+/// This only applies to `AF_INET` and `AF_INET6` sockets. Since sockets
+/// created by `io_uring` bypass seccomp, `io_uring` is disabled entirely.
+#[derive(Copy, Clone, Default)]
+pub struct ProtocolFilter {
+    pub tcp: bool,
+    pub udp: bool,
+}
+
+impl ProtocolFilter {
+    /// Apply the seccomp filter.
+    pub fn apply(&self) -> Result<()> {
+        event!(target: "birdcage::linux", "installing protocol filter: {}", self.summary());
+
+        let mut socket_rules = Vec::new();
+        for domain in [libc::AF_INET, libc::AF_INET6] {
+            let socket_rule = |socket_type: libc::c_int, protocol: Option<libc::c_int>| {
+                let mut conditions = vec![
+                    SeccompCondition::new(
+                        0,
+                        SeccompCmpArgLen::Dword,
+                        SeccompCmpOp::Eq,
+                        domain as u64,
+                    )?,
+                    SeccompCondition::new(
+                        1,
+                        SeccompCmpArgLen::Dword,
+                        SeccompCmpOp::MaskedEq(SOCK_TYPE_MASK),
+                        socket_type as u64,
+                    )?,
+                ];
+                if let Some(protocol) = protocol {
+                    conditions.push(SeccompCondition::new(
+                        2,
+                        SeccompCmpArgLen::Dword,
+                        SeccompCmpOp::Eq,
+                        protocol as u64,
+                    )?);
+                }
+                SeccompRule::new(conditions)
+            };
+
+            // Deny all socket types which are neither TCP nor UDP.
+            for socket_type in
+                [libc::SOCK_RAW, libc::SOCK_RDM, libc::SOCK_SEQPACKET, libc::SOCK_DCCP]
+            {
+                socket_rules.push(socket_rule(socket_type, None)?);
+            }
+
+            // Deny other protocols using stream/datagram sockets.
+            if self.tcp {
+                socket_rules.push(socket_rule(libc::SOCK_STREAM, Some(libc::IPPROTO_SCTP))?);
+            } else {
+                socket_rules.push(socket_rule(libc::SOCK_STREAM, None)?);
+            }
+            if self.udp {
+                socket_rules.push(socket_rule(libc::SOCK_DGRAM, Some(libc::IPPROTO_ICMP))?);
+                socket_rules.push(socket_rule(libc::SOCK_DGRAM, Some(libc::IPPROTO_ICMPV6))?);
+            } else {
+                socket_rules.push(socket_rule(libc::SOCK_DGRAM, None)?);
+            }
+        }
+
+        let mut rules = BTreeMap::new();
+        rules.insert(libc::SYS_socket, socket_rules);
+        let filter = SeccompFilter::new(
+            rules,
+            // Action performed if no rule matches.
+            SeccompAction::Allow,
+            // Action performed if any rule matches.
+            SeccompAction::Errno(libc::EACCES as u32),
+            ARCH,
+        )?;
+        let program: BpfProgram = filter.try_into()?;
+        seccompiler::apply_filter(&program)?;
+
+        // Change `io_uring_setup` syscall error to "not implemented".
+        let mut rules = BTreeMap::new();
+        rules.insert(libc::SYS_io_uring_setup, Vec::new());
+        let filter = SeccompFilter::new(
+            rules,
+            // Action performed if no rule matches.
+            SeccompAction::Allow,
+            // Action performed if any rule matches.
+            SeccompAction::Errno(libc::ENOSYS as u32),
+            ARCH,
+        )?;
+        let program: BpfProgram = filter.try_into()?;
+        seccompiler::apply_filter(&program)?;
+
+        Ok(())
+    }
+
+    /// Describe the allowed protocols.
+    pub fn summary(&self) -> &'static str {
+        match (self.tcp, self.udp) {
+            (true, true) => "tcp and udp only",
+            (true, false) => "tcp only",
+            (false, true) => "udp only",
+            (false, false) => "no internet protocols",
+        }
+    }
+}
+
+/// Seccomp filter forwarding network syscalls to a supervisor.
+///
+/// The `connect` and `bind` syscalls are sent to the listener, while
+/// alternative ways of establishing connections like TCP fast open and
+/// `io_uring` are denied.
+pub struct NetworkFilter;
+
+impl NetworkFilter {
+    /// Apply the seccomp filter.
+    ///
+    /// Returns the seccomp user notification listener.
+    pub fn apply() -> io::Result<OwnedFd> {
+        // Offsets into `struct seccomp_data`.
+        const NR: u32 = 0;
+        const ARCH: u32 = 4;
+        const ARG2: u32 = 16 + 2 * 8;
+        const ARG3: u32 = 16 + 3 * 8;
+
+        let ld = |offset| bpf_stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset);
+        let jeq =
+            |value, jt, jf| bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, value, jt, jf);
+        let jset =
+            |value, jt, jf| bpf_jump(libc::BPF_JMP | libc::BPF_JSET | libc::BPF_K, value, jt, jf);
+        let ret = |value| bpf_stmt(libc::BPF_RET | libc::BPF_K, value);
+        let fastopen = libc::MSG_FASTOPEN as u32;
+
+        let filter = [
+            // 0
+            ld(ARCH),
+            // 1
+            jeq(AUDIT_ARCH, 0, 14),
+            // 2
+            ld(NR),
+            // 3
+            jeq(libc::SYS_connect as u32, 9, 0),
+            // 4
+            jeq(libc::SYS_bind as u32, 8, 0),
+            // 5
+            jeq(libc::SYS_io_uring_setup as u32, 8, 0),
+            // 6
+            jeq(libc::SYS_sendto as u32, 2, 0),
+            // 7
+            jeq(libc::SYS_sendmmsg as u32, 1, 0),
+            // 8
+            jeq(libc::SYS_sendmsg as u32, 2, 7),
+            // Flags for `sendto` and `sendmmsg`.
+            // 9
+            ld(ARG3),
+            // 10
+            jset(fastopen, 4, 5),
+            // Flags for `sendmsg`.
+            // 11
+            ld(ARG2),
+            // 12
+            jset(fastopen, 2, 3),
+            // 13
+            ret(libc::SECCOMP_RET_USER_NOTIF),
+            // 14
+            ret(libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+            // 15
+            ret(libc::SECCOMP_RET_ERRNO | libc::EOPNOTSUPP as u32),
+            // 16
+            ret(libc::SECCOMP_RET_ALLOW),
+        ];
+
+        apply_listener_filter(&filter)
+    }
+}
+
+/// Syscalls reported to the auditor in audit mode.
+const AUDITED_SYSCALLS: &[libc::c_long] = &[
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_open,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_creat,
+    libc::SYS_openat,
+    libc::SYS_openat2,
+    libc::SYS_execve,
+    libc::SYS_execveat,
+];
+
+/// Seccomp filter sending file accesses to an auditor.
+pub struct AuditFilter;
+
+impl AuditFilter {
+    /// Apply the seccomp filter.
+    ///
+    /// Returns the seccomp user notification listener.
+    pub fn apply() -> io::Result<OwnedFd> {
+        // Offsets into `struct seccomp_data`.
+        const NR: u32 = 0;
+        const ARCH: u32 = 4;
+
+        let ld = |offset| bpf_stmt(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, offset);
+        let jeq =
+            |value, jt, jf| bpf_jump(libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K, value, jt, jf);
+        let ret = |value| bpf_stmt(libc::BPF_RET | libc::BPF_K, value);
+
+        // Jump to the notification for audited syscalls, allowing everything else.
+        let count = AUDITED_SYSCALLS.len();
+        let mut filter = vec![ld(ARCH), jeq(AUDIT_ARCH, 0, count as u8 + 1), ld(NR)];
+        for (i, syscall) in AUDITED_SYSCALLS.iter().enumerate() {
+            filter.push(jeq(*syscall as u32, (count - i) as u8, 0));
+        }
+        filter.push(ret(libc::SECCOMP_RET_ALLOW));
+        filter.push(ret(libc::SECCOMP_RET_USER_NOTIF));
+
+        apply_listener_filter(&filter)
+    }
+}
+
+/// Check if seccomp filters can be installed.
+///
+/// Seccomp is commonly unavailable inside containers which already have a
+/// seccomp filter denying the `seccomp` syscall. The check installs a filter
+/// allowing all syscalls in a forked child, the result is cached for the
+/// lifetime of the process.
+pub fn is_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let filter = [bpf_stmt(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_ALLOW)];
+        let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_ptr() as _ };
+
+        unsafe {
+            match libc::fork() {
+                -1 => false,
+                0 => {
+                    let nnp = libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0);
+                    let result = libc::syscall(
+                        libc::SYS_seccomp,
+                        libc::SECCOMP_SET_MODE_FILTER,
+                        0,
+                        &program,
+                    );
+                    libc::_exit((nnp != 0 || result != 0) as libc::c_int);
+                },
+                pid => {
+                    let mut status = 0;
+                    libc::waitpid(pid, &mut status, 0) == pid
+                        && libc::WIFEXITED(status)
+                        && libc::WEXITSTATUS(status) == 0
+                },
+            }
+        }
+    })
+}
+
+/// Apply a seccomp filter with a user notification listener.
+fn apply_listener_filter(filter: &[libc::sock_filter]) -> io::Result<OwnedFd> {
+    let program = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_ptr() as _ };
+    let listener = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+            &program,
+        )
+    };
+
+    if listener < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(unsafe { OwnedFd::from_raw_fd(listener as RawFd) })
+    }
+}
+
+/// Create a BPF statement.
+fn bpf_stmt(code: u32, k: u32) -> libc::sock_filter {
+    libc::sock_filter { code: code as u16, jt: 0, jf: 0, k }
+}
+
+/// Create a BPF jump.
+fn bpf_jump(code: u32, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter { code: code as u16, jt, jf, k }
+}
+
+/// Syscalls required by the sandbox's PID 1 to launch and reap the sandboxee.
+const REQUIRED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_close,
+    libc::SYS_close_range,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_dup2,
+    libc::SYS_dup3,
+    libc::SYS_fcntl,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_clone,
+    libc::SYS_execve,
+    libc::SYS_wait4,
+    libc::SYS_kill,
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_prctl,
+    libc::SYS_prlimit64,
+    libc::SYS_seccomp,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+];
+
+/// Syscalls which could be used to leave the sandbox's namespaces.
+const NAMESPACE_SYSCALLS: &[libc::c_long] = &[libc::SYS_unshare, libc::SYS_setns, libc::SYS_clone3];
+
 /// Unconditionally allowed syscalls for networking.
 const SYSCALL_WHITELIST: &[libc::c_long] = &[
     libc::SYS_read,
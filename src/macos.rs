@@ -1,17 +1,27 @@
-//! Linux sandboxing.
+//! macOS sandboxing.
 //!
 //! This module implements sandboxing on macOS using `sandbox_init`.
 
 use std::collections::HashMap;
-use std::ffi::{CStr, CString};
-use std::io::{Result as IoResult, Write};
+use std::ffi::{CStr, CString, OsStr};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, Write};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
-use std::{fs, ptr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{env, fs, ptr};
 
 use bitflags::bitflags;
 
 use crate::error::{Error, Result};
-use crate::{Child, Command, Exception, Sandbox};
+use crate::{
+    Capabilities, Child, Command, DeviceKind, Exception, ResourceLimits, Sandbox, SandboxWarning,
+    SupportedExceptions,
+};
+
+/// Whether the current process was sandboxed.
+static LOCKED: AtomicBool = AtomicBool::new(false);
 
 /// Deny-all fallback rule.
 static DEFAULT_RULE: &[u8] = b"\
@@ -29,14 +39,43 @@ static DEFAULT_RULE: &[u8] = b"\
 (system-network)
 ";
 
+/// Mechanism used to enforce the sandbox on macOS.
+///
+/// See `MacSandbox::backend_kind`.
+#[non_exhaustive]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MacBackend {
+    /// Seatbelt profiles applied with `sandbox_init`.
+    ///
+    /// While `sandbox_init` with custom profiles is deprecated and
+    /// undocumented, it is still enforced by the kernel on all macOS versions
+    /// and used by Apple's own `sandbox-exec`.
+    Seatbelt,
+}
+
 /// macOS sandboxing based on Seatbelt.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct MacSandbox {
     path_exceptions: HashMap<String, PathException>,
+    denied_paths: Vec<String>,
     env_exceptions: Vec<String>,
+    env_prefixes: Vec<String>,
+    env_patterns: Vec<String>,
     custom_env: Option<HashMap<String, String>>,
+    set_env: HashMap<String, String>,
+    network_rules: Vec<String>,
+    unix_sockets: Vec<String>,
+    network_protocols: Option<(bool, bool)>,
+    resource_limits: ResourceLimits,
+    exceptions: Vec<Exception>,
     net_exception: bool,
+    net_outbound: bool,
+    net_inbound: bool,
     full_env: bool,
+    proc_self: bool,
+    deny_subprocesses: bool,
+    restrict_symlink_escape: bool,
+    base_dir: Option<PathBuf>,
 }
 
 impl Sandbox for MacSandbox {
@@ -45,37 +84,212 @@ impl Sandbox for MacSandbox {
     }
 
     fn add_exception(&mut self, exception: Exception) -> Result<&mut Self> {
+        let exception = crate::resolve_relative_paths(exception, self.base_dir.as_deref());
+        let recorded = exception.clone();
+
         match exception {
             Exception::Read(path) => self.update_path_exceptions(path, PathException::READ)?,
+            Exception::ReadFile(path) => {
+                let metadata =
+                    path.metadata().map_err(|err| Error::from_path(path.clone(), &err))?;
+                if !metadata.is_file() {
+                    return Err(Error::InvalidPath(path));
+                }
+                self.update_path_exceptions(path, PathException::READ)?;
+            },
+            Exception::ReadExact(path) => {
+                self.update_path_exceptions(path, PathException::EXACT)?
+            },
+            Exception::ReadGlob(pattern) => {
+                for path in crate::glob::expand(&pattern)? {
+                    self.update_path_exceptions(path, PathException::READ)?;
+                }
+            },
+            Exception::ReadShallow(path) => {
+                for entry in crate::directory_entries(&path)? {
+                    match self.update_path_exceptions(entry, PathException::EXACT) {
+                        // Broken symlinks, escaping symlinks and denied entries are skipped.
+                        Ok(())
+                        | Err(
+                            Error::PathNotFound(_)
+                            | Error::SymlinkEscape(_)
+                            | Error::ConflictingPath(_),
+                        ) => (),
+                        Err(err) => return Err(err),
+                    }
+                }
+                self.update_path_exceptions(path, PathException::SHALLOW)?;
+            },
+            Exception::ReadDir(path) => {
+                let metadata =
+                    path.metadata().map_err(|err| Error::from_path(path.clone(), &err))?;
+                if !metadata.is_dir() {
+                    return Err(Error::InvalidPath(path));
+                }
+                self.update_path_exceptions(path, PathException::LIST)?;
+            },
             Exception::WriteAndRead(path) => {
                 self.update_path_exceptions(path, PathException::WRITE | PathException::READ)?
             },
             Exception::ExecuteAndRead(path) => {
                 self.update_path_exceptions(path, PathException::EXECUTE | PathException::READ)?
             },
+            // Seatbelt cannot distinguish truncation from other writes.
+            Exception::AppendOnly(_) => {
+                return Err(Error::UnsupportedException(
+                    "append-only exceptions are not supported on macOS".into(),
+                ));
+            },
+            Exception::Deny(path) => {
+                let escaped_path = escape_path(&path)?;
+                if self.path_exceptions.contains_key(&escaped_path) {
+                    return Err(Error::ConflictingPath(path));
+                }
+                self.denied_paths.push(escaped_path);
+            },
             Exception::Networking => self.net_exception = true,
-            Exception::Environment(key) => {
-                self.env_exceptions.push(key);
-                return Ok(self);
+            Exception::NetworkOutbound => self.net_outbound = true,
+            Exception::NetworkInbound => self.net_inbound = true,
+            Exception::UnixSocket(path) => {
+                let metadata =
+                    path.metadata().map_err(|err| Error::from_path(path.clone(), &err))?;
+                if !metadata.file_type().is_socket() {
+                    return Err(Error::InvalidPath(path));
+                }
+                self.unix_sockets.push(escape_path(&path)?);
+            },
+            Exception::NetworkProtocol { tcp, udp } => {
+                let protocols = self.network_protocols.get_or_insert((false, false));
+                protocols.0 |= tcp;
+                protocols.1 |= udp;
+            },
+            // Shared memory is allowed by the default rule.
+            Exception::SharedMemory(name) | Exception::SharedMemoryCreate(name) => {
+                crate::shm_path(&name)?;
+            },
+            // Capabilities do not exist on macOS.
+            Exception::DropCapabilities(_) | Exception::DropAllCapabilities => (),
+            Exception::ResourceLimits(limits) => {
+                limits.validate()?;
+                self.resource_limits.merge(limits);
             },
-            Exception::FullEnvironment => {
-                self.full_env = true;
-                return Ok(self);
+            Exception::NetworkConnect(addr) => {
+                let host = network_host(addr)?;
+                self.network_rules.push(format!("(allow network-outbound (remote tcp {host}))"));
             },
+            Exception::NetworkBind(addr) => {
+                let host = network_host(addr)?;
+                self.network_rules.push(format!("(allow network-bind (local tcp {host}))"));
+                self.network_rules.push(format!("(allow network-inbound (local tcp {host}))"));
+            },
+            Exception::NetworkSubnet { addr, prefix_len } => {
+                crate::validate_subnet(addr, prefix_len)?;
+
+                // Seatbelt cannot match subnets, so only loopback is supported.
+                let min_len = if addr.is_ipv4() { 8 } else { 128 };
+                if prefix_len < min_len {
+                    return Err(Error::UnsupportedException(format!(
+                        "network exceptions for {addr}/{prefix_len} are not supported on macOS, \
+                         only loopback addresses can be allowed"
+                    )));
+                }
+
+                let host = network_host(SocketAddr::new(addr, 0))?;
+                self.network_rules.push(format!("(allow network-outbound (remote tcp {host}))"));
+            },
+            Exception::NetworkHost { host, port } => {
+                for addr in (host.as_str(), port.unwrap_or(0)).to_socket_addrs()? {
+                    let host = network_host(addr)?;
+                    self.network_rules
+                        .push(format!("(allow network-outbound (remote tcp {host}))"));
+                }
+            },
+            Exception::Environment(key) => self.env_exceptions.push(key),
+            Exception::EnvironmentPrefix(prefix) => self.env_prefixes.push(prefix),
+            Exception::EnvironmentPattern(pattern) => self.env_patterns.push(pattern),
+            Exception::FullEnvironment => self.full_env = true,
+            Exception::ProcSelf => self.proc_self = true,
             Exception::CustomEnvironment(env_map) => {
+                crate::validate_env_variables(&env_map)?;
                 self.custom_env = Some(env_map);
-                return Ok(self);
+            },
+            Exception::SetEnvironment(env_map) => {
+                crate::validate_env_variables(&env_map)?;
+                self.set_env.extend(env_map);
+            },
+            Exception::Device(DeviceKind::Dri) => {
+                return Err(Error::UnsupportedException("DRI devices require Linux".into()));
+            },
+            Exception::Device(device) => {
+                device.validate()?;
+                let mut exceptions = PathException::READ;
+                if device.is_writable() {
+                    exceptions |= PathException::WRITE;
+                }
+                self.update_path_exceptions(device.path().to_path_buf(), exceptions)?;
             },
         }
+
+        crate::record_exception(&mut self.exceptions, recorded);
+
         Ok(self)
     }
 
     fn spawn(self, mut sandboxee: Command) -> Result<Child> {
-        // Remove/replace environment variables.
+        self.validate()?;
+        self.check_current_dir(&sandboxee)?;
+
+        // The current process must be able to fork the sandboxee.
+        if self.deny_subprocesses {
+            return Err(Error::ActivationFailed(
+                "denying subprocesses is not supported by `Sandbox::spawn`".into(),
+            ));
+        }
+        self.restrict_current_process()?;
+
+        // Restrict sandboxee's resource usage.
+        let resource_limits = self.resource_limits;
+        unsafe { sandboxee.pre_exec(move || resource_limits.apply()) };
+
+        event!(target: "birdcage::macos", "executing {:?}", sandboxee.get_program());
+        Ok(sandboxee.spawn()?)
+    }
+
+    fn spawn_isolated(self, mut sandboxee: Command) -> Result<Child> {
+        self.validate()?;
+        self.check_current_dir(&sandboxee)?;
+
+        // Keep variables explicitly set on the command.
+        let explicit_env: Vec<_> = sandboxee
+            .get_envs()
+            .map(|(key, value)| (key.to_os_string(), value.map(OsStr::to_os_string)))
+            .collect();
+
+        // Pass restricted environment variables to the child.
         if let Some(ref custom_env) = self.custom_env {
-            crate::restrict_env_variables_with_custom(&[], Some(custom_env));
+            sandboxee.env_clear().envs(custom_env);
         } else if !self.full_env {
-            crate::restrict_env_variables(&self.env_exceptions);
+            sandboxee.env_clear();
+            for (key, value) in env::vars_os() {
+                let allowed = key.to_str().is_some_and(|key| {
+                    crate::env_allowed(
+                        key,
+                        &self.env_exceptions,
+                        &self.env_prefixes,
+                        &self.env_patterns,
+                    )
+                });
+                if allowed {
+                    sandboxee.env(key, value);
+                }
+            }
+        }
+        sandboxee.envs(&self.set_env);
+        for (key, value) in explicit_env {
+            match value {
+                Some(value) => sandboxee.env(key, value),
+                None => sandboxee.env_remove(key),
+            };
         }
 
         // Create the seatbelt sandbox profile.
@@ -83,28 +297,291 @@ impl Sandbox for MacSandbox {
         let profile =
             CString::new(profile).map_err(|_| Error::ActivationFailed("invalid profile".into()))?;
 
-        let mut error = ptr::null_mut();
-        let result = unsafe { sandbox_init(profile.as_ptr(), 0, &mut error) };
+        // Apply the profile after forking, to leave the current process untouched.
+        let resource_limits = self.resource_limits;
+        unsafe {
+            sandboxee.pre_exec(move || {
+                resource_limits.apply()?;
+                apply_profile(&profile).map_err(|err| IoError::new(IoErrorKind::Other, err))
+            });
+        }
 
-        if result == 0 {
-            Ok(sandboxee.spawn()?)
-        } else {
-            unsafe {
-                let error_text = CStr::from_ptr(error)
-                    .to_str()
-                    .map_err(|_| Error::ActivationFailed("sandbox_init failed".into()))?
-                    .to_owned();
-                sandbox_free_error(error);
-
-                Err(Error::ActivationFailed(error_text))
+        event!(target: "birdcage::macos", "executing {:?}", sandboxee.get_program());
+        Ok(sandboxee.spawn()?)
+    }
+
+    fn lock(self) -> Result<()> {
+        self.validate()?;
+
+        if LOCKED.swap(true, Ordering::SeqCst) {
+            return Err(Error::AlreadyLocked);
+        }
+
+        self.restrict_current_process()?;
+
+        // Restrict resource usage.
+        self.resource_limits.apply()?;
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<Vec<SandboxWarning>> {
+        let mut warnings = Vec::new();
+
+        // Sort by component count to ensure parent paths appear before descendants.
+        let mut path_exceptions: Vec<_> = self
+            .path_exceptions
+            .iter()
+            .map(|(path, exception)| (unescape_path(path), *exception))
+            .collect();
+        path_exceptions.sort_unstable_by_key(|(path, _)| path.components().count());
+
+        for (i, (path, exception)) in path_exceptions.iter().enumerate() {
+            // Check that exception paths were not removed in the meantime.
+            if path.symlink_metadata().is_err() {
+                warnings.push(SandboxWarning::PathDoesNotExist(path.clone()));
+            }
+
+            // Check if access is reduced compared to the closest parent exception.
+            let parent =
+                path_exceptions[..i].iter().rev().find(|(parent, _)| path.starts_with(parent));
+            if let Some((parent, parent_exception)) = parent {
+                let reduced = |flag| parent_exception.contains(flag) && !exception.contains(flag);
+                if reduced(PathException::WRITE) || reduced(PathException::EXECUTE) {
+                    warnings.push(SandboxWarning::RestrictedSubpath {
+                        path: path.clone(),
+                        parent: parent.clone(),
+                    });
+                }
+            }
+
+            // Check that executable files can actually be executed.
+            if exception.contains(PathException::EXECUTE) {
+                let mode = path.metadata().map(|metadata| metadata.permissions().mode());
+                if matches!(mode, Ok(mode) if path.is_file() && mode & 0o111 == 0) {
+                    warnings.push(SandboxWarning::NotExecutable(path.clone()));
+                }
+            }
+        }
+
+        // Check that denied paths would be accessible otherwise.
+        let mut denied_paths: Vec<_> =
+            self.denied_paths.iter().map(|path| unescape_path(path)).collect();
+        denied_paths.sort_unstable();
+        for path in denied_paths {
+            if !path_exceptions.iter().any(|(parent, _)| path.starts_with(parent)) {
+                warnings.push(SandboxWarning::DenyIgnored(path));
             }
         }
+
+        let env_exceptions = !self.env_exceptions.is_empty()
+            || !self.env_prefixes.is_empty()
+            || !self.env_patterns.is_empty();
+        if self.custom_env.is_some() && (self.full_env || env_exceptions) {
+            warnings.push(SandboxWarning::EnvironmentIgnored);
+        }
+
+        let full_network = self.net_exception || self.network_protocols.is_some();
+        let directional = self.net_outbound || self.net_inbound;
+        let has_rules = |kind| self.network_rules.iter().any(|rule| rule.starts_with(kind));
+        if full_network && (!self.network_rules.is_empty() || directional) {
+            warnings.push(SandboxWarning::NetworkExceptionsIgnored);
+        } else if (self.net_outbound && has_rules("(allow network-outbound"))
+            || (self.net_inbound && has_rules("(allow network-bind"))
+        {
+            warnings.push(SandboxWarning::NetworkExceptionsRedundant);
+        }
+
+        warnings.append(&mut crate::loader_env_warnings(&self.exceptions));
+
+        Ok(warnings)
+    }
+
+    fn profile_preview(&self) -> String {
+        let env = crate::env_preview(
+            &self.env_exceptions,
+            &self.env_prefixes,
+            &self.env_patterns,
+            self.full_env,
+            self.custom_env.as_ref(),
+            &self.set_env,
+        );
+        let mut preview = format!(";; environment: {env}\n");
+
+        match self.create_profile() {
+            Ok(profile) => preview.push_str(&String::from_utf8_lossy(&profile)),
+            Err(err) => preview.push_str(&format!(";; invalid profile: {err}\n")),
+        }
+
+        preview
+    }
+
+    fn exceptions(&self) -> &[Exception] {
+        &self.exceptions
+    }
+
+    fn clone_config(&self) -> Self {
+        self.clone()
+    }
+
+    fn allow_subprocesses(&mut self, allow: bool) -> &mut Self {
+        self.deny_subprocesses = !allow;
+        self
+    }
+
+    fn restrict_symlink_escape(&mut self, restrict: bool) -> &mut Self {
+        self.restrict_symlink_escape = restrict;
+        self
+    }
+
+    fn set_base_dir(&mut self, path: PathBuf) -> Result<&mut Self> {
+        self.base_dir = Some(crate::canonical_base_dir(path)?);
+        Ok(self)
+    }
+
+    fn supported_exceptions() -> SupportedExceptions {
+        // Seatbelt only supports filtering by port for loopback addresses.
+        SupportedExceptions {
+            unix_sockets: true,
+            network_direction_split: true,
+            remote_network_addresses: false,
+            network_protocols: true,
+            append_only: false,
+            list_only_directories: true,
+            seccomp_filtering: false,
+            user_namespaces: false,
+            resource_limits: true,
+            environment_patterns: true,
+        }
     }
 }
 
 impl MacSandbox {
+    /// Get the enforceable sandbox features.
+    ///
+    /// Seatbelt is available on all supported macOS versions.
+    pub(crate) fn capabilities() -> Capabilities {
+        Capabilities {
+            path_exceptions: true,
+            network_filtering: true,
+            env_filtering: true,
+            subprocess_control: true,
+        }
+    }
+
+    /// Get the mechanism used to enforce the sandbox.
+    ///
+    /// Birdcage currently only supports [`MacBackend::Seatbelt`]. The
+    /// supported alternatives cannot restrict arbitrary processes at runtime:
+    /// the App Sandbox is configured through code signing entitlements, while
+    /// Endpoint Security requires an entitled system extension.
+    ///
+    /// Since `sandbox_init` fails if a profile cannot be applied, a
+    /// successfully spawned or locked sandbox is always enforced.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, MacBackend, Sandbox};
+    ///
+    /// let sandbox = Birdcage::new();
+    /// assert_eq!(sandbox.backend_kind(), MacBackend::Seatbelt);
+    /// ```
+    pub fn backend_kind(&self) -> MacBackend {
+        MacBackend::Seatbelt
+    }
+
+    /// Get the generated seatbelt profile.
+    ///
+    /// The profile is valid input for `sandbox-exec -p`. Since environment
+    /// filtering and resource limits are not part of the profile, they are
+    /// only applied by [`Sandbox::spawn`] and [`Sandbox::lock`].
+    ///
+    /// Fails if an exception's path cannot be resolved anymore.
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::Read("/usr/bin".into())).unwrap();
+    ///
+    /// let profile = sandbox.profile_string().unwrap();
+    /// assert!(profile.contains("(allow file-read* (subpath \"/usr/bin\"))"));
+    /// ```
+    pub fn profile_string(&self) -> Result<String> {
+        let profile = self.create_profile()?;
+        Ok(String::from_utf8_lossy(&profile).into_owned())
+    }
+
+    /// Write the generated seatbelt profile to a file.
+    ///
+    /// The file is valid input for `sandbox-exec -f`, see
+    /// [`MacSandbox::profile_string`].
+    ///
+    /// ```rust
+    /// use birdcage::{Birdcage, Exception, Sandbox};
+    ///
+    /// let mut sandbox = Birdcage::new();
+    /// sandbox.add_exception(Exception::WriteAndRead("/tmp".into())).unwrap();
+    ///
+    /// let path = std::env::temp_dir().join("birdcage-export.sb");
+    /// sandbox.export_profile(&path).unwrap();
+    ///
+    /// let profile = std::fs::read_to_string(&path).unwrap();
+    /// assert!(profile.contains("(allow file-write* (subpath \"/private/tmp\"))"));
+    /// ```
+    pub fn export_profile(&self, path: impl AsRef<Path>) -> Result<()> {
+        let profile = self.create_profile()?;
+        fs::write(path, profile)?;
+        Ok(())
+    }
+
+    /// Ensure the sandboxee's working directory is accessible.
+    fn check_current_dir(&self, sandboxee: &Command) -> Result<()> {
+        let Some(current_dir) = sandboxee.get_current_dir() else { return Ok(()) };
+        let accessible = current_dir.canonicalize().is_ok_and(|dir| {
+            let readable = self.path_exceptions.iter().any(|(path, exception)| {
+                (exception.intersects(PathException::READ | PathException::LIST)
+                    && dir.starts_with(unescape_path(path)))
+                    || (exception.intersects(PathException::EXACT | PathException::SHALLOW)
+                        && dir == unescape_path(path))
+            });
+            readable && !self.denied_paths.iter().any(|path| dir.starts_with(unescape_path(path)))
+        });
+
+        if !accessible {
+            return Err(Error::CurrentDirNotAccessible(current_dir.into()));
+        }
+
+        Ok(())
+    }
+
+    /// Apply sandbox restrictions to the current process.
+    fn restrict_current_process(&self) -> Result<()> {
+        // Remove/replace environment variables.
+        if let Some(ref custom_env) = self.custom_env {
+            crate::restrict_env_variables_with_custom(&[], &[], &[], Some(custom_env));
+        } else if !self.full_env {
+            crate::restrict_env_variables(
+                &self.env_exceptions,
+                &self.env_prefixes,
+                &self.env_patterns,
+            );
+        }
+        crate::set_env_variables(&self.set_env);
+
+        // Create the seatbelt sandbox profile.
+        let profile = self.create_profile()?;
+        let profile =
+            CString::new(profile).map_err(|_| Error::ActivationFailed("invalid profile".into()))?;
+
+        apply_profile(&profile)
+    }
+
     /// Add or modify a path's exceptions.
     fn update_path_exceptions(&mut self, path: PathBuf, exceptions: PathException) -> Result<()> {
+        if self.restrict_symlink_escape {
+            crate::check_symlink_escape(&path)?;
+        }
+
         // Canonicalize all exception paths.
         //
         // Since the macOS sandbox only cares about permissions for symlink targets, due
@@ -112,6 +589,11 @@ impl MacSandbox {
         // keeping the original paths.
         let escaped_path = escape_path(&path)?;
 
+        // Reject allowing access to explicitly denied paths.
+        if self.denied_paths.contains(&escaped_path) {
+            return Err(Error::ConflictingPath(path));
+        }
+
         let exception = self.path_exceptions.entry(escaped_path).or_insert(PathException::empty());
         exception.insert(exceptions);
 
@@ -123,16 +605,40 @@ impl MacSandbox {
         let mut profile = DEFAULT_RULE.to_vec();
 
         // Sort by component count to ensure parent paths appear before descendants.
-        let mut path_exceptions: Vec<_> = self.path_exceptions.iter().collect();
-        path_exceptions.sort_unstable_by(|a, b| a.0.len().cmp(&b.0.len()));
+        let mut path_exceptions: Vec<_> = self
+            .path_exceptions
+            .iter()
+            .map(|(path, exception)| (path.as_str(), exception))
+            .collect();
+        path_exceptions.sort_unstable_by_key(|(path, _)| path.len());
+
+        // Skip paths with the same permissions as their parent.
+        crate::remove_redundant_paths(&mut path_exceptions);
 
         for (path, exception) in path_exceptions {
+            // Exact exceptions only add access, to avoid revoking access from parents.
+            if (PathException::EXACT | PathException::SHALLOW).contains(*exception) {
+                let access = if unescape_path(path).is_dir()
+                    && !exception.contains(PathException::SHALLOW)
+                {
+                    "file-read-metadata"
+                } else {
+                    "file-read*"
+                };
+                let rule = format!("(allow {access} (literal {path}))\n");
+                profile.write_all(rule.as_bytes())?;
+                continue;
+            }
+
             // Deny all access to clear existing permission grants.
             Self::revoke_path_access(&mut profile, path)?;
 
             if exception.contains(PathException::READ) {
                 let rule = PathRule::new(RuleMode::Allow, "file-read*", path.into());
                 rule.write_to(&mut profile)?;
+            } else if exception.contains(PathException::LIST) {
+                let rule = format!("(allow file-read* (subpath {path}) (vnode-type DIRECTORY))\n");
+                profile.write_all(rule.as_bytes())?;
             }
             if exception.contains(PathException::WRITE) {
                 let rule = PathRule::new(RuleMode::Allow, "file-write*", path.into());
@@ -144,10 +650,55 @@ impl MacSandbox {
             }
         }
 
-        if self.net_exception {
+        // Revoke access to denied paths after all exceptions have been granted.
+        for path in &self.denied_paths {
+            Self::revoke_path_access(&mut profile, path)?;
+        }
+
+        for path in &self.unix_sockets {
+            let rule = format!("(allow file-read* file-write* (literal {path}))\n");
+            profile.write_all(rule.as_bytes())?;
+            let rule =
+                format!("(allow network-outbound (remote unix-socket (path-literal {path})))\n");
+            profile.write_all(rule.as_bytes())?;
+        }
+
+        if let Some((tcp, udp)) = self.network_protocols {
+            for (protocol, allowed) in [("tcp", tcp), ("udp", udp)] {
+                if allowed {
+                    let rule = format!(
+                        "(allow network* (remote {protocol} \"*:*\") (local {protocol} \"*:*\"))\n"
+                    );
+                    profile.write_all(rule.as_bytes())?;
+                }
+            }
+        } else if self.net_exception {
             profile.write_all(b"(allow network*)\n")?;
+        } else {
+            if self.net_outbound {
+                profile.write_all(b"(allow network-outbound (remote tcp \"*:*\"))\n")?;
+            }
+            if self.net_inbound {
+                profile.write_all(b"(allow network-bind (local tcp \"*:*\"))\n")?;
+                profile.write_all(b"(allow network-inbound (local tcp \"*:*\"))\n")?;
+            }
+            for rule in &self.network_rules {
+                profile.write_all(rule.as_bytes())?;
+                profile.write_all(b"\n")?;
+            }
+        }
+
+        if self.proc_self {
+            profile.write_all(b"(allow process-info* (target self))\n")?;
+        }
+
+        // Override the default rule allowing forks.
+        if self.deny_subprocesses {
+            profile.write_all(b"(deny process-fork)\n")?;
         }
 
+        event!(target: "birdcage::macos", "created seatbelt profile:\n{}", String::from_utf8_lossy(&profile));
+
         Ok(profile)
     }
 
@@ -213,10 +764,14 @@ impl RuleMode {
 
 bitflags! {
     /// Types of sandbox filesystem exceptions.
+    #[derive(Copy, Clone, PartialEq, Eq)]
     struct PathException: u8 {
         const EXECUTE = 0b0001;
         const WRITE   = 0b0010;
         const READ    = 0b0100;
+        const LIST    = 0b1000;
+        const EXACT   = 0b1_0000;
+        const SHALLOW = 0b10_0000;
     }
 }
 
@@ -225,7 +780,7 @@ fn escape_path(path: &Path) -> Result<String> {
     // Canonicalize the incoming path to support relative paths.
     // The `subpath` action only allows absolute paths.
     let canonical_path =
-        fs::canonicalize(&path).map_err(|_| Error::InvalidPath(path.to_path_buf()))?;
+        fs::canonicalize(path).map_err(|err| Error::from_path(path.to_path_buf(), &err))?;
 
     let mut path_str = canonical_path
         .into_os_string()
@@ -240,6 +795,51 @@ fn escape_path(path: &Path) -> Result<String> {
     Ok(format!("\"{path_str}\""))
 }
 
+/// Apply a seatbelt profile to the current process.
+fn apply_profile(profile: &CStr) -> Result<()> {
+    let mut error = ptr::null_mut();
+    let result = unsafe { sandbox_init(profile.as_ptr(), 0, &mut error) };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        unsafe {
+            let error_text = CStr::from_ptr(error)
+                .to_str()
+                .map_err(|_| Error::ActivationFailed("sandbox_init failed".into()))?
+                .to_owned();
+            sandbox_free_error(error);
+
+            Err(Error::ActivationFailed(error_text))
+        }
+    }
+}
+
+/// Convert a socket address to a seatbelt host: 127.0.0.1:80 -> "localhost:80"
+///
+/// Seatbelt only supports filtering by port for remote hosts, so only loopback
+/// addresses can be allowed.
+fn network_host(addr: SocketAddr) -> Result<String> {
+    if !addr.ip().is_loopback() {
+        return Err(Error::UnsupportedException(format!(
+            "network exceptions for {} are not supported on macOS, only loopback addresses can be \
+             allowed",
+            addr.ip()
+        )));
+    }
+
+    match addr.port() {
+        0 => Ok("\"localhost:*\"".into()),
+        port => Ok(format!("\"localhost:{port}\"")),
+    }
+}
+
+/// Reverse path escaping: "/tt/in\\a\"x" -> /tt/in\a"x
+fn unescape_path(path: &str) -> PathBuf {
+    let path = path.strip_prefix('"').and_then(|path| path.strip_suffix('"')).unwrap_or(path);
+    PathBuf::from(path.replace(r#"\\"#, "\\").replace(r#"\""#, "\""))
+}
+
 extern "C" {
     fn sandbox_init(profile: *const i8, flags: u64, errorbuf: *mut *mut i8) -> i32;
     fn sandbox_free_error(errorbuf: *mut i8);
@@ -5,17 +5,23 @@
 //!
 //! [STD]: https://doc.rust-lang.org/std/process/index.html
 
+use std::collections::BTreeMap;
 use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
 use std::io::{self, Read, Write};
-use std::mem;
-use std::os::fd::{AsRawFd, OwnedFd, RawFd};
-use std::os::unix::ffi::OsStrExt;
-use std::os::unix::process::ExitStatusExt;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
 pub use std::process::{ExitStatus, Output};
+use std::time::{Duration, Instant};
+use std::{env, mem, ptr};
 
 use rustix::fs::{Mode, OFlags};
 use rustix::pipe::pipe;
-use rustix::process::{Pid, Signal};
+use rustix::process::{Pid, PidfdFlags};
+
+use crate::linux::Cgroup;
 
 /// A process builder, providing fine-grained control
 /// over how a new process should be spawned.
@@ -33,11 +39,25 @@ use rustix::process::{Pid, Signal};
 pub struct Command {
     program: OsString,
     args: Vec<OsString>,
+    envs: BTreeMap<OsString, Option<OsString>>,
+    env_clear: bool,
+    current_dir: Option<PathBuf>,
+    pub(crate) uid: Option<u32>,
+    pub(crate) gid: Option<u32>,
+    pub(crate) fd_mappings: BTreeMap<RawFd, RawFd>,
+    close_fds_from: RawFd,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) core_dump_limit: Option<Option<u64>>,
+    pub(crate) kill_on_drop: bool,
     pub(crate) stdin: Stdio,
     pub(crate) stdout: Stdio,
     pub(crate) stderr: Stdio,
+    pub(crate) pre_exec_hooks: Vec<PreExecHook>,
 }
 
+/// Closure executed by the sandboxee right before `execve`.
+pub(crate) type PreExecHook = Box<dyn FnMut() -> io::Result<()> + Send + Sync>;
+
 impl Command {
     /// Constructs a new `Command` for launching the program at
     /// path `program`, with the following default configuration:
@@ -69,6 +89,17 @@ impl Command {
             stderr: Default::default(),
             stdin: Default::default(),
             args: Default::default(),
+            envs: Default::default(),
+            env_clear: Default::default(),
+            current_dir: Default::default(),
+            uid: Default::default(),
+            gid: Default::default(),
+            fd_mappings: Default::default(),
+            close_fds_from: 3,
+            timeout: Default::default(),
+            core_dump_limit: Default::default(),
+            kill_on_drop: Default::default(),
+            pre_exec_hooks: Default::default(),
         }
     }
 
@@ -147,6 +178,137 @@ impl Command {
         self
     }
 
+    /// Inserts or updates an explicit environment variable mapping.
+    ///
+    /// This method allows you to add an environment variable mapping to the
+    /// spawned process or overwrite a previously set value.
+    ///
+    /// Explicit variables are applied on top of the environment left after
+    /// the sandbox's environment exceptions, so they are passed to the child
+    /// even if they are not part of an [`Exception::CustomEnvironment`].
+    ///
+    /// [`Exception::CustomEnvironment`]: crate::Exception::CustomEnvironment
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("ls").env("PATH", "/bin");
+    /// ```
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, val: V) -> &mut Self {
+        let key = key.as_ref().to_os_string();
+        self.envs.insert(key, Some(val.as_ref().to_os_string()));
+        self
+    }
+
+    /// Removes an explicitly set environment variable and prevents inheriting
+    /// it from a parent process.
+    ///
+    /// This removes the variable even if it was allowed by the sandbox's
+    /// environment exceptions.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("ls").env_remove("PATH");
+    /// ```
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+        self.envs.insert(key.as_ref().to_os_string(), None);
+        self
+    }
+
+    /// Clears all explicitly set environment variables and prevents inheriting
+    /// any parent process environment variables.
+    ///
+    /// Variables set with [`Command::env`] after calling this method are
+    /// still passed to the child.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("ls").env_clear();
+    /// ```
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.envs.clear();
+        self.env_clear = true;
+        self
+    }
+
+    /// Sets the working directory for the child process.
+    ///
+    /// The directory is resolved inside the sandbox, so it must be accessible
+    /// through one of the sandbox's exceptions. Otherwise spawning fails with
+    /// [`Error::CurrentDirNotAccessible`].
+    ///
+    /// [`Error::CurrentDirNotAccessible`]: crate::error::Error::CurrentDirNotAccessible
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("ls").current_dir("/bin");
+    /// ```
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the child process's user ID.
+    ///
+    /// The ID is set inside the sandbox's user namespace, so it must be
+    /// mapped by `LinuxSandbox::with_user_namespace`. Without a custom
+    /// mapping, only the UID of the calling process is available. Otherwise
+    /// spawning fails with [`Error::ActivationFailed`].
+    ///
+    /// [`Error::ActivationFailed`]: crate::error::Error::ActivationFailed
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("id").uid(1000);
+    /// ```
+    pub fn uid(&mut self, id: u32) -> &mut Self {
+        self.uid = Some(id);
+        self
+    }
+
+    /// Sets the child process's group ID.
+    ///
+    /// Like [`Command::uid`], the ID must be mapped in the sandbox's user
+    /// namespace.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("id").gid(1000);
+    /// ```
+    pub fn gid(&mut self, id: u32) -> &mut Self {
+        self.gid = Some(id);
+        self
+    }
+
     /// Configuration for the child process's standard input (stdin) handle.
     ///
     /// Defaults to [`inherit`].
@@ -207,6 +369,289 @@ impl Command {
         self
     }
 
+    /// Capture the child process's standard output and error.
+    ///
+    /// This is a shorthand for setting both [`stdout`] and [`stderr`] to
+    /// [`Stdio::piped`], allowing the output to be collected with
+    /// [`Child::output`].
+    ///
+    /// [`stdout`]: Command::stdout
+    /// [`stderr`]: Command::stderr
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("ls").capture_output();
+    /// ```
+    pub fn capture_output(&mut self) -> &mut Self {
+        self.stdout = Stdio::piped();
+        self.stderr = Stdio::piped();
+        self
+    }
+
+    /// Controls whether the child process is killed when its [`Child`] is
+    /// dropped.
+    ///
+    /// If enabled, dropping a [`Child`] which was not waited on sends
+    /// `SIGKILL` to the sandbox and reaps it. This also applies when the
+    /// [`Child`] is dropped while unwinding from a panic.
+    ///
+    /// By default, the child keeps running after its [`Child`] was dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("sleep").arg("10").kill_on_drop(true);
+    /// ```
+    pub fn kill_on_drop(&mut self, kill: bool) -> &mut Self {
+        self.kill_on_drop = kill;
+        self
+    }
+
+    /// Schedules a closure to be run just before the `exec` function is
+    /// invoked.
+    ///
+    /// This behaves like [`CommandExt::pre_exec`], but the closure runs in the
+    /// sandboxee after the sandbox's own setup. All sandbox restrictions,
+    /// including resource limits and the subprocess filter, are already
+    /// applied at this point. Multiple closures run in the order they were
+    /// registered.
+    ///
+    /// On macOS, [`Command`] is [`std::process::Command`], where closures
+    /// registered before spawning run before the Seatbelt profile is applied.
+    ///
+    /// # Safety
+    ///
+    /// This closure will be run in the context of the child process after a
+    /// `fork`. This primarily means that any modifications made to memory on
+    /// behalf of this closure will **not** be visible to the parent process.
+    /// This is often a very constrained environment where normal operations
+    /// like `malloc`, accessing environment variables through
+    /// [`std::env`](mod@std::env) or acquiring a mutex are not guaranteed
+    /// to work (due to other threads perhaps still running when the `fork`
+    /// was run).
+    ///
+    /// For further details refer to the [POSIX fork() specification]
+    /// and the equivalent documentation for any targeted
+    /// platform, especially the requirements around *async-signal-safety*.
+    ///
+    /// This also means that all resources such as file descriptors and
+    /// memory-mapped regions got duplicated. It is your responsibility to make
+    /// sure that the closure does not violate library invariants by making
+    /// invalid use of these duplicates.
+    ///
+    /// Panicking in the closure is safe only if all the format arguments for
+    /// the panic message can be safely formatted; this is because although
+    /// `Command` aborts on panics before calling the pre_exec hook, panic will
+    /// still try to format the panic message.
+    ///
+    /// When this closure is run, aspects such as the stdio file descriptors
+    /// and working directory have successfully been changed, so output to
+    /// these locations might not appear where intended.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// let mut command = Command::new("sh");
+    /// unsafe {
+    ///     command.pre_exec(|| {
+    ///         libc::setsid();
+    ///         Ok(())
+    ///     })
+    /// };
+    /// ```
+    ///
+    /// [`CommandExt::pre_exec`]: std::os::unix::process::CommandExt::pre_exec
+    /// [POSIX fork() specification]:
+    ///     https://pubs.opengroup.org/onlinepubs/9699919799/functions/fork.html
+    pub unsafe fn pre_exec<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnMut() -> io::Result<()> + Send + Sync + 'static,
+    {
+        self.pre_exec_hooks.push(Box::new(f));
+        self
+    }
+
+    /// Kills the child process once `duration` has elapsed.
+    ///
+    /// The deadline is enforced by the sandbox's PID 1, starting once the
+    /// child was spawned. When the deadline is exceeded, the child is killed
+    /// with `SIGKILL`, together with all other processes in the sandbox, and
+    /// [`Child::timed_out`] returns `true` after waiting on it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("sleep").arg("60").timeout(Duration::from_secs(10));
+    /// ```
+    pub fn timeout(&mut self, duration: Duration) -> &mut Self {
+        self.timeout = Some(duration);
+        self
+    }
+
+    /// Prevents the child process from writing core dumps.
+    ///
+    /// This sets both the soft and hard `RLIMIT_CORE` of the child to zero,
+    /// so neither the child nor its subprocesses can re-enable core dumps.
+    ///
+    /// This is a defense-in-depth measure against secrets leaking into core
+    /// files. It does not prevent other processes from inspecting the child's
+    /// memory: processes in the same sandbox and processes of the same user
+    /// outside of it can still read it through `/proc/<pid>/mem` or ptrace,
+    /// unless they are restricted by the host.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("gpg").arg("--decrypt").suppress_core_dumps();
+    /// ```
+    pub fn suppress_core_dumps(&mut self) -> &mut Self {
+        self.core_dump_limit = Some(Some(0));
+        self
+    }
+
+    /// Allows the child process to write core dumps up to `max_size` bytes.
+    ///
+    /// This sets the soft `RLIMIT_CORE` of the child. Without a `max_size`,
+    /// or if `max_size` exceeds the calling process's hard limit, the hard
+    /// limit is used instead. Where core dumps end up is controlled by the
+    /// host's `/proc/sys/kernel/core_pattern`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// Command::new("./crashy").allow_core_dumps(Some(64 * 1024 * 1024));
+    /// ```
+    pub fn allow_core_dumps(&mut self, max_size: Option<u64>) -> &mut Self {
+        self.core_dump_limit = Some(max_size);
+        self
+    }
+
+    /// Passes the parent's file descriptor `parent_fd` to the child process as
+    /// `child_fd`.
+    ///
+    /// The descriptor is duplicated when the sandbox is spawned, so
+    /// `parent_fd` can be closed afterwards. Mapping a descriptor to `child_fd`
+    /// 0, 1 or 2 overrides the stdio configuration of the command, while
+    /// mapping multiple descriptors to the same `child_fd` only keeps the last
+    /// one.
+    ///
+    /// Mapped descriptors are exempt from [`Command::close_fds_from`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::os::fd::AsRawFd;
+    ///
+    /// use birdcage::process::Command;
+    ///
+    /// let file = File::open("/etc/hostname").unwrap();
+    /// Command::new("cat").arg("/dev/fd/3").fd_mapping(3, file.as_raw_fd());
+    /// ```
+    pub fn fd_mapping(&mut self, child_fd: RawFd, parent_fd: RawFd) -> &mut Self {
+        self.fd_mappings.insert(child_fd, parent_fd);
+        self
+    }
+
+    /// Passes the parent's file descriptor `fd` to the child process with the
+    /// same number.
+    ///
+    /// This is equivalent to `fd_mapping(fd, fd)`, so the descriptor is
+    /// inherited even if it has the `FD_CLOEXEC` flag set.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::os::fd::AsRawFd;
+    ///
+    /// use birdcage::process::Command;
+    ///
+    /// let file = File::open("/etc/hostname").unwrap();
+    /// let fd = file.as_raw_fd();
+    /// Command::new("cat").arg(format!("/dev/fd/{fd}")).keep_fd(fd);
+    /// ```
+    pub fn keep_fd(&mut self, fd: RawFd) -> &mut Self {
+        self.fd_mapping(fd, fd)
+    }
+
+    /// Sets the lowest file descriptor which is closed for the child process.
+    ///
+    /// All descriptors starting at `fd` are closed when the child is executed,
+    /// regardless of whether they have the `FD_CLOEXEC` flag set, unless they
+    /// were passed with [`Command::fd_mapping`] or [`Command::keep_fd`]. This
+    /// prevents the child from accidentally inheriting access to resources of
+    /// the parent, which are not covered by any sandbox exception.
+    ///
+    /// By default, all descriptors except stdin, stdout and stderr are closed.
+    /// Use [`RawFd::MAX`] to inherit all descriptors without `FD_CLOEXEC`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    ///
+    /// // Inherit FDs 3 and 4, closing all others.
+    /// Command::new("ls").close_fds_from(5);
+    /// ```
+    pub fn close_fds_from(&mut self, fd: RawFd) -> &mut Self {
+        self.close_fds_from = fd.max(0);
+        self
+    }
+
+    /// Returns the working directory for the child process.
+    ///
+    /// This returns [`None`] if the working directory will not be changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    ///
+    /// use birdcage::process::Command;
+    ///
+    /// let mut cmd = Command::new("ls");
+    /// assert_eq!(cmd.get_current_dir(), None);
+    /// cmd.current_dir("/bin");
+    /// assert_eq!(cmd.get_current_dir(), Some(Path::new("/bin")));
+    /// ```
+    pub fn get_current_dir(&self) -> Option<&Path> {
+        self.current_dir.as_deref()
+    }
+
     /// Returns the path to the program that was given to [`Command::new`].
     ///
     /// # Examples
@@ -220,6 +665,29 @@ impl Command {
     pub fn get_program(&self) -> &OsStr {
         OsStr::from_bytes(self.program.as_bytes())
     }
+
+    /// Converts this command into a [`std::process::Command`], to run it
+    /// without a sandbox.
+    ///
+    /// The program, arguments, environment, working directory, stdio and
+    /// user and group IDs are preserved. Settings without a standard library
+    /// equivalent are dropped, including fd mappings, timeouts, core dump
+    /// limits, [`Command::kill_on_drop`] and [`Command::pre_exec`] closures.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use birdcage::process::{Command, Stdio};
+    ///
+    /// let mut cmd = Command::new("echo");
+    /// cmd.arg("hello").stdout(Stdio::piped());
+    ///
+    /// let output = cmd.into_std().output().unwrap();
+    /// assert_eq!(output.stdout, b"hello\n");
+    /// ```
+    pub fn into_std(self) -> std::process::Command {
+        self.into()
+    }
 }
 
 impl From<Command> for std::process::Command {
@@ -227,6 +695,27 @@ impl From<Command> for std::process::Command {
         let mut std_command = std::process::Command::new(command.program);
         std_command.args(command.args);
 
+        if command.env_clear {
+            std_command.env_clear();
+        }
+        for (key, value) in command.envs {
+            match value {
+                Some(value) => std_command.env(key, value),
+                None => std_command.env_remove(key),
+            };
+        }
+
+        if let Some(current_dir) = command.current_dir {
+            std_command.current_dir(current_dir);
+        }
+
+        if let Some(uid) = command.uid {
+            std_command.uid(uid);
+        }
+        if let Some(gid) = command.gid {
+            std_command.gid(gid);
+        }
+
         let stdin: Option<std::process::Stdio> = command.stdin.into();
         if let Some(stdin) = stdin {
             std_command.stdin(stdin);
@@ -246,6 +735,46 @@ impl From<Command> for std::process::Command {
     }
 }
 
+/// Convert a standard library command.
+///
+/// The program, arguments, environment changes and working directory are
+/// copied. Since the standard library provides no way to read them, stdio
+/// configuration, user and group IDs, `pre_exec` closures and
+/// [`std::process::Command::env_clear`] are lost.
+///
+/// ```
+/// use std::ffi::OsStr;
+/// use std::path::Path;
+///
+/// use birdcage::process::Command;
+///
+/// let mut std_command = std::process::Command::new("ls");
+/// std_command.arg("-l").current_dir("/bin");
+///
+/// let command = Command::from(std_command);
+/// assert_eq!(command.get_program(), OsStr::new("ls"));
+/// assert_eq!(command.get_current_dir(), Some(Path::new("/bin")));
+/// ```
+impl From<std::process::Command> for Command {
+    fn from(std_command: std::process::Command) -> Self {
+        let mut command = Command::new(std_command.get_program());
+        command.args(std_command.get_args());
+
+        for (key, value) in std_command.get_envs() {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+
+        if let Some(current_dir) = std_command.get_current_dir() {
+            command.current_dir(current_dir);
+        }
+
+        command
+    }
+}
+
 /// Representation of a running or exited child process.
 ///
 /// This structure is used to represent and manage child processes. A child
@@ -253,10 +782,9 @@ impl From<Command> for std::process::Command {
 /// spawning process and can itself be constructed using a builder-style
 /// interface.
 ///
-/// There is no implementation of [`Drop`] for child processes,
-/// so if you do not ensure the `Child` has exited then it will continue to
-/// run, even after the `Child` handle to the child process has gone out of
-/// scope.
+/// Unless [`Command::kill_on_drop`] is enabled, if you do not ensure the
+/// `Child` has exited then it will continue to run, even after the `Child`
+/// handle to the child process has gone out of scope.
 ///
 /// Calling [`wait`] (or other functions that wrap around it) will make
 /// the parent process wait until the child has actually exited before
@@ -270,9 +798,10 @@ impl From<Command> for std::process::Command {
 /// global resources (for example process IDs).
 ///
 /// Birdcage does *not* automatically wait on child processes (not even if the
-/// `Child` is dropped), it is up to the application developer to do so. As a
-/// consequence, dropping `Child` handles without waiting on them first is not
-/// recommended in long-running applications.
+/// `Child` is dropped, unless it is killed on drop), it is up to the
+/// application developer to do so. As a consequence, dropping `Child` handles
+/// without waiting on them first is not recommended in long-running
+/// applications.
 ///
 /// # Examples
 ///
@@ -324,8 +853,17 @@ pub struct Child {
     /// calling functions on `child` while using `stderr`.
     pub stderr: Option<ChildStderr>,
 
+    pub(crate) temp_dirs: Vec<TempDir>,
+    pub(crate) cgroup: Option<Cgroup>,
+    pub(crate) kill_on_drop: bool,
+
     exit_signal: OwnedFd,
+    exited: bool,
+    timed_out: bool,
+    pidfd: OwnedFd,
     pid: u32,
+    sandboxee_pidfd: Option<OwnedFd>,
+    sandboxee_pid: Option<u32>,
 }
 
 impl Child {
@@ -337,9 +875,20 @@ impl Child {
         stdout: Option<OwnedFd>,
         stderr: Option<OwnedFd>,
     ) -> io::Result<Self> {
+        // Pin the process, so signals are never sent to a reused PID.
+        let pidfd = rustix::process::pidfd_open(Pid::from_raw(pid).unwrap(), PidfdFlags::empty())?;
+
         Ok(Self {
             exit_signal,
+            pidfd,
             pid: pid as u32,
+            temp_dirs: Vec::new(),
+            cgroup: None,
+            kill_on_drop: false,
+            sandboxee_pidfd: None,
+            sandboxee_pid: None,
+            exited: false,
+            timed_out: false,
             stdin: stdin.map(ChildStdin::new).transpose()?,
             stdout: stdout.map(ChildStdout::new).transpose()?,
             stderr: stderr.map(ChildStderr::new).transpose()?,
@@ -373,13 +922,18 @@ impl Child {
     /// [`ErrorKind`]: io::ErrorKind
     /// [`InvalidInput`]: io::ErrorKind::InvalidInput
     pub fn kill(&mut self) -> io::Result<()> {
-        let pid = Pid::from_raw(self.pid as i32).unwrap();
-        rustix::process::kill_process(pid, Signal::Kill)?;
-        Ok(())
+        if self.exited {
+            return Ok(());
+        }
+        send_signal(&self.pidfd, libc::SIGKILL)
     }
 
     /// Returns the OS-assigned process identifier associated with this child.
     ///
+    /// This is the PID of the sandbox's PID 1, which spawns the sandboxee and
+    /// exits with it. Use [`Child::sandboxee_id`] to get the PID of the
+    /// sandboxee itself.
+    ///
     /// # Examples
     ///
     /// Basic usage:
@@ -399,6 +953,128 @@ impl Child {
         self.pid
     }
 
+    /// Returns the OS-assigned process identifier of the sandboxee.
+    ///
+    /// Unlike [`Child::id`], this is the PID of the spawned command itself,
+    /// as seen from outside the sandbox.
+    ///
+    /// Returns `None` if the sandboxee could not be spawned, or already exited
+    /// before it was passed back to the parent.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let command = Command::new("ls");
+    /// if let Ok(child) = Birdcage::new().spawn(command) {
+    ///     println!("Sandboxee's ID is {:?}", child.sandboxee_id());
+    /// } else {
+    ///     println!("ls command didn't start");
+    /// }
+    /// ```
+    pub fn sandboxee_id(&self) -> Option<u32> {
+        self.sandboxee_pid
+    }
+
+    /// Set the PID file descriptor of the sandboxee spawned by PID 1.
+    pub(crate) fn set_sandboxee(&mut self, pidfd: OwnedFd) -> io::Result<()> {
+        // Get the PID in our namespace, which is `-1` once the process exited.
+        let fdinfo = fs::read_to_string(format!("/proc/self/fdinfo/{}", pidfd.as_raw_fd()))?;
+        let pid = fdinfo
+            .lines()
+            .find_map(|line| line.strip_prefix("Pid:"))
+            .and_then(|pid| pid.trim().parse::<i32>().ok());
+
+        self.sandboxee_pid = pid.filter(|pid| *pid > 0).map(|pid| pid as u32);
+        self.sandboxee_pidfd = Some(pidfd);
+
+        Ok(())
+    }
+
+    /// Sends a signal to the child process.
+    ///
+    /// Signals are sent to the sandboxee itself, rather than the sandbox's
+    /// PID 1. If the sandboxee could not be spawned, they are sent to PID 1
+    /// instead.
+    ///
+    /// Signals are sent through a PID file descriptor, so they can never reach
+    /// an unrelated process reusing the child's PID. Once the child was
+    /// waited on, an error of kind [`InvalidInput`] is returned instead.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use birdcage::process::{Command, Signal};
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let command = Command::new("yes");
+    /// if let Ok(child) = Birdcage::new().spawn(command) {
+    ///     child.signal(Signal::TERM).expect("command couldn't be terminated");
+    /// } else {
+    ///     println!("yes command didn't start");
+    /// }
+    /// ```
+    ///
+    /// [`InvalidInput`]: io::ErrorKind::InvalidInput
+    pub fn signal(&self, signal: Signal) -> io::Result<()> {
+        if self.exited {
+            let error = "cannot signal a child which was already waited on";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+        }
+
+        // Fall back to PID 1 once the sandboxee was reaped, which only exits
+        // after the sandboxee.
+        let signal = signal.as_raw();
+        match &self.sandboxee_pidfd {
+            Some(sandboxee_pidfd) => match send_signal(sandboxee_pidfd, signal) {
+                Err(err) if err.raw_os_error() == Some(libc::ESRCH) => {
+                    send_signal(&self.pidfd, signal)
+                },
+                result => result,
+            },
+            None => send_signal(&self.pidfd, signal),
+        }
+    }
+
+    /// Returns whether the child was killed for exceeding its
+    /// [`Command::timeout`].
+    ///
+    /// This is only `true` after the child was successfully waited on.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use birdcage::process::Command;
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut command = Command::new("sleep");
+    /// command.arg("60").timeout(Duration::from_millis(200));
+    /// let mut child = Birdcage::new().spawn(command).unwrap();
+    ///
+    /// child.wait().unwrap();
+    /// assert!(child.timed_out());
+    /// ```
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Returns whether the child is killed when it is dropped.
+    ///
+    /// See [`Command::kill_on_drop`].
+    pub fn kill_on_drop(&self) -> bool {
+        self.kill_on_drop
+    }
+
     /// Waits for the child to exit completely, returning the status that it
     /// exited with. This function will continue to have the same return value
     /// after it has been called at least once.
@@ -426,16 +1102,22 @@ impl Child {
     /// ```
     pub fn wait(&mut self) -> io::Result<ExitStatus> {
         // Wait for child process to exit.
-        unsafe {
+        let status = unsafe {
             let mut status: libc::c_int = 0;
             if libc::waitpid(self.pid as i32, &mut status, 0) == -1 {
-                Err(io::Error::last_os_error())
-            } else {
-                match self.exit_signal()? {
-                    Some(exit_signal) => Ok(exit_signal),
-                    None => Ok(ExitStatus::from_raw(status)),
-                }
+                return Err(io::Error::last_os_error());
             }
+            status
+        };
+
+        // Remove temporary directories and cgroup once the sandbox is gone.
+        self.temp_dirs.clear();
+        self.cgroup = None;
+        self.exited = true;
+
+        match self.exit_signal()? {
+            Some(exit_signal) => Ok(exit_signal),
+            None => Ok(ExitStatus::from_raw(status)),
         }
     }
 
@@ -485,6 +1167,11 @@ impl Child {
             } else if pid == 0 {
                 Ok(None)
             } else {
+                // Remove temporary directories and cgroup once the sandbox is gone.
+                self.temp_dirs.clear();
+                self.cgroup = None;
+                self.exited = true;
+
                 match self.exit_signal()? {
                     Some(exit_signal) => Ok(Some(exit_signal)),
                     None => Ok(Some(ExitStatus::from_raw(status))),
@@ -493,6 +1180,52 @@ impl Child {
         }
     }
 
+    /// Waits for the child to exit for at most `timeout`.
+    ///
+    /// If the child has exited, then `Ok(Some(status))` is returned. If the
+    /// child is still running after `timeout` has elapsed, then `Ok(None)` is
+    /// returned.
+    ///
+    /// Like [`Child::try_wait`], this function will not attempt to drop stdin.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use birdcage::process::Command;
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut cmd = Command::new("sleep");
+    /// cmd.arg("10");
+    /// let mut child = Birdcage::new().spawn(cmd).unwrap();
+    ///
+    /// if child.wait_timeout(Duration::from_secs(1)).unwrap().is_none() {
+    ///     child.kill().unwrap();
+    /// }
+    /// ```
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        let pidfd = self.pidfd.as_raw_fd();
+
+        // Wait for the PID file descriptor to become readable on exit.
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let timeout_ms = ((remaining.as_nanos() + 999_999) / 1_000_000).min(i32::MAX as u128);
+
+            let mut poll_fd = libc::pollfd { fd: pidfd, events: libc::POLLIN, revents: 0 };
+            let result = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms as libc::c_int) };
+            match result {
+                -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => continue,
+                -1 => return Err(io::Error::last_os_error()),
+                0 => return Ok(None),
+                _ => return self.try_wait(),
+            }
+        }
+    }
+
     /// Simultaneously waits for the child to exit and collect all remaining
     /// output on the stdout/stderr handles, returning an `Output`
     /// instance.
@@ -536,8 +1269,35 @@ impl Child {
         Ok(Output { status, stdout, stderr })
     }
 
+    /// Waits for the child to exit completely, returning its status and all
+    /// captured output.
+    ///
+    /// This is equivalent to [`wait_with_output`], intended for commands
+    /// configured with [`Command::capture_output`]. Both pipes are drained
+    /// concurrently, so the child cannot block on a full pipe buffer.
+    ///
+    /// [`wait_with_output`]: Child::wait_with_output
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use birdcage::process::Command;
+    /// use birdcage::{Birdcage, Sandbox};
+    ///
+    /// let mut cmd = Command::new("echo");
+    /// cmd.arg("hello").capture_output();
+    /// let child = Birdcage::new().spawn(cmd).expect("failed to execute child");
+    ///
+    /// let output = child.output().expect("failed to wait on child");
+    ///
+    /// assert_eq!(output.stdout, b"hello\n");
+    /// ```
+    pub fn output(self) -> io::Result<Output> {
+        self.wait_with_output()
+    }
+
     /// Get the child's exit signal.
-    fn exit_signal(&self) -> io::Result<Option<ExitStatus>> {
+    fn exit_signal(&mut self) -> io::Result<Option<ExitStatus>> {
         // Don't block when trying to read.
         rustix::fs::fcntl_setfl(&self.exit_signal, OFlags::NONBLOCK)?;
 
@@ -552,6 +1312,8 @@ impl Child {
         // Convert signal to exit status.
         if read == mem::size_of::<u32>() {
             let signal = u32::from_le_bytes(bytes);
+            self.timed_out = signal & EXIT_SIGNAL_TIMEOUT != 0;
+            let signal = signal & !EXIT_SIGNAL_TIMEOUT;
 
             // Exit signal are the lowest 7 bits of wstatus:
             // https://github.com/torvalds/linux/blob/259f7d5e2baf87fcbb4fabc46526c9c47fed1914/tools/include/nolibc/types.h#L110
@@ -564,6 +1326,59 @@ impl Child {
     }
 }
 
+/// Flag added to the exit signal, if the child was killed by its timeout.
+pub(crate) const EXIT_SIGNAL_TIMEOUT: u32 = 1 << 8;
+
+/// Send a signal to a process using its PID file descriptor.
+fn send_signal(pidfd: &OwnedFd, signal: libc::c_int) -> io::Result<()> {
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_pidfd_send_signal,
+            pidfd.as_raw_fd(),
+            signal,
+            ptr::null::<libc::siginfo_t>(),
+            0,
+        )
+    };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Signal which can be sent to a child process.
+///
+/// See [`Child::signal`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Signal(i32);
+
+impl Signal {
+    /// Continue a stopped process (`SIGCONT`).
+    pub const CONT: Self = Self(libc::SIGCONT);
+    /// Hangup (`SIGHUP`).
+    pub const HUP: Self = Self(libc::SIGHUP);
+    /// Interrupt (`SIGINT`).
+    pub const INT: Self = Self(libc::SIGINT);
+    /// Stop the process (`SIGSTOP`).
+    pub const STOP: Self = Self(libc::SIGSTOP);
+    /// Termination request (`SIGTERM`).
+    pub const TERM: Self = Self(libc::SIGTERM);
+    /// User-defined signal 1 (`SIGUSR1`).
+    pub const USR1: Self = Self(libc::SIGUSR1);
+    /// User-defined signal 2 (`SIGUSR2`).
+    pub const USR2: Self = Self(libc::SIGUSR2);
+
+    /// Create a signal from its raw number.
+    pub const fn from_raw(signal: i32) -> Self {
+        Self(signal)
+    }
+
+    /// Get the raw signal number.
+    pub const fn as_raw(self) -> i32 {
+        self.0
+    }
+}
+
 /// Describes what to do with a standard I/O stream for a child process when
 /// passed to the [`stdin`], [`stdout`], and [`stderr`] methods of [`Command`].
 ///
@@ -651,8 +1466,10 @@ impl Stdio {
     ///
     /// This will return the corresponding read and write FDs.
     pub(crate) fn make_pipe(&self, stdin: bool) -> io::Result<(Option<OwnedFd>, Option<OwnedFd>)> {
-        match self.ty {
+        match &self.ty {
             StdioType::Inherit | StdioType::Default => Ok((None, None)),
+            StdioType::Fd(fd) if stdin => Ok((Some(fd.try_clone()?), None)),
+            StdioType::Fd(fd) => Ok((None, Some(fd.try_clone()?))),
             StdioType::Piped => {
                 let (rx, tx) = pipe()?;
                 Ok((Some(rx), Some(tx)))
@@ -676,18 +1493,45 @@ impl From<Stdio> for Option<std::process::Stdio> {
             StdioType::Inherit => Some(std::process::Stdio::inherit()),
             StdioType::Piped => Some(std::process::Stdio::piped()),
             StdioType::Null => Some(std::process::Stdio::null()),
+            StdioType::Fd(fd) => Some(std::process::Stdio::from(fd)),
         }
     }
 }
 
+impl From<OwnedFd> for Stdio {
+    /// Use an existing file descriptor as the child's stream.
+    fn from(fd: OwnedFd) -> Self {
+        Self { ty: StdioType::Fd(fd) }
+    }
+}
+
+impl From<File> for Stdio {
+    /// Use an open file as the child's stream.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use birdcage::process::Command;
+    ///
+    /// let file = File::open("foo.txt").unwrap();
+    /// Command::new("rev").stdin(file);
+    /// ```
+    fn from(file: File) -> Self {
+        Self::from(OwnedFd::from(file))
+    }
+}
+
 /// Type of parent/child I/O coupling.
-#[derive(Default, Copy, Clone)]
+#[derive(Default)]
 pub(crate) enum StdioType {
     #[default]
     Default,
     Piped,
     Inherit,
     Null,
+    Fd(OwnedFd),
 }
 
 /// A handle to a child process's standard input (stdin).
@@ -793,7 +1637,8 @@ impl ChildReader {
     fn read(mut self) -> io::Result<(Vec<u8>, Vec<u8>)> {
         while !self.poll_fds.is_empty() {
             // Block for next FD readiness.
-            let result = unsafe { libc::poll(self.poll_fds.as_mut_ptr(), 2, -1) };
+            let result =
+                unsafe { libc::poll(self.poll_fds.as_mut_ptr(), self.poll_fds.len() as _, -1) };
             if result == -1 {
                 return Err(io::Error::last_os_error());
             }
@@ -832,3 +1677,104 @@ impl ChildReader {
         }
     }
 }
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        // Once reaped, the PID might have been reused by another process.
+        if self.kill_on_drop && !self.exited {
+            let _ = self.kill();
+            let _ = self.wait();
+        }
+    }
+}
+
+/// File descriptors passed to the sandboxee.
+pub(crate) struct FdMappings {
+    fds: Vec<(RawFd, OwnedFd)>,
+    close_from: RawFd,
+}
+
+impl FdMappings {
+    /// Duplicate the mapped file descriptors of a command.
+    ///
+    /// Duplicates are placed above all target descriptors, so applying the
+    /// mappings cannot replace a descriptor which is yet to be mapped.
+    pub(crate) fn new(command: &Command) -> io::Result<Self> {
+        let min_fd = command.fd_mappings.keys().max().map_or(0, |fd| fd + 1);
+
+        let mut fds = Vec::new();
+        for (child_fd, parent_fd) in &command.fd_mappings {
+            let fd = unsafe { libc::fcntl(*parent_fd, libc::F_DUPFD_CLOEXEC, min_fd) };
+            if fd == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            fds.push((*child_fd, unsafe { OwnedFd::from_raw_fd(fd) }));
+        }
+
+        Ok(Self { fds, close_from: command.close_fds_from })
+    }
+
+    /// Move all descriptors to their target and close all other descriptors
+    /// on exec.
+    ///
+    /// This must be called right before the sandboxee is executed.
+    pub(crate) fn apply(&self) -> io::Result<()> {
+        for (child_fd, fd) in &self.fds {
+            if unsafe { libc::dup2(fd.as_raw_fd(), *child_fd) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        // Set `FD_CLOEXEC` on all gaps between the mapped descriptors.
+        let mut start = self.close_from;
+        for (child_fd, _) in &self.fds {
+            if *child_fd > start {
+                close_range_cloexec(start as u32, *child_fd as u32 - 1)?;
+            }
+            start = start.max(*child_fd + 1);
+        }
+        close_range_cloexec(start as u32, u32::MAX)
+    }
+}
+
+/// Set `FD_CLOEXEC` on a range of file descriptors.
+fn close_range_cloexec(first: u32, last: u32) -> io::Result<()> {
+    let result =
+        unsafe { libc::syscall(libc::SYS_close_range, first, last, libc::CLOSE_RANGE_CLOEXEC) };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Temporary directory, which is removed on drop.
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create a new directory beneath the system's temporary directory.
+    pub(crate) fn new() -> io::Result<Self> {
+        let mut template = env::temp_dir().join("birdcage-XXXXXX").into_os_string().into_vec();
+        template.push(b'\0');
+
+        let result = unsafe { libc::mkdtemp(template.as_mut_ptr() as *mut libc::c_char) };
+        if result.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        template.pop();
+        Ok(Self { path: PathBuf::from(OsString::from_vec(template)) })
+    }
+
+    /// Get the directory's path.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
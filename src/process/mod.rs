@@ -5,8 +5,10 @@ mod macos;
 
 #[cfg(target_os = "linux")]
 pub use crate::process::linux::{
-    Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Output, Stdio,
+    Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Output, Signal, Stdio,
 };
+#[cfg(target_os = "linux")]
+pub(crate) use crate::process::linux::{FdMappings, TempDir, EXIT_SIGNAL_TIMEOUT};
 #[cfg(target_os = "macos")]
 pub use crate::process::macos::{
     Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Output, Stdio,
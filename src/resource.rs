@@ -0,0 +1,159 @@
+//! Process resource limits.
+
+use std::io;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Resource argument of `getrlimit` and `setrlimit`.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type Resource = libc::c_int;
+
+/// Resource limits for the sandboxee.
+///
+/// All limits are applied using `setrlimit(2)` right before the sandboxee is
+/// executed, with both the soft and hard limit set to the configured value.
+/// Since most of these limits apply per process, child processes can each
+/// consume up to the configured limit.
+///
+/// # Examples
+///
+/// ```rust
+/// use birdcage::{Birdcage, Exception, ResourceLimits, Sandbox};
+///
+/// let limits = ResourceLimits::new().max_memory_bytes(512 * 1024 * 1024).max_open_files(64);
+///
+/// let mut sandbox = Birdcage::new();
+/// sandbox.add_exception(Exception::ResourceLimits(limits)).unwrap();
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct ResourceLimits {
+    /// Maximum size of the virtual address space (`RLIMIT_AS`).
+    ///
+    /// Allocations exceeding this limit will fail, which usually aborts the
+    /// process.
+    pub max_memory_bytes: Option<u64>,
+
+    /// Maximum CPU time in seconds (`RLIMIT_CPU`).
+    ///
+    /// Once exceeded, the process is killed with `SIGKILL`.
+    pub max_cpu_seconds: Option<u64>,
+
+    /// Maximum number of open file descriptors (`RLIMIT_NOFILE`).
+    pub max_open_files: Option<u64>,
+
+    /// Maximum size of files created by the process (`RLIMIT_FSIZE`).
+    pub max_file_size_bytes: Option<u64>,
+
+    /// Maximum number of processes for the sandboxee's user (`RLIMIT_NPROC`).
+    pub max_processes: Option<u32>,
+}
+
+impl ResourceLimits {
+    /// Create resource limits without any restrictions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the size of the virtual address space.
+    pub fn max_memory_bytes(mut self, bytes: u64) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Limit CPU time in seconds.
+    pub fn max_cpu_seconds(mut self, seconds: u64) -> Self {
+        self.max_cpu_seconds = Some(seconds);
+        self
+    }
+
+    /// Limit the number of open file descriptors.
+    pub fn max_open_files(mut self, files: u64) -> Self {
+        self.max_open_files = Some(files);
+        self
+    }
+
+    /// Limit the size of created files.
+    pub fn max_file_size_bytes(mut self, bytes: u64) -> Self {
+        self.max_file_size_bytes = Some(bytes);
+        self
+    }
+
+    /// Limit the number of processes.
+    pub fn max_processes(mut self, processes: u32) -> Self {
+        self.max_processes = Some(processes);
+        self
+    }
+
+    /// Ensure the limits can be applied.
+    ///
+    /// Without privileges, hard limits can only be lowered. So this will fail
+    /// if any limit exceeds the current process's hard limit.
+    pub fn validate(&self) -> Result<()> {
+        for (name, resource, limit) in self.limits() {
+            let limit = match limit {
+                Some(limit) => limit,
+                None => continue,
+            };
+
+            let hard_limit = get_hard_limit(resource)?;
+            if hard_limit != libc::RLIM_INFINITY && limit > hard_limit {
+                return Err(Error::InvalidResourceLimit(format!(
+                    "{name} of {limit} exceeds hard limit of {hard_limit}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Combine two sets of limits, preferring limits from `other`.
+    pub(crate) fn merge(&mut self, other: Self) {
+        self.max_memory_bytes = other.max_memory_bytes.or(self.max_memory_bytes);
+        self.max_cpu_seconds = other.max_cpu_seconds.or(self.max_cpu_seconds);
+        self.max_open_files = other.max_open_files.or(self.max_open_files);
+        self.max_file_size_bytes = other.max_file_size_bytes.or(self.max_file_size_bytes);
+        self.max_processes = other.max_processes.or(self.max_processes);
+    }
+
+    /// Apply the limits to the current process.
+    ///
+    /// This is async-signal-safe, so it can be used after `fork`.
+    pub(crate) fn apply(&self) -> io::Result<()> {
+        for (_, resource, limit) in self.limits() {
+            if let Some(limit) = limit {
+                let rlimit = libc::rlimit { rlim_cur: limit, rlim_max: limit };
+                if unsafe { libc::setrlimit(resource, &rlimit) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get all limits with their resource.
+    fn limits(&self) -> [(&'static str, Resource, Option<u64>); 5] {
+        [
+            ("max_memory_bytes", libc::RLIMIT_AS, self.max_memory_bytes),
+            ("max_cpu_seconds", libc::RLIMIT_CPU, self.max_cpu_seconds),
+            ("max_open_files", libc::RLIMIT_NOFILE, self.max_open_files),
+            ("max_file_size_bytes", libc::RLIMIT_FSIZE, self.max_file_size_bytes),
+            ("max_processes", libc::RLIMIT_NPROC, self.max_processes.map(u64::from)),
+        ]
+    }
+}
+
+/// Get the current process's hard limit for a resource.
+fn get_hard_limit(resource: Resource) -> io::Result<u64> {
+    let mut rlimit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::getrlimit(resource, &mut rlimit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(rlimit.rlim_max)
+}